@@ -0,0 +1,128 @@
+//! Crash-safe file writes with rotating backups.
+//!
+//! A save writes a temp file next to the target, fsyncs it and renames it over the original,
+//! so readers see either the old or the new file, never a truncated one. Before replacing,
+//! the current file is copied to `<name>.1`, pushing older copies to `<name>.2` .. `<name>.N`.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Path of the `n`th backup of `path` (1 = most recent).
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(format!(".{}", n));
+    path.with_file_name(name)
+}
+
+/// Existing backups of `path`, most recent first.
+pub fn list_backups(path: &Path) -> Vec<PathBuf> {
+    (1..)
+        .map(|n| backup_path(path, n))
+        .take_while(|p| p.exists())
+        .collect()
+}
+
+/// Write `contents` to `path` atomically (temp file + fsync + rename).
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?
+        .to_string_lossy()
+        .into_owned();
+    let tmp = parent.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(contents)?;
+        f.sync_all()?;
+        drop(f);
+        fs::rename(&tmp, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(&parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Rotate backups (keeping at most `keep`), then write `contents` atomically.
+/// With `keep == 0` no backups are made.
+pub fn write_with_backups(path: &Path, contents: &[u8], keep: usize) -> Result<(), String> {
+    if keep > 0 && path.exists() {
+        let _ = fs::remove_file(backup_path(path, keep));
+        for n in (1..keep).rev() {
+            let from = backup_path(path, n);
+            if from.exists() {
+                fs::rename(&from, backup_path(path, n + 1))
+                    .map_err(|e| format!("Failed to rotate backup {}: {}", from.display(), e))?;
+            }
+        }
+        let current = fs::read(path)
+            .map_err(|e| format!("Failed to read {} for backup: {}", path.display(), e))?;
+        write_atomic(&backup_path(path, 1), &current)?;
+    }
+    write_atomic(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_dir;
+
+    #[test]
+    fn write_atomic_replaces_and_leaves_no_temp_files() {
+        let dir = test_dir("atomic_replace");
+        let path = dir.join("cfg.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backups_rotate_and_are_capped() {
+        let dir = test_dir("atomic_rotate");
+        let path = dir.join("cfg.json");
+        for i in 0..5 {
+            write_with_backups(&path, format!("v{}", i).as_bytes(), 3).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+        let backups = list_backups(&path);
+        assert_eq!(backups.len(), 3);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(&backups[2]).unwrap(), "v1");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn zero_backups_keeps_only_the_file() {
+        let dir = test_dir("atomic_nobackup");
+        let path = dir.join("cfg.json");
+        write_with_backups(&path, b"a", 0).unwrap();
+        write_with_backups(&path, b"b", 0).unwrap();
+        assert!(list_backups(&path).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
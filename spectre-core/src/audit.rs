@@ -0,0 +1,460 @@
+//! Append-only audit log of administrative actions (who started, stopped, changed, kicked
+//! or edited what, from where).
+//!
+//! One JSON object per line, so the file can be appended to without rewriting it and read
+//! back even if the last line was cut off.
+
+use crate::server::ServerLauncherData;
+use crate::util::unix_now;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Entries returned by [`read_entries`] when the query sets no limit.
+pub const DEFAULT_QUERY_LIMIT: usize = 200;
+
+/// Config fields whose values are never written to the audit log.
+const SECRET_FIELDS: [&str; 2] = ["password", "admin_pass"];
+const SECRET_MASK: &str = "********";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix time in seconds.
+    pub timestamp: u64,
+    /// Username, "local" for the desktop app, "ds-helper" for automatic kicks.
+    pub actor: String,
+    /// Client IP for web requests, "local" otherwise.
+    pub source: String,
+    /// e.g. "start", "stop", "save", "restore", "set_user", "delete_user", "kick".
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    /// Who was acted on besides the server: a username or a player.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+}
+
+/// One changed config field. For lists of plain values (ban lists, whitelists) `old` holds
+/// the removed items and `new` the added ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    #[serde(default)]
+    pub old: Value,
+    #[serde(default)]
+    pub new: Value,
+}
+
+impl AuditEntry {
+    pub fn new(actor: &str, source: &str, action: &str) -> Self {
+        Self {
+            timestamp: unix_now(),
+            actor: actor.to_string(),
+            source: source.to_string(),
+            action: action.to_string(),
+            server_id: None,
+            server_name: None,
+            target: None,
+            detail: None,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn server(mut self, id: &str, name: &str) -> Self {
+        self.server_id = Some(id.to_string());
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn changes(mut self, changes: Vec<FieldChange>) -> Self {
+        self.changes = changes;
+        self
+    }
+}
+
+/// Filters for [`read_entries`]; every field is optional.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AuditQuery {
+    /// Case-insensitive.
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub server_id: Option<String>,
+    /// Unix seconds, inclusive.
+    pub since: Option<u64>,
+    /// Unix seconds, inclusive.
+    pub until: Option<u64>,
+    /// Most recent N matches (default [`DEFAULT_QUERY_LIMIT`]).
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, e: &AuditEntry) -> bool {
+        self.actor
+            .as_deref()
+            .is_none_or(|a| e.actor.eq_ignore_ascii_case(a))
+            && self.action.as_deref().is_none_or(|a| e.action == a)
+            && self
+                .server_id
+                .as_deref()
+                .is_none_or(|id| e.server_id.as_deref() == Some(id))
+            && self.since.is_none_or(|t| e.timestamp >= t)
+            && self.until.is_none_or(|t| e.timestamp <= t)
+    }
+}
+
+/// Append one entry as a JSON line.
+pub fn append(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    crate::util::append_json_line(path, entry).map_err(|e| format!("Audit log: {}", e))
+}
+
+/// Matching entries, oldest first. A missing file is an empty log; unreadable lines are skipped.
+pub fn read_entries(path: &Path, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read audit log {}: {}",
+                path.display(),
+                e
+            ))
+        }
+    };
+    let mut entries: Vec<AuditEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|e| query.matches(e))
+        .collect();
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    entries.drain(..entries.len().saturating_sub(limit));
+    Ok(entries)
+}
+
+/// Field-level diff of what a save changed in servers and manager settings. Users are left
+/// out (their edits are logged separately, without password hashes), as are runtime fields.
+/// Webhook URLs and ban list subscription URLs are cut down to their host since they usually
+/// embed a token, and server passwords are masked (a change to them is still listed).
+pub fn config_changes(before: &ServerLauncherData, after: &ServerLauncherData) -> Vec<FieldChange> {
+    let view = |data: &ServerLauncherData| {
+        let mut servers = serde_json::to_value(&data.servers).unwrap_or(Value::Null);
+        if let Value::Array(list) = &mut servers {
+            for server in list.iter_mut().filter_map(Value::as_object_mut) {
+                server.remove("running");
+                server.remove("available_maps_by_style");
+            }
+        }
+        let mut manager = data.server_manager.clone();
+        manager.redact_urls();
+        serde_json::json!({
+            "server_manager": serde_json::to_value(&manager).unwrap_or(Value::Null),
+            "servers": servers,
+        })
+    };
+    let mut changes = Vec::new();
+    diff_json("", &view(before), &view(after), &mut changes);
+    // Masked after diffing, so changing one password to another is not lost.
+    for change in changes.iter_mut() {
+        let secret = SECRET_FIELDS
+            .iter()
+            .any(|f| change.path.ends_with(&format!(".{}", f)));
+        mask_secrets(&mut change.old, secret);
+        mask_secrets(&mut change.new, secret);
+    }
+    changes
+}
+
+/// Replace non-empty `SECRET_FIELDS` values anywhere in `value` (or `value` itself when
+/// `secret`) with `SECRET_MASK`.
+fn mask_secrets(value: &mut Value, secret: bool) {
+    match value {
+        Value::String(s) if secret && !s.is_empty() => *s = SECRET_MASK.to_string(),
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                mask_secrets(v, SECRET_FIELDS.contains(&key.as_str()));
+            }
+        }
+        Value::Array(list) => {
+            for v in list.iter_mut() {
+                mask_secrets(v, false);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Append the differences between `old` and `new` under `path`. Lists of objects are matched
+/// by `id` (else `name`, else `ip` for ban entries) and shown by name, so reordering is not a
+/// change.
+pub fn diff_json(path: &str, old: &Value, new: &Value, out: &mut Vec<FieldChange>) {
+    if old == new {
+        return;
+    }
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let null = Value::Null;
+                diff_json(
+                    &join(key),
+                    a.get(key).unwrap_or(&null),
+                    b.get(key).unwrap_or(&null),
+                    out,
+                );
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if is_keyed_list(a) && is_keyed_list(b) => {
+            let find =
+                |list: &[Value], key: &str| list.iter().find(|v| item_key(v) == key).cloned();
+            let mut keys: Vec<String> = Vec::new();
+            for item in a.iter().chain(b.iter()) {
+                let key = item_key(item);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            for key in keys {
+                let before = find(a, &key).unwrap_or(Value::Null);
+                let after = find(b, &key).unwrap_or(Value::Null);
+                let label = after
+                    .get("name")
+                    .or_else(|| before.get("name"))
+                    .and_then(Value::as_str)
+                    .unwrap_or(&key)
+                    .to_string();
+                let item_path = format!("{}[{}]", path, label);
+                if before.is_null() || after.is_null() {
+                    out.push(FieldChange {
+                        path: item_path,
+                        old: before,
+                        new: after,
+                    });
+                } else {
+                    diff_json(&item_path, &before, &after, out);
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b))
+            if a.iter()
+                .chain(b.iter())
+                .all(|v| !v.is_object() && !v.is_array()) =>
+        {
+            let removed: Vec<Value> = a.iter().filter(|v| !b.contains(v)).cloned().collect();
+            let added: Vec<Value> = b.iter().filter(|v| !a.contains(v)).cloned().collect();
+            if !removed.is_empty() || !added.is_empty() {
+                out.push(FieldChange {
+                    path: path.to_string(),
+                    old: Value::Array(removed),
+                    new: Value::Array(added),
+                });
+            }
+        }
+        _ => out.push(FieldChange {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn item_key(item: &Value) -> String {
+    ["id", "name", "ip"]
+        .iter()
+        .filter_map(|k| item.get(k).and_then(Value::as_str))
+        .find(|s| !s.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn is_keyed_list(list: &[Value]) -> bool {
+    !list.is_empty()
+        && list
+            .iter()
+            .all(|v| v.is_object() && !item_key(v).is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ban_subscriptions::BanListSubscription;
+    use crate::bans::BanEntry;
+    use crate::server::{Server, ServerConfig};
+    use crate::util::test_dir;
+    use std::io::Write;
+
+    #[test]
+    fn config_diff_names_the_changed_fields() {
+        let before = ServerLauncherData {
+            servers: vec![Server {
+                id: "a1".to_string(),
+                name: "Main".to_string(),
+                port: 22000,
+                configs: vec![ServerConfig {
+                    name: "Default".to_string(),
+                    ban_list: vec![BanEntry::new("1.2.3.4", ""), BanEntry::new("5.6.7.8", "")],
+                    whitelist: vec!["1.1.1.1".to_string()],
+                    ..ServerConfig::default()
+                }],
+                ..Server::default()
+            }],
+            ..ServerLauncherData::default()
+        };
+        let mut after = before.clone();
+        after.servers[0].port = 22001;
+        after.servers[0].running = true;
+        after.servers[0].configs[0].ban_list =
+            vec![BanEntry::new("5.6.7.8", ""), BanEntry::new("9.9.9.9", "tk")];
+        after.servers[0].configs[0].whitelist = vec!["2.2.2.2".to_string()];
+        after.servers.push(Server {
+            id: "b2".to_string(),
+            name: "Second".to_string(),
+            ..Server::default()
+        });
+
+        let changes = config_changes(&before, &after);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "servers[Main].configs[Default].ban_list[1.2.3.4]",
+                "servers[Main].configs[Default].ban_list[9.9.9.9]",
+                "servers[Main].configs[Default].whitelist",
+                "servers[Main].port",
+                "servers[Second]",
+            ]
+        );
+        assert!(changes[0].new.is_null());
+        assert_eq!(changes[1].new["reason"], "tk");
+        assert_eq!(changes[2].old, serde_json::json!(["1.1.1.1"]));
+        assert_eq!(changes[2].new, serde_json::json!(["2.2.2.2"]));
+        assert_eq!(changes[3].new, serde_json::json!(22001));
+        assert!(changes[4].old.is_null());
+        assert!(config_changes(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn config_diff_masks_server_passwords() {
+        let config = |password: &str, admin_pass: &str| ServerConfig {
+            name: "Default".to_string(),
+            password: password.to_string(),
+            admin_pass: admin_pass.to_string(),
+            ..ServerConfig::default()
+        };
+        let server = |name: &str, config: ServerConfig| Server {
+            id: name.to_string(),
+            name: name.to_string(),
+            configs: vec![config],
+            ..Server::default()
+        };
+        let before = ServerLauncherData {
+            servers: vec![server("Main", config("", "old-admin"))],
+            ..ServerLauncherData::default()
+        };
+        let mut after = before.clone();
+        after.servers[0].configs[0] = config("join-me", "new-admin");
+        after
+            .servers
+            .push(server("Second", config("second-pw", "second-admin")));
+
+        let changes = config_changes(&before, &after);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "servers[Main].configs[Default].admin_pass",
+                "servers[Main].configs[Default].password",
+                "servers[Second]",
+            ]
+        );
+        assert_eq!(changes[0].old, SECRET_MASK);
+        assert_eq!(changes[0].new, SECRET_MASK);
+        assert_eq!(changes[1].old, "");
+        assert_eq!(changes[1].new, SECRET_MASK);
+        let added = serde_json::to_string(&changes[2].new).unwrap();
+        assert!(!added.contains("second-pw") && !added.contains("second-admin"));
+        assert_eq!(changes[2].new["configs"][0]["password"], SECRET_MASK);
+
+        // Subscription URLs are cut down to their host, like webhook URLs.
+        let mut after = before.clone();
+        after
+            .server_manager
+            .ban_list_subscriptions
+            .push(BanListSubscription {
+                name: "Shared".to_string(),
+                source: "https://lists.example/raw/s3cret-token.txt".to_string(),
+                enabled: true,
+                format: None,
+                refresh_minutes: 60,
+            });
+        let changes = serde_json::to_string(&config_changes(&before, &after)).unwrap();
+        assert!(changes.contains("https://lists.example/…"), "{}", changes);
+        assert!(!changes.contains("s3cret"));
+    }
+
+    #[test]
+    fn entries_are_appended_and_queried() {
+        let dir = test_dir("audit");
+        let path = dir.join("audit.jsonl");
+        assert!(read_entries(&path, &AuditQuery::default())
+            .unwrap()
+            .is_empty());
+
+        let mut start = AuditEntry::new("alice", "192.168.1.5", "start").server("a1", "Main");
+        start.timestamp = 100;
+        let mut edit = AuditEntry::new("Bob", "local", "set_user").target("carol");
+        edit.timestamp = 200;
+        append(&path, &start).unwrap();
+        append(&path, &edit).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"truncated")
+            .unwrap();
+
+        let all = read_entries(&path, &AuditQuery::default()).unwrap();
+        assert_eq!(all, vec![start.clone(), edit.clone()]);
+        let by_actor = AuditQuery {
+            actor: Some("bob".to_string()),
+            ..AuditQuery::default()
+        };
+        assert_eq!(read_entries(&path, &by_actor).unwrap(), vec![edit.clone()]);
+        let by_server = AuditQuery {
+            server_id: Some("a1".to_string()),
+            until: Some(150),
+            ..AuditQuery::default()
+        };
+        assert_eq!(read_entries(&path, &by_server).unwrap(), vec![start]);
+        let latest = AuditQuery {
+            limit: Some(1),
+            ..AuditQuery::default()
+        };
+        assert_eq!(read_entries(&path, &latest).unwrap(), vec![edit]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,195 @@
+//! Server Utility users: password hashing and privilege levels.
+//!
+//! Passwords are stored as Argon2id PHC strings (`$argon2id$v=19$...`), which carry their
+//! own salt and parameters. A user with an empty hash cannot log in. Failed logins are
+//! throttled per client address by [`LoginThrottle`].
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Failed logins allowed from one address within [`FAILED_LOGIN_WINDOW`].
+pub const MAX_FAILED_LOGINS: u32 = 5;
+/// Failures are counted over this window; once the limit is hit the address is refused until
+/// the window ends.
+pub const FAILED_LOGIN_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// What a user may do. Stored as `User::privilege_level` (0, 1, 2); higher levels include
+/// everything the lower ones can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Privilege {
+    /// See server status, players and logs.
+    Viewer = 0,
+    /// Also start and stop servers.
+    Operator = 1,
+    /// Also edit configs and users.
+    Admin = 2,
+}
+
+impl Privilege {
+    /// Levels above 2 are treated as admin.
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            0 => Privilege::Viewer,
+            1 => Privilege::Operator,
+            _ => Privilege::Admin,
+        }
+    }
+
+    pub fn level(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Privilege::Viewer => "viewer",
+            Privilege::Operator => "operator",
+            Privilege::Admin => "admin",
+        }
+    }
+}
+
+/// Minimum privilege for a Server Utility IPC action. Unknown actions require admin.
+pub fn required_privilege(action: &str) -> Privilege {
+    match action {
+        "get_running"
+        | "get_players"
+        | "get_log_content"
+        | "search_player_history"
+        | "get_ban_subscriptions"
+        | "repaint" => Privilege::Viewer,
+        // Only convert lists the client already holds; saving the result still needs admin.
+        "import_list" | "export_list" => Privilege::Viewer,
+        "start" | "stop" | "start_all" | "stop_all" | "refresh_mpmaplist" | "open_log_file" => {
+            Privilege::Operator
+        }
+        _ => Privilege::Admin,
+    }
+}
+
+/// Hash `password` with Argon2id and a fresh random salt.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// Check `password` against a stored hash. Empty or malformed hashes never match.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    if hash.is_empty() {
+        return false;
+    }
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Per-address count of failed logins, so passwords cannot be brute-forced over the network.
+#[derive(Debug, Default)]
+pub struct LoginThrottle {
+    /// Address -> (failures, time of the first failure in the current window).
+    failures: HashMap<IpAddr, (u32, Instant)>,
+}
+
+impl LoginThrottle {
+    /// `Err(retry_after)` while `ip` is locked out.
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        match self.failures.get(&ip) {
+            Some(&(count, first)) if count >= MAX_FAILED_LOGINS => {
+                let elapsed = now.saturating_duration_since(first);
+                if elapsed < FAILED_LOGIN_WINDOW {
+                    Err(FAILED_LOGIN_WINDOW - elapsed)
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&mut self, ip: IpAddr, now: Instant) {
+        self.failures
+            .retain(|_, (_, first)| now.saturating_duration_since(*first) < FAILED_LOGIN_WINDOW);
+        let entry = self.failures.entry(ip).or_insert((0, now));
+        entry.0 += 1;
+    }
+
+    pub fn record_success(&mut self, ip: IpAddr) {
+        self.failures.remove(&ip);
+    }
+
+    /// `check`, then count the attempt as failed until `record_success` clears it. Done as one
+    /// step under the caller's lock, so parallel attempts cannot all pass `check` while their
+    /// passwords are still being verified.
+    pub fn begin_attempt(&mut self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        self.check(ip, now)?;
+        self.record_failure(ip, now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_salted_and_verifies() {
+        let a = hash_password("hunter2").unwrap();
+        let b = hash_password("hunter2").unwrap();
+        assert_ne!(a, b);
+        assert!(a.starts_with("$argon2id$"));
+        assert!(verify_password(&a, "hunter2"));
+        assert!(!verify_password(&a, "hunter3"));
+        assert!(!verify_password("", ""));
+        assert!(!verify_password("hunter2", "hunter2"));
+    }
+
+    #[test]
+    fn actions_are_gated_by_level() {
+        assert_eq!(required_privilege("get_players"), Privilege::Viewer);
+        assert_eq!(required_privilege("stop_all"), Privilege::Operator);
+        assert_eq!(required_privilege("save"), Privilege::Admin);
+        assert_eq!(required_privilege("no_such_action"), Privilege::Admin);
+        assert!(Privilege::from_level(1) < Privilege::Admin);
+        assert_eq!(Privilege::from_level(9), Privilege::Admin);
+    }
+
+    #[test]
+    fn repeated_failed_logins_lock_out_the_address() {
+        let mut throttle = LoginThrottle::default();
+        let ip: IpAddr = "192.168.1.20".parse().unwrap();
+        let other: IpAddr = "192.168.1.21".parse().unwrap();
+        let start = Instant::now();
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert!(throttle.check(ip, start).is_ok());
+            throttle.record_failure(ip, start);
+        }
+        let later = start + Duration::from_secs(60);
+        assert_eq!(
+            throttle.check(ip, later),
+            Err(FAILED_LOGIN_WINDOW - Duration::from_secs(60))
+        );
+        assert!(throttle.check(other, later).is_ok());
+        assert!(throttle.check(ip, start + FAILED_LOGIN_WINDOW).is_ok());
+
+        throttle.record_failure(other, start);
+        throttle.record_success(other);
+        assert!(!throttle.failures.contains_key(&other));
+
+        // Attempts still being verified already count.
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert!(throttle.begin_attempt(other, later).is_ok());
+        }
+        assert!(throttle.begin_attempt(other, later).is_err());
+        throttle.record_success(other);
+        assert!(throttle.begin_attempt(other, later).is_ok());
+    }
+}
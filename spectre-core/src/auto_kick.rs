@@ -0,0 +1,302 @@
+//! Auto-kick for high ping and inactivity, driven by the player slots the DS helper reads.
+//!
+//! With `ServerConfig::enable_auto_kick` on, `max_ping` and `max_inactivity` are enforced here
+//! instead of by the DS: ping is averaged over a sliding window so one spike does not kick
+//! anyone, and a player gets an `asay` warning `auto_kick_grace` seconds before the kick.
+//! A limit the slot layout cannot read (no confirmed offset) stays with the DS.
+
+use crate::player_slots::SlotLayout;
+use crate::server::ServerConfig;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Ping is averaged over this many seconds of samples.
+pub const PING_WINDOW_SECS: u64 = 60;
+/// New players are not checked for this long (ping settles and they need time to spawn).
+pub const JOIN_GRACE_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoKickPolicy {
+    /// Milliseconds; 0 = no ping limit.
+    pub max_ping: u32,
+    /// Seconds; 0 = no inactivity limit.
+    pub max_inactivity: u64,
+    /// Seconds from the warning to the kick.
+    pub grace: u64,
+}
+
+impl AutoKickPolicy {
+    /// The limits the helper enforces on a DS read with `layout`: only those whose value the
+    /// layout has an offset for. None when auto-kick is off or no such limit is set.
+    pub fn from_config(config: &ServerConfig, layout: &SlotLayout) -> Option<Self> {
+        let policy = Self {
+            max_ping: if layout.ping_offset.is_some() {
+                config.max_ping as u32
+            } else {
+                0
+            },
+            max_inactivity: if layout.activity_offset.is_some() {
+                config.max_inactivity as u64
+            } else {
+                0
+            },
+            grace: config.auto_kick_grace as u64,
+        };
+        (config.enable_auto_kick && (policy.max_ping > 0 || policy.max_inactivity > 0))
+            .then_some(policy)
+    }
+}
+
+/// What the helper read from one player slot; None where the layout has no confirmed offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerSample {
+    pub ping: Option<u32>,
+    /// Any per-player value that changes while the player is playing (input or movement).
+    /// Inactivity is only judged once it has been seen to change, so a value that never moves
+    /// (a wrong offset) cannot get everyone kicked.
+    pub activity: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum AutoKickReason {
+    HighPing { average: u32 },
+    Inactive { idle_secs: u64 },
+}
+
+impl AutoKickReason {
+    /// Kind label for metrics and history, like the ban list kick kinds.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AutoKickReason::HighPing { .. } => "high_ping",
+            AutoKickReason::Inactive { .. } => "inactivity",
+        }
+    }
+
+    /// Short text for the in-game warning and the kick reason.
+    pub fn describe(&self, policy: &AutoKickPolicy) -> String {
+        match self {
+            AutoKickReason::HighPing { average } => {
+                format!("ping {} ms over {} ms", average, policy.max_ping)
+            }
+            AutoKickReason::Inactive { idle_secs } => format!("inactive {} s", idle_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoKickAction {
+    Warn {
+        name: String,
+        ip: String,
+        reason: AutoKickReason,
+    },
+    Kick {
+        name: String,
+        ip: String,
+        reason: AutoKickReason,
+    },
+}
+
+#[derive(Debug)]
+struct Track {
+    joined_at: u64,
+    pings: VecDeque<(u64, u32)>,
+    activity: Option<u32>,
+    /// The activity value changed at least once, so it is live for this player.
+    activity_seen: bool,
+    last_active_at: u64,
+    warned_at: Option<u64>,
+}
+
+/// Per-server state between helper polls, keyed by (name, IP).
+#[derive(Debug, Default)]
+pub struct AutoKickTracker {
+    players: HashMap<(String, String), Track>,
+}
+
+impl AutoKickTracker {
+    /// Record a poll of the connected players and return the warnings and kicks due at `now`.
+    /// Players that left are forgotten; a player whose ping recovers or who becomes active
+    /// again after a warning is not kicked.
+    pub fn update(
+        &mut self,
+        policy: &AutoKickPolicy,
+        players: &[(String, String, PlayerSample)],
+        now: u64,
+    ) -> Vec<AutoKickAction> {
+        self.players
+            .retain(|(n, i), _| players.iter().any(|(name, ip, _)| name == n && ip == i));
+        let mut actions = Vec::new();
+        for (name, ip, sample) in players {
+            let track = self
+                .players
+                .entry((name.clone(), ip.clone()))
+                .or_insert_with(|| Track {
+                    joined_at: now,
+                    pings: VecDeque::new(),
+                    activity: sample.activity,
+                    activity_seen: false,
+                    last_active_at: now,
+                    warned_at: None,
+                });
+            if let Some(ping) = sample.ping {
+                track.pings.push_back((now, ping));
+            }
+            while track
+                .pings
+                .front()
+                .is_some_and(|(t, _)| *t + PING_WINDOW_SECS < now)
+            {
+                track.pings.pop_front();
+            }
+            if sample.activity.is_some() && sample.activity != track.activity {
+                track.activity_seen = track.activity.is_some();
+                track.activity = sample.activity;
+                track.last_active_at = now;
+            }
+
+            let Some(reason) = track.violation(policy, now) else {
+                track.warned_at = None;
+                continue;
+            };
+            let (name, ip) = (name.clone(), ip.clone());
+            match track.warned_at {
+                None => {
+                    track.warned_at = Some(now);
+                    actions.push(AutoKickAction::Warn { name, ip, reason });
+                }
+                Some(warned) if now >= warned + policy.grace => {
+                    actions.push(AutoKickAction::Kick { name, ip, reason });
+                }
+                Some(_) => {}
+            }
+        }
+        actions
+    }
+}
+
+impl Track {
+    fn violation(&self, policy: &AutoKickPolicy, now: u64) -> Option<AutoKickReason> {
+        if now < self.joined_at + JOIN_GRACE_SECS {
+            return None;
+        }
+        if policy.max_ping > 0 && !self.pings.is_empty() {
+            let average =
+                self.pings.iter().map(|(_, p)| *p as u64).sum::<u64>() / self.pings.len() as u64;
+            if average > policy.max_ping as u64 {
+                return Some(AutoKickReason::HighPing {
+                    average: average as u32,
+                });
+            }
+        }
+        let idle_secs = now - self.last_active_at;
+        if policy.max_inactivity > 0 && self.activity_seen && idle_secs >= policy.max_inactivity {
+            return Some(AutoKickReason::Inactive { idle_secs });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: AutoKickPolicy = AutoKickPolicy {
+        max_ping: 200,
+        max_inactivity: 300,
+        grace: 30,
+    };
+
+    fn poll(ping: u32, activity: u32) -> Vec<(String, String, PlayerSample)> {
+        vec![(
+            "Pilot".to_string(),
+            "1.2.3.4".to_string(),
+            PlayerSample {
+                ping: Some(ping),
+                activity: Some(activity),
+            },
+        )]
+    }
+
+    fn kinds(actions: &[AutoKickAction]) -> Vec<&'static str> {
+        actions
+            .iter()
+            .map(|a| match a {
+                AutoKickAction::Warn { .. } => "warn",
+                AutoKickAction::Kick { .. } => "kick",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn high_ping_is_averaged_then_warned_then_kicked() {
+        let mut tracker = AutoKickTracker::default();
+        // Spikes during the join grace period and a single spike afterwards are tolerated.
+        assert!(tracker.update(&POLICY, &poll(900, 1), 0).is_empty());
+        for t in (5..=120).step_by(5) {
+            let ping = if t == 100 { 600 } else { 100 };
+            assert!(
+                tracker.update(&POLICY, &poll(ping, t as u32), t).is_empty(),
+                "{}",
+                t
+            );
+        }
+        let mut actions = Vec::new();
+        for t in (125..=240).step_by(5) {
+            actions.extend(tracker.update(&POLICY, &poll(400, t as u32), t));
+        }
+        assert_eq!(kinds(&actions)[..2], ["warn", "kick"]);
+        assert!(matches!(
+            actions[0],
+            AutoKickAction::Warn { reason: AutoKickReason::HighPing { average }, .. } if average > 200
+        ));
+    }
+
+    #[test]
+    fn inactivity_warning_is_cancelled_by_activity() {
+        let mut tracker = AutoKickTracker::default();
+        assert!(tracker.update(&POLICY, &poll(50, 6), 0).is_empty());
+        // Never changed since joining: not judged.
+        assert!(tracker.update(&POLICY, &poll(50, 6), 500).is_empty());
+        assert!(tracker.update(&POLICY, &poll(50, 7), 510).is_empty());
+        assert!(tracker.update(&POLICY, &poll(50, 7), 809).is_empty());
+        assert_eq!(kinds(&tracker.update(&POLICY, &poll(50, 7), 810)), ["warn"]);
+        assert!(tracker.update(&POLICY, &poll(50, 7), 820).is_empty());
+        // Moved before the grace period ended: no kick, and the idle clock restarts.
+        assert!(tracker.update(&POLICY, &poll(50, 8), 830).is_empty());
+        assert!(tracker.update(&POLICY, &poll(50, 8), 1100).is_empty());
+        assert_eq!(
+            kinds(&tracker.update(&POLICY, &poll(50, 8), 1130)),
+            ["warn"]
+        );
+        assert_eq!(
+            kinds(&tracker.update(&POLICY, &poll(50, 8), 1160)),
+            ["kick"]
+        );
+
+        // Leaving forgets the player; a rejoin starts a new grace period.
+        assert!(tracker.update(&POLICY, &[], 1170).is_empty());
+        assert!(tracker.update(&POLICY, &poll(50, 9), 1180).is_empty());
+
+        let mut config = ServerConfig {
+            enable_auto_kick: true,
+            ..Default::default()
+        };
+        let layout = SlotLayout {
+            ping_offset: Some(188),
+            activity_offset: None,
+            ..SlotLayout::HD2DS
+        };
+        assert_eq!(AutoKickPolicy::from_config(&config, &layout), None);
+        // Inactivity cannot be read with this layout, so only the ping limit is taken over.
+        config.max_ping = 250;
+        config.max_inactivity = 300;
+        let policy = AutoKickPolicy::from_config(&config, &layout).unwrap();
+        assert_eq!((policy.max_ping, policy.max_inactivity), (250, 0));
+        assert_eq!(
+            AutoKickPolicy::from_config(&config, &SlotLayout::HD2DS),
+            None
+        );
+    }
+}
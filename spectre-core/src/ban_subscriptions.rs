@@ -0,0 +1,282 @@
+//! Ban list subscriptions: shared lists at a URL or local file path, refreshed on an interval
+//! and enforced with the forced ban list.
+//!
+//! Subscriptions are configured in `ServerManager::ban_list_subscriptions`. What was fetched is
+//! kept in a [`SubscriptionCache`] next to the config, so enforcement does not depend on the
+//! source being reachable; a failed refresh keeps the last good copy.
+
+use crate::bans::BanEntry;
+use crate::list_io::{self, ListFormat};
+use crate::notify::Connector;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Largest list accepted from a source.
+pub const MAX_LIST_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanListSubscription {
+    pub name: String,
+    /// `http://` or `https://` URL, or a local file path.
+    pub source: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// None: from the source's extension (`.csv`, `.json`), otherwise one IP per line.
+    #[serde(default)]
+    pub format: Option<ListFormat>,
+    #[serde(default = "default_refresh_minutes")]
+    pub refresh_minutes: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_refresh_minutes() -> u32 {
+    60
+}
+
+impl BanListSubscription {
+    pub fn is_url(&self) -> bool {
+        let source = self.source.trim().to_lowercase();
+        source.starts_with("http://") || source.starts_with("https://")
+    }
+
+    /// `source` as shown to users who cannot edit it and in the audit log: a URL is cut down to
+    /// its host like a webhook URL (it often carries a token), a file path is kept.
+    pub fn redacted_source(&self) -> String {
+        if self.is_url() {
+            crate::notify::redact_url(self.source.trim())
+        } else {
+            self.source.clone()
+        }
+    }
+
+    pub fn format(&self) -> ListFormat {
+        if let Some(format) = self.format {
+            return format;
+        }
+        let path = self.source.trim().to_lowercase();
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        if path.ends_with(".csv") {
+            ListFormat::Csv
+        } else if path.ends_with(".json") {
+            ListFormat::Json
+        } else {
+            ListFormat::Text
+        }
+    }
+
+    /// Download or read the list. Entries without an author are credited to the subscription.
+    pub fn fetch(&self, tls: Option<Connector>) -> Result<Vec<BanEntry>, String> {
+        let source = self.source.trim();
+        let text = if self.is_url() {
+            crate::notify::get_text(source, tls, MAX_LIST_BYTES)?
+        } else {
+            let path = Path::new(source);
+            let size = std::fs::metadata(path)
+                .map_err(|e| format!("{}: {}", source, e))?
+                .len();
+            if size > MAX_LIST_BYTES as u64 {
+                return Err(format!("{}: larger than {} bytes", source, MAX_LIST_BYTES));
+            }
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", source, e))?
+        };
+        let mut entries = list_io::import(&text, self.format())?;
+        for entry in entries.iter_mut().filter(|e| e.added_by.is_empty()) {
+            entry.added_by = self.name.clone();
+        }
+        Ok(entries)
+    }
+}
+
+/// Last fetch of one source.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceState {
+    /// Unix seconds of the last attempt.
+    pub checked_at: u64,
+    /// Unix seconds of the last successful fetch; 0 if none yet.
+    pub updated_at: u64,
+    pub entries: Vec<BanEntry>,
+    /// Why the last attempt failed; `entries` are from the last success.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Fetched lists keyed by source.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionCache {
+    #[serde(default)]
+    pub sources: BTreeMap<String, SourceState>,
+}
+
+impl SubscriptionCache {
+    /// The cache at `path`; empty if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        crate::atomic_file::write_atomic(path, json.as_bytes())
+    }
+
+    /// Enabled subscriptions never fetched or last checked `refresh_minutes` ago or more.
+    pub fn due<'a>(
+        &self,
+        subscriptions: &'a [BanListSubscription],
+        now: u64,
+    ) -> Vec<&'a BanListSubscription> {
+        subscriptions
+            .iter()
+            .filter(|s| s.enabled && !s.source.trim().is_empty())
+            .filter(|s| {
+                self.sources.get(s.source.trim()).is_none_or(|state| {
+                    now >= state.checked_at + s.refresh_minutes.max(1) as u64 * 60
+                })
+            })
+            .collect()
+    }
+
+    /// Store the outcome of a fetch. A failure keeps the entries from the last success.
+    pub fn record(&mut self, source: &str, result: Result<Vec<BanEntry>, String>, now: u64) {
+        let state = self.sources.entry(source.trim().to_string()).or_default();
+        state.checked_at = now;
+        match result {
+            Ok(entries) => {
+                state.entries = entries;
+                state.updated_at = now;
+                state.error = None;
+            }
+            Err(e) => state.error = Some(e),
+        }
+    }
+
+    /// Forget sources that are no longer subscribed.
+    pub fn retain(&mut self, subscriptions: &[BanListSubscription]) {
+        self.sources
+            .retain(|source, _| subscriptions.iter().any(|s| s.source.trim() == source));
+    }
+
+    /// Entries of the enabled subscriptions.
+    pub fn entries<'a>(
+        &'a self,
+        subscriptions: &'a [BanListSubscription],
+    ) -> impl Iterator<Item = &'a BanEntry> + 'a {
+        subscriptions
+            .iter()
+            .filter(|s| s.enabled)
+            .filter_map(|s| self.sources.get(s.source.trim()))
+            .flat_map(|state| state.entries.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_dir;
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn subscription(name: &str, source: &str) -> BanListSubscription {
+        BanListSubscription {
+            name: name.to_string(),
+            source: source.to_string(),
+            enabled: true,
+            format: None,
+            refresh_minutes: 60,
+        }
+    }
+
+    /// Local HTTP stand-in: answers one request with `status` and `body`, chunked.
+    fn stand_in(status: u16, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/lists/shared.csv", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let (first, rest) = body.split_at(body.len() / 2);
+            write!(
+                stream,
+                "HTTP/1.1 {} X\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                status,
+                first.len(),
+                first,
+                rest.len(),
+                rest
+            )
+            .unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn fetches_files_and_urls() {
+        let dir = test_dir("subscriptions");
+        let file = dir.join("community.txt");
+        std::fs::write(&file, "# shared\n1.2.3.4 :> cheating\n10.0.0.0/8\n").unwrap();
+        let entries = subscription("Community", file.to_str().unwrap())
+            .fetch(None)
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].reason, "cheating");
+        assert_eq!(entries[1].added_by, "Community");
+
+        let url = stand_in(200, "ip,reason\n5.6.7.8,aimbot\n9.9.9.9,spam\n");
+        let sub = subscription("Remote", &url);
+        assert_eq!(sub.format(), ListFormat::Csv);
+        assert!(
+            sub.redacted_source().ends_with("/…"),
+            "{}",
+            sub.redacted_source()
+        );
+        assert!(!sub.redacted_source().contains("shared.csv"));
+        let entries = sub.fetch(None).unwrap();
+        let ips: Vec<&str> = entries.iter().map(|e| e.ip.as_str()).collect();
+        assert_eq!(ips, vec!["5.6.7.8", "9.9.9.9"]);
+
+        assert!(subscription("Gone", &stand_in(404, "missing"))
+            .fetch(None)
+            .is_err());
+        assert!(
+            subscription("Missing", dir.join("none.txt").to_str().unwrap())
+                .fetch(None)
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cache_keeps_last_good_copy() {
+        let mut subs = vec![subscription("A", "a.txt"), subscription("B", "b.txt")];
+        let mut cache = SubscriptionCache::default();
+        assert_eq!(cache.due(&subs, 1000).len(), 2);
+
+        cache.record("a.txt", Ok(vec![BanEntry::new("1.1.1.1", "")]), 1000);
+        cache.record("b.txt", Err("offline".to_string()), 1000);
+        assert!(cache.due(&subs, 1000 + 59 * 60).is_empty());
+        assert_eq!(cache.due(&subs, 1000 + 60 * 60).len(), 2);
+
+        cache.record("a.txt", Err("timed out".to_string()), 5000);
+        let state = &cache.sources["a.txt"];
+        assert_eq!((state.updated_at, state.entries.len()), (1000, 1));
+        assert_eq!(state.error.as_deref(), Some("timed out"));
+        assert_eq!(cache.entries(&subs).count(), 1);
+
+        subs[0].enabled = false;
+        assert_eq!(cache.entries(&subs).count(), 0);
+        assert_eq!(cache.due(&subs, 99_999).len(), 1);
+        subs.remove(1);
+        cache.retain(&subs);
+        assert_eq!(cache.sources.len(), 1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Ban list entries: an IP pattern with a reason, who added it and when, and an optional
+//! expiry for temporary bans.
+//!
+//! Lists written before entries were structured hold strings (`"1.2.3.4 :> griefing"`); those
+//! still deserialize, as permanent bans with no author.
+
+use crate::ip_match::entry_ip;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "BanEntryRepr")]
+pub struct BanEntry {
+    /// IP, CIDR block, wildcard or range (see `ip_match`).
+    pub ip: String,
+    /// Shown in-game with the kick (cut to fit the `asay` line).
+    pub reason: String,
+    /// Username, "local" for the desktop app; empty for entries from string lists.
+    pub added_by: String,
+    /// Unix seconds; 0 when unknown.
+    pub created_at: u64,
+    /// Unix seconds; None for a permanent ban.
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BanEntryRepr {
+    Legacy(String),
+    Entry {
+        ip: String,
+        #[serde(default)]
+        reason: String,
+        #[serde(default)]
+        added_by: String,
+        #[serde(default)]
+        created_at: u64,
+        #[serde(default)]
+        expires_at: Option<u64>,
+    },
+}
+
+impl From<BanEntryRepr> for BanEntry {
+    fn from(repr: BanEntryRepr) -> Self {
+        match repr {
+            BanEntryRepr::Legacy(text) => BanEntry::from_legacy(&text),
+            BanEntryRepr::Entry {
+                ip,
+                reason,
+                added_by,
+                created_at,
+                expires_at,
+            } => BanEntry {
+                ip: ip.trim().to_string(),
+                reason,
+                added_by,
+                created_at,
+                expires_at,
+            },
+        }
+    }
+}
+
+impl BanEntry {
+    /// Permanent ban with no author or creation time.
+    pub fn new(ip: &str, reason: &str) -> Self {
+        Self {
+            ip: ip.trim().to_string(),
+            reason: reason.trim().to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Parse a string list entry: the IP, then an optional comment after `:>`.
+    pub fn from_legacy(text: &str) -> Self {
+        let reason = text
+            .find(":>")
+            .map(|pos| text[pos + 2..].trim())
+            .unwrap_or_default();
+        Self::new(entry_ip(text), reason)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+
+    /// `ip :> reason`, as string lists wrote it; used in logs.
+    pub fn label(&self) -> String {
+        if self.reason.is_empty() {
+            self.ip.clone()
+        } else {
+            format!("{} :> {}", self.ip, self.reason)
+        }
+    }
+}
+
+/// Remove expired entries from `list`, returning them.
+pub fn prune_expired(list: &mut Vec<BanEntry>, now: u64) -> Vec<BanEntry> {
+    let (expired, kept): (Vec<BanEntry>, Vec<BanEntry>) =
+        list.drain(..).partition(|e| e.is_expired(now));
+    *list = kept;
+    expired
+}
+
+/// Fill in `added_by` and `created_at` on entries of `list` that are not in `before` and have
+/// no author yet, i.e. the ones a client just added.
+pub fn stamp_new(list: &mut [BanEntry], before: &[BanEntry], actor: &str, now: u64) {
+    for entry in list.iter_mut() {
+        if entry.added_by.is_empty() && !before.contains(entry) {
+            entry.added_by = actor.to_string();
+            if entry.created_at == 0 {
+                entry.created_at = now;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_strings_and_objects() {
+        let list: Vec<BanEntry> = serde_json::from_str(
+            r#"["1.2.3.4 :> griefing", " 5.6.7.0/24 ",
+                {"ip": "9.9.9.9", "reason": "teamkilling", "added_by": "alice",
+                 "created_at": 100, "expires_at": 200}]"#,
+        )
+        .unwrap();
+        assert_eq!(list[0], BanEntry::new("1.2.3.4", "griefing"));
+        assert_eq!(list[1].ip, "5.6.7.0/24");
+        assert!(list[1].reason.is_empty());
+        assert_eq!(list[2].added_by, "alice");
+        assert_eq!(list[2].expires_at, Some(200));
+
+        let json = serde_json::to_value(&list[0]).unwrap();
+        assert_eq!(json["ip"], "1.2.3.4");
+        assert!(json["expires_at"].is_null());
+        let back: BanEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(back, list[0]);
+    }
+
+    #[test]
+    fn expired_entries_are_pruned() {
+        let mut list = vec![
+            BanEntry::new("1.1.1.1", "permanent"),
+            BanEntry {
+                expires_at: Some(150),
+                ..BanEntry::new("2.2.2.2", "1 day")
+            },
+            BanEntry {
+                expires_at: Some(300),
+                ..BanEntry::new("3.3.3.3", "later")
+            },
+        ];
+        assert!(prune_expired(&mut list, 100).is_empty());
+        let expired = prune_expired(&mut list, 150);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].ip, "2.2.2.2");
+        let ips: Vec<&str> = list.iter().map(|e| e.ip.as_str()).collect();
+        assert_eq!(ips, vec!["1.1.1.1", "3.3.3.3"]);
+    }
+
+    #[test]
+    fn only_new_entries_are_stamped() {
+        let old = BanEntry::new("1.1.1.1", "legacy");
+        let mut list = vec![old.clone(), BanEntry::new("2.2.2.2", "new")];
+        stamp_new(&mut list, std::slice::from_ref(&old), "bob", 500);
+        assert_eq!(list[0], old);
+        assert_eq!(list[1].added_by, "bob");
+        assert_eq!(list[1].created_at, 500);
+    }
+}
@@ -0,0 +1,152 @@
+//! DS console control: the commands the helper sends (asay, kickplayer) behind a [`DsConsole`]
+//! backend, so the kick and message logic ([`crate::enforce`]) runs the same against the real
+//! console window and against [`FakeConsole`] in tests.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Longest line `asay` shows.
+pub const ASAY_MAX_LEN: usize = 43;
+/// Ban reasons are cut to this in the kick notice so the name still fits.
+pub const BAN_REASON_MAX_LEN: usize = 21;
+/// Time for the kick notice to show before the player is gone.
+pub const KICK_NOTICE_DELAY: Duration = Duration::from_millis(400);
+
+pub trait DsConsole {
+    /// Run one console command.
+    fn send(&mut self, command: &str) -> Result<(), String>;
+
+    /// Console lines written since the last call. Backends that cannot read the console (the
+    /// DS console window, driven by window messages) always return none, so callers must not
+    /// wait for output to confirm a command.
+    fn read_output(&mut self) -> Vec<String>;
+
+    /// Wait before the next command, for the DS to show what was sent.
+    fn pause(&mut self, _duration: Duration) {}
+}
+
+/// Send `text` to all players, as several `asay` lines if it is longer than one.
+pub fn asay(console: &mut dyn DsConsole, text: &str) -> Result<(), String> {
+    for line in crate::messages::split_lines(text, ASAY_MAX_LEN) {
+        console.send(&format!("asay {}", line))?;
+    }
+    Ok(())
+}
+
+/// Show `notice` in-game, then kick `name`.
+pub fn kick(console: &mut dyn DsConsole, name: &str, notice: &str) -> Result<(), String> {
+    let notice: String = notice.chars().take(ASAY_MAX_LEN).collect();
+    console.send(&format!("asay {}", notice))?;
+    console.pause(KICK_NOTICE_DELAY);
+    console.send(&format!("kickplayer {}", name.trim()))
+}
+
+/// The one-line `asay` notice for a kick of `kind` (the labels of `PlayerEvent::Kicked`).
+pub fn kick_notice(player_name: &str, kind: &str, comment: Option<&str>, clan_tag: &str) -> String {
+    let name = player_name.trim();
+    let msg = match kind {
+        "whitelist" => format!("{} not in whitelist.", name),
+        "clan_tag" => format!("{} is not in clan {}.", name, clan_tag.trim()),
+        "clan_reserve" => format!("{}: slots reserved for {}.", name, clan_tag.trim()),
+        "high_ping" | "inactivity" => {
+            format!("{} kicked: {}", name, comment.unwrap_or_default())
+        }
+        _ => {
+            let reason = comment.filter(|c| !c.is_empty()).unwrap_or("(none)");
+            let reason_trim = reason.chars().take(BAN_REASON_MAX_LEN).collect::<String>();
+            format!("{} is banned. Reason: {}", name, reason_trim)
+        }
+    };
+    msg.chars().take(ASAY_MAX_LEN).collect()
+}
+
+/// The `asay` warning sent `grace` seconds before an auto-kick.
+pub fn auto_kick_warning(player_name: &str, reason: &str, grace: u64) -> String {
+    let msg = format!("{}: {}, kick in {}s", player_name.trim(), reason, grace);
+    msg.chars().take(ASAY_MAX_LEN).collect()
+}
+
+/// In-process console: records what was sent and replays queued output.
+#[derive(Debug, Default)]
+pub struct FakeConsole {
+    pub sent: Vec<String>,
+    pub paused: Duration,
+    output: VecDeque<String>,
+    /// When set, `send` fails with this error (a DS that went away).
+    pub fail_with: Option<String>,
+}
+
+impl FakeConsole {
+    /// Queue a line for the next `read_output`.
+    pub fn push_output(&mut self, line: &str) {
+        self.output.push_back(line.to_string());
+    }
+}
+
+impl DsConsole for FakeConsole {
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        if let Some(e) = &self.fail_with {
+            return Err(e.clone());
+        }
+        self.sent.push(command.to_string());
+        Ok(())
+    }
+
+    fn read_output(&mut self) -> Vec<String> {
+        self.output.drain(..).collect()
+    }
+
+    fn pause(&mut self, duration: Duration) {
+        self.paused += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kick_shows_notice_then_kicks() {
+        let mut console = FakeConsole::default();
+        let notice = kick_notice(" Griefer ", "ban", Some("teamkilling on every map"), "");
+        assert_eq!(notice, "Griefer is banned. Reason: teamkilling on e");
+        kick(&mut console, " Griefer ", &notice).unwrap();
+        assert_eq!(
+            console.sent,
+            vec![format!("asay {}", notice), "kickplayer Griefer".to_string()]
+        );
+        assert_eq!(console.paused, KICK_NOTICE_DELAY);
+
+        assert_eq!(
+            kick_notice("Pilot", "clan_reserve", None, " [ABC] "),
+            "Pilot: slots reserved for [ABC]."
+        );
+        assert_eq!(
+            auto_kick_warning("Pilot", "ping 350 ms over 250 ms", 30),
+            "Pilot: ping 350 ms over 250 ms, kick in 30s"
+        );
+
+        console.fail_with = Some("DS window closed".to_string());
+        assert!(kick(&mut console, "Other", "bye").is_err());
+        assert_eq!(console.sent.len(), 2);
+    }
+
+    #[test]
+    fn long_asay_is_split_and_output_is_drained() {
+        let mut console = FakeConsole::default();
+        asay(
+            &mut console,
+            "Next restart in 9m. Finish your round and rejoin in a minute, thanks!",
+        )
+        .unwrap();
+        assert_eq!(console.sent.len(), 2);
+        assert!(console
+            .sent
+            .iter()
+            .all(|c| c.starts_with("asay ") && c.len() <= "asay ".len() + ASAY_MAX_LEN));
+
+        console.push_output("Player Pilot kicked");
+        assert_eq!(console.read_output(), vec!["Player Pilot kicked"]);
+        assert!(console.read_output().is_empty());
+    }
+}
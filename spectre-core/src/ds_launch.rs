@@ -1,5 +1,7 @@
 //! HD2 dedicated server script builder.
 
+use crate::auto_kick::AutoKickPolicy;
+use crate::player_slots::SlotLayout;
 use crate::server::{Server, ServerConfig};
 use std::path::Path;
 use std::process::Command;
@@ -69,11 +71,19 @@ pub fn build_ds_script(server: &Server, config: &ServerConfig) -> Vec<String> {
         add(&mut lines, "fallingdmg 0".to_string());
     }
     add(&mut lines, format!("maxfreq {}", config.max_freq));
-    add(&mut lines, format!("maxping {}", config.max_ping));
-    add(
-        &mut lines,
-        format!("maxinactivity {}", config.max_inactivity),
-    );
+    // With auto-kick on, the helper enforces the limits it can read (with a warning) instead of
+    // the DS.
+    let policy = AutoKickPolicy::from_config(config, SlotLayout::for_server(server));
+    let max_ping = match policy {
+        Some(p) if p.max_ping > 0 => 0,
+        _ => config.max_ping,
+    };
+    let max_inactivity = match policy {
+        Some(p) if p.max_inactivity > 0 => 0,
+        _ => config.max_inactivity,
+    };
+    add(&mut lines, format!("maxping {}", max_ping));
+    add(&mut lines, format!("maxinactivity {}", max_inactivity));
     if config.allow_vehicles {
         add(&mut lines, "allowvehicles 1".to_string());
     } else {
@@ -249,12 +259,16 @@ mod tests {
 
     #[test]
     fn script_build_smoke() {
-        let mut server = Server::default();
-        server.port = 22000;
-        let mut config = ServerConfig::default();
-        config.session_name = "Test".to_string();
-        config.style = "Occupation".to_string();
-        config.maps = vec!["Burma1".to_string()];
+        let server = Server {
+            port: 22000,
+            ..Default::default()
+        };
+        let mut config = ServerConfig {
+            session_name: "Test".to_string(),
+            style: "Occupation".to_string(),
+            maps: vec!["Burma1".to_string()],
+            ..Default::default()
+        };
         let script = build_ds_script(&server, &config);
         assert!(!script.is_empty());
         assert!(script.iter().any(|s| s.contains("sessionname")));
@@ -263,5 +277,16 @@ mod tests {
         config.domain = "internet".to_string();
         let script_inet = build_ds_script(&server, &config);
         assert!(script_inet.iter().any(|s| s.contains("port 22000")));
+
+        config.max_ping = 250;
+        config.max_inactivity = 300;
+        let script = build_ds_script(&server, &config);
+        assert!(script.iter().any(|s| s == "maxping 250"));
+        // Ping and activity offsets are unconfirmed for HD2DS, so the DS keeps both limits
+        // even with auto-kick on.
+        config.enable_auto_kick = true;
+        let script = build_ds_script(&server, &config);
+        assert!(script.iter().any(|s| s == "maxping 250"));
+        assert!(script.iter().any(|s| s == "maxinactivity 300"));
     }
 }
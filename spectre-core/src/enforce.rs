@@ -0,0 +1,616 @@
+//! What the DS helper does on each poll of a server: report joins and leaves, and kick players
+//! on the ban lists, name bans, clan rules, whitelist and auto-kick limits.
+//!
+//! Only core types are used ([`PlayerSource`], [`DsConsole`]), so a poll runs the same against a
+//! running DS and against [`BufferSource`](crate::player_slots::BufferSource) and
+//! [`FakeConsole`](crate::ds_console::FakeConsole) in tests.
+
+use crate::auto_kick::{AutoKickAction, AutoKickPolicy, AutoKickTracker, PlayerSample};
+use crate::bans::BanEntry;
+use crate::ds_console::{self, DsConsole};
+use crate::ip_match::IpList;
+use crate::logging::Level;
+use crate::name_match::{clan_reserve_kicks, impersonates_clan, is_clan_member, NameRules};
+use crate::player_slots::PlayerSource;
+use crate::server::{ServerConfig, ServerManager};
+use std::collections::HashSet;
+
+/// What `enforce_player_lists` saw or did to a player.
+pub enum PlayerEvent<'a> {
+    Joined {
+        name: &'a str,
+        ip: &'a str,
+    },
+    Left {
+        name: &'a str,
+        ip: &'a str,
+    },
+    /// `kind` is a fixed label for metrics ("forced_ban", "ban", "name_ban", "clan_tag",
+    /// "clan_reserve", "whitelist", "high_ping", "inactivity"); `reason` is free text.
+    Kicked {
+        name: &'a str,
+        ip: &'a str,
+        kind: &'static str,
+        reason: &'a str,
+    },
+}
+
+pub type PlayerEventFn<'a> = &'a dyn Fn(PlayerEvent);
+
+/// Receives what the helper did, for the app log.
+pub type LogLineFn<'a> = &'a dyn Fn(Level, &str);
+
+/// One server as a poll sees it.
+pub struct PollContext<'a> {
+    /// Only used in log lines.
+    pub port: u16,
+    pub config: &'a ServerConfig,
+    pub manager: &'a ServerManager,
+    /// From ban list subscriptions; enforced with the forced ban list.
+    pub subscribed_bans: &'a [BanEntry],
+    /// What the previous poll returned; None on the first poll.
+    pub previous_slots: Option<&'a [(String, String)]>,
+    /// Unix seconds.
+    pub now: u64,
+    pub log_line: Option<LogLineFn<'a>>,
+    /// Joins and leaves since `previous_slots`, and each kick sent.
+    pub on_player: Option<PlayerEventFn<'a>>,
+}
+
+impl PollContext<'_> {
+    fn log(&self, level: Level, line: &str) {
+        if let Some(log) = self.log_line {
+            log(level, line);
+        }
+    }
+}
+
+/// Enforces ban/whitelist and clan rules: kicks matching players, sends asay then kickplayer
+/// through `console`, and keeps `clan_reserve` slots free for the clan by kicking the newest
+/// non-members. With auto-kick on, `auto_kick` tracks ping and activity between polls and
+/// players over a limit are warned with asay, then kicked if still over it after the grace
+/// period. Players in `kicked` are not kicked again while they stay connected; a kick is only
+/// recorded there once it was sent. Returns the connected players in join order (pass them back
+/// as `previous_slots`).
+pub fn enforce_player_lists(
+    ctx: &PollContext,
+    source: &mut dyn PlayerSource,
+    mut console: Option<&mut dyn DsConsole>,
+    kicked: &mut HashSet<String>,
+    auto_kick: Option<&mut AutoKickTracker>,
+) -> Result<Vec<(String, String)>, String> {
+    let (port, config, manager) = (ctx.port, ctx.config, ctx.manager);
+    let players = source.read_slots()?;
+    let slots: Vec<(String, String)> = players
+        .iter()
+        .map(|p| (p.name.clone(), p.ip.clone()))
+        .collect();
+
+    // Join order: players still here from the last poll first, then new arrivals.
+    let mut current_connected: Vec<(String, String)> = ctx
+        .previous_slots
+        .map(|p| p.iter().filter(|s| slots.contains(s)).cloned().collect())
+        .unwrap_or_default();
+    for slot in slots.iter().filter(|(n, _)| !n.is_empty()) {
+        if !current_connected.contains(slot) {
+            current_connected.push(slot.clone());
+        }
+    }
+
+    let previous_set: HashSet<(String, String)> = ctx
+        .previous_slots
+        .map(|s| s.iter().cloned().collect())
+        .unwrap_or_default();
+
+    for (name, ip) in &current_connected {
+        if !previous_set.contains(&(name.clone(), ip.clone())) {
+            ctx.log(
+                Level::Info,
+                &format!("player joined: \"{}\" ({})", name, ip),
+            );
+            if let Some(cb) = ctx.on_player {
+                cb(PlayerEvent::Joined { name, ip });
+            }
+        }
+    }
+    if let Some(previous) = ctx.previous_slots {
+        let current_set: HashSet<&(String, String)> = current_connected.iter().collect();
+        for slot in previous.iter().filter(|s| !current_set.contains(s)) {
+            ctx.log(
+                Level::Info,
+                &format!("player left: \"{}\" ({})", slot.0, slot.1),
+            );
+            if let Some(cb) = ctx.on_player {
+                cb(PlayerEvent::Left {
+                    name: &slot.0,
+                    ip: &slot.1,
+                });
+            }
+        }
+    }
+
+    let current_names: HashSet<String> = current_connected.iter().map(|(n, _)| n.clone()).collect();
+    kicked.retain(|name| current_names.contains(name));
+
+    let should_do_forced_ban = manager.enable_forced_ban_list
+        && !(manager.forced_ban_list.is_empty() && ctx.subscribed_bans.is_empty());
+    let should_do_ban = !config.ban_list.is_empty();
+    let should_do_whitelist = config.enable_whitelist;
+    let name_rules = NameRules::new(&config.name_ban_list);
+    let should_do_name_ban = !name_rules.is_empty();
+    let should_do_clan_tag = config.enforce_clan_tag && !config.clan_tag.trim().is_empty();
+    let should_do_clan_reserve =
+        config.clan_reserve > 0 && config.max_clients > 0 && !config.clan_tag.trim().is_empty();
+    let auto_kick_policy = AutoKickPolicy::from_config(config, source.layout());
+
+    if !should_do_forced_ban
+        && !should_do_ban
+        && !should_do_name_ban
+        && !should_do_clan_tag
+        && !should_do_clan_reserve
+        && !should_do_whitelist
+        && auto_kick_policy.is_none()
+    {
+        return Ok(current_connected);
+    }
+
+    if console.is_none() {
+        ctx.log(
+            Level::Warn,
+            &format!(
+                "port {}: Could not find DS window (kick command will not be sent)",
+                port
+            ),
+        );
+    }
+
+    if should_do_ban && !current_connected.is_empty() {
+        ctx.log(
+            Level::Debug,
+            &format!(
+                "port {} ban_list has {} entries (first: {:?})",
+                port,
+                config.ban_list.len(),
+                config.ban_list.first().map(|e| e.label())
+            ),
+        );
+    }
+
+    // Expired bans stop matching right away; the watchdog prunes them from the config.
+    let active = |e: &BanEntry| !e.is_expired(ctx.now);
+    let forced_ban_list = IpList::with(
+        manager
+            .forced_ban_list
+            .iter()
+            .chain(ctx.subscribed_bans)
+            .filter(|e| active(e)),
+        |e| &e.ip,
+    );
+    let ban_list = IpList::with(config.ban_list.iter().filter(|e| active(e)), |e| &e.ip);
+    let whitelist = IpList::new(&config.whitelist);
+
+    // (slot, name, IP, kind, reason, asay message)
+    let mut to_kick: Vec<(usize, String, String, &'static str, String, String)> = Vec::new();
+    for (slot_index, (name, ip)) in slots.iter().enumerate() {
+        if name.is_empty() {
+            continue;
+        }
+        if kicked.contains(name) {
+            continue;
+        }
+
+        let ip_trimmed = ip.trim();
+
+        let mut should_kick = false;
+        let mut kick_kind = "";
+        let mut kick_reason = String::new();
+        let mut comment: Option<&str> = None;
+
+        if should_do_forced_ban {
+            if let Some(entry) = forced_ban_list.find(ip_trimmed) {
+                should_kick = true;
+                kick_kind = "forced_ban";
+                kick_reason = format!("forced_ban list (entry: {})", entry.label());
+                comment = Some(entry.reason.trim());
+            }
+        }
+        if !should_kick && should_do_ban {
+            if let Some(entry) = ban_list.find(ip_trimmed) {
+                should_kick = true;
+                kick_kind = "ban";
+                kick_reason = format!("ban list (entry: {})", entry.label());
+                comment = Some(entry.reason.trim());
+            }
+        }
+        if !should_kick && should_do_name_ban {
+            if let Some(rule) = name_rules.find(name) {
+                should_kick = true;
+                kick_kind = "name_ban";
+                kick_reason = format!("name ban (pattern: {})", rule.pattern);
+                comment = Some(rule.reason.trim());
+            }
+        }
+        if !should_kick
+            && should_do_clan_tag
+            && impersonates_clan(name, &config.clan_tag, &config.clan_roster)
+        {
+            should_kick = true;
+            kick_kind = "clan_tag";
+            kick_reason = format!(
+                "clan tag {} without being on the roster",
+                config.clan_tag.trim()
+            );
+        }
+        if !should_kick && should_do_whitelist && !whitelist.contains(ip_trimmed) {
+            should_kick = true;
+            kick_kind = "whitelist";
+            kick_reason = "not in whitelist".to_string();
+        }
+
+        if should_kick {
+            let asay_msg = ds_console::kick_notice(name, kick_kind, comment, &config.clan_tag);
+            to_kick.push((
+                slot_index,
+                name.clone(),
+                ip_trimmed.to_string(),
+                kick_kind,
+                kick_reason,
+                asay_msg,
+            ));
+        }
+    }
+
+    if should_do_clan_reserve {
+        // Players kicked above (or on an earlier poll and not gone yet) no longer hold a slot.
+        let staying: Vec<(String, String)> = current_connected
+            .iter()
+            .filter(|(n, _)| !kicked.contains(n) && !to_kick.iter().any(|k| &k.1 == n))
+            .cloned()
+            .collect();
+        let is_member = |n: &str| is_clan_member(n, &config.clan_tag, &config.clan_roster);
+        let reserve = config.clan_reserve as usize;
+        for (name, ip) in
+            clan_reserve_kicks(&staying, config.max_clients as usize, reserve, is_member)
+        {
+            let slot_index = slots
+                .iter()
+                .position(|(n, _)| n == name)
+                .unwrap_or_default();
+            let reason = format!(
+                "{} slots reserved for clan {}",
+                reserve,
+                config.clan_tag.trim()
+            );
+            let asay_msg = ds_console::kick_notice(name, "clan_reserve", None, &config.clan_tag);
+            to_kick.push((
+                slot_index,
+                name.clone(),
+                ip.trim().to_string(),
+                "clan_reserve",
+                reason,
+                asay_msg,
+            ));
+        }
+    }
+
+    if let (Some(policy), Some(tracker)) = (auto_kick_policy, auto_kick) {
+        let samples: Vec<(String, String, PlayerSample)> = slots
+            .iter()
+            .enumerate()
+            .filter(|(_, (n, _))| !n.is_empty() && !kicked.contains(n))
+            .filter(|(_, (n, _))| !to_kick.iter().any(|k| &k.1 == n))
+            .map(|(i, (n, ip))| (n.clone(), ip.trim().to_string(), players[i].sample))
+            .collect();
+        for action in tracker.update(&policy, &samples, ctx.now) {
+            match action {
+                AutoKickAction::Warn { name, ip, reason } => {
+                    let reason = reason.describe(&policy);
+                    ctx.log(
+                        Level::Info,
+                        &format!("auto-kick warning \"{}\" ({}): {}", name, ip, reason),
+                    );
+                    if let Some(c) = console.as_deref_mut() {
+                        let warning = ds_console::auto_kick_warning(&name, &reason, policy.grace);
+                        if let Err(e) = ds_console::asay(c, &warning) {
+                            ctx.log(Level::Warn, &format!("port {}: {}", port, e));
+                        }
+                    }
+                }
+                AutoKickAction::Kick { name, ip, reason } => {
+                    let slot_index = slots
+                        .iter()
+                        .position(|(n, _)| *n == name)
+                        .unwrap_or_default();
+                    let kind = reason.kind();
+                    let reason = reason.describe(&policy);
+                    let asay_msg =
+                        ds_console::kick_notice(&name, kind, Some(&reason), &config.clan_tag);
+                    to_kick.push((
+                        slot_index,
+                        name,
+                        ip,
+                        kind,
+                        format!("auto-kick ({})", reason),
+                        asay_msg,
+                    ));
+                }
+            }
+        }
+    }
+
+    for (slot_index, name, ip, kick_kind, kick_reason, asay_msg) in to_kick {
+        ctx.log(
+            Level::Warn,
+            &format!(
+                "KICK slot {} \"{}\" ({}) reason: {}",
+                slot_index, name, ip, kick_reason
+            ),
+        );
+        if let Some(c) = console.as_deref_mut() {
+            if let Err(e) = ds_console::kick(c, &name, &asay_msg) {
+                ctx.log(Level::Warn, &format!("port {}: kick not sent: {}", port, e));
+                continue;
+            }
+            if let Some(cb) = ctx.on_player {
+                cb(PlayerEvent::Kicked {
+                    name: &name,
+                    ip: &ip,
+                    kind: kick_kind,
+                    reason: &kick_reason,
+                });
+            }
+            kicked.insert(name);
+        }
+    }
+
+    Ok(current_connected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto_kick::JOIN_GRACE_SECS;
+    use crate::ds_console::FakeConsole;
+    use crate::name_match::{NameMatchMode, NameRule};
+    use crate::player_slots::{BufferSource, SlotLayout};
+    use std::cell::RefCell;
+
+    /// An HD2DS slot table holding `players` in the first slots.
+    fn source(players: &[(&str, [u8; 4])]) -> BufferSource {
+        source_with(SlotLayout::HD2DS, players)
+    }
+
+    fn source_with(layout: SlotLayout, players: &[(&str, [u8; 4])]) -> BufferSource {
+        let mut buffer = vec![0u8; layout.table_len()];
+        for (i, (name, ip)) in players.iter().enumerate() {
+            let slot = &mut buffer[i * layout.stride..(i + 1) * layout.stride];
+            slot[layout.ip_offset..layout.ip_offset + 4].copy_from_slice(ip);
+            slot[layout.name_offset..layout.name_offset + name.len()]
+                .copy_from_slice(name.as_bytes());
+        }
+        BufferSource::new(layout, buffer)
+    }
+
+    /// One poll; returns the connected players and the events as "joined Name" etc.
+    fn poll(
+        config: &ServerConfig,
+        manager: &ServerManager,
+        players: &[(&str, [u8; 4])],
+        previous: Option<&[(String, String)]>,
+        console: &mut FakeConsole,
+        kicked: &mut HashSet<String>,
+    ) -> (Vec<(String, String)>, Vec<String>) {
+        let events = RefCell::new(Vec::new());
+        let on_player = |event: PlayerEvent| {
+            events.borrow_mut().push(match event {
+                PlayerEvent::Joined { name, .. } => format!("joined {}", name),
+                PlayerEvent::Left { name, .. } => format!("left {}", name),
+                PlayerEvent::Kicked { name, kind, .. } => format!("kicked {} {}", name, kind),
+            })
+        };
+        let ctx = PollContext {
+            port: 11001,
+            config,
+            manager,
+            subscribed_bans: &[],
+            previous_slots: previous,
+            now: 1_000,
+            log_line: None,
+            on_player: Some(&on_player),
+        };
+        let connected =
+            enforce_player_lists(&ctx, &mut source(players), Some(console), kicked, None).unwrap();
+        (connected, events.into_inner())
+    }
+
+    #[test]
+    fn kicks_once_per_list_and_reports_players() {
+        let manager = ServerManager {
+            enable_forced_ban_list: true,
+            forced_ban_list: vec![BanEntry::new("1.1.1.1", "")],
+            ..Default::default()
+        };
+        let config = ServerConfig {
+            ban_list: vec![BanEntry::new("2.2.2.2", "cheating")],
+            name_ban_list: vec![NameRule {
+                pattern: "Griefer".to_string(),
+                mode: NameMatchMode::Exact,
+                reason: String::new(),
+            }],
+            enforce_clan_tag: true,
+            clan_tag: "[ABC]".to_string(),
+            clan_roster: vec!["Real".to_string()],
+            enable_whitelist: true,
+            whitelist: vec!["6.6.6.6".to_string(), "7.7.7.7".to_string()],
+            ..Default::default()
+        };
+        let players = [
+            ("Forced", [1, 1, 1, 1]),
+            ("Banned", [2, 2, 2, 2]),
+            ("Griefer", [7, 7, 7, 7]),
+            ("[ABC]Fake", [6, 6, 6, 6]),
+            ("Stranger", [5, 5, 5, 5]),
+            ("[ABC]Real", [6, 6, 6, 6]),
+        ];
+        let mut console = FakeConsole::default();
+        let mut kicked = HashSet::new();
+        let (connected, events) =
+            poll(&config, &manager, &players, None, &mut console, &mut kicked);
+        assert_eq!(connected.len(), 6);
+        assert_eq!(
+            events[6..],
+            [
+                "kicked Forced forced_ban",
+                "kicked Banned ban",
+                "kicked Griefer name_ban",
+                "kicked [ABC]Fake clan_tag",
+                "kicked Stranger whitelist",
+            ]
+        );
+        assert!(events[..6].iter().all(|e| e.starts_with("joined ")));
+        assert!(console.sent.contains(&"kickplayer Banned".to_string()));
+        assert_eq!(kicked.len(), 5);
+
+        // Still connected: not kicked again. Gone: forgotten and reported as left.
+        let sent = console.sent.len();
+        let (_, events) = poll(
+            &config,
+            &manager,
+            &players[1..],
+            Some(&connected),
+            &mut console,
+            &mut kicked,
+        );
+        assert_eq!(events, ["left Forced"]);
+        assert_eq!(console.sent.len(), sent);
+        assert_eq!(kicked.len(), 4);
+    }
+
+    #[test]
+    fn failed_kick_is_retried_and_reserve_kicks_newest() {
+        let manager = ServerManager::default();
+        let config = ServerConfig {
+            ban_list: vec![BanEntry::new("2.2.2.2", "")],
+            ..Default::default()
+        };
+        let players = [("Banned", [2, 2, 2, 2])];
+        let mut console = FakeConsole::default();
+        console.fail_with = Some("DS console window is gone".to_string());
+        let mut kicked = HashSet::new();
+        let (connected, events) =
+            poll(&config, &manager, &players, None, &mut console, &mut kicked);
+        assert_eq!(events, ["joined Banned"]);
+        assert!(kicked.is_empty());
+        console.fail_with = None;
+        let (_, events) = poll(
+            &config,
+            &manager,
+            &players,
+            Some(&connected),
+            &mut console,
+            &mut kicked,
+        );
+        assert_eq!(events, ["kicked Banned ban"]);
+
+        // Full, with one of the 2 reserved slots held by a member: the newest non-member goes.
+        let config = ServerConfig {
+            max_clients: 4,
+            clan_reserve: 2,
+            clan_tag: "[ABC]".to_string(),
+            ..Default::default()
+        };
+        let players = [
+            ("First", [1, 0, 0, 1]),
+            ("[ABC]Member", [1, 0, 0, 2]),
+            ("Second", [1, 0, 0, 3]),
+            ("Newest", [1, 0, 0, 4]),
+        ];
+        let mut kicked = HashSet::new();
+        let (_, events) = poll(
+            &config,
+            &manager,
+            &players,
+            None,
+            &mut FakeConsole::default(),
+            &mut kicked,
+        );
+        assert_eq!(events[4..], ["kicked Newest clan_reserve"]);
+
+        // A config without slots (never saved that way) does not kick everyone.
+        let config = ServerConfig {
+            max_clients: 0,
+            ..config
+        };
+        let (_, events) = poll(
+            &config,
+            &manager,
+            &players,
+            None,
+            &mut FakeConsole::default(),
+            &mut HashSet::new(),
+        );
+        assert!(
+            events.iter().all(|e| e.starts_with("joined ")),
+            "{:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn auto_kick_uses_the_samples_of_the_source_layout() {
+        let config = ServerConfig {
+            enable_auto_kick: true,
+            max_ping: 200,
+            auto_kick_grace: 30,
+            ..Default::default()
+        };
+        let manager = ServerManager::default();
+        // Sent to the console by polls at join, after the join grace and after the warning.
+        let run = |mut source: BufferSource| {
+            let mut console = FakeConsole::default();
+            let (mut kicked, mut tracker) = (HashSet::new(), AutoKickTracker::default());
+            let mut previous: Option<Vec<(String, String)>> = None;
+            for now in [0, JOIN_GRACE_SECS, JOIN_GRACE_SECS + 30] {
+                let ctx = PollContext {
+                    port: 11001,
+                    config: &config,
+                    manager: &manager,
+                    subscribed_bans: &[],
+                    previous_slots: previous.as_deref(),
+                    now,
+                    log_line: None,
+                    on_player: None,
+                };
+                let console = Some(&mut console as &mut dyn DsConsole);
+                let connected = enforce_player_lists(
+                    &ctx,
+                    &mut source,
+                    console,
+                    &mut kicked,
+                    Some(&mut tracker),
+                )
+                .unwrap();
+                previous = Some(connected);
+            }
+            console.sent
+        };
+
+        // Not a known DS layout: ping at the end of the slot, to check the samples reach
+        // auto-kick.
+        let layout = SlotLayout {
+            name_len: 180,
+            ping_offset: Some(188),
+            ..SlotLayout::HD2DS
+        };
+        let mut source = source_with(layout, &[("Lagger", [1, 2, 3, 4])]);
+        source.buffer[188..192].copy_from_slice(&500u32.to_le_bytes());
+        let sent = run(source.clone());
+        assert_eq!(sent.len(), 3, "{:?}", sent);
+        assert!(sent[0].starts_with("asay Lagger: ping 500 ms over 200 ms"));
+        assert_eq!(sent[2], "kickplayer Lagger");
+
+        // HD2DS has no confirmed ping offset: the same table is not judged.
+        source.layout = SlotLayout::HD2DS;
+        assert!(run(source).is_empty());
+    }
+}
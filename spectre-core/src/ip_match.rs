@@ -0,0 +1,277 @@
+//! IP patterns for ban lists and whitelists.
+//!
+//! An entry is an exact IP, a CIDR block (`1.2.3.0/24`, `2001:db8::/32`), an IPv4 wildcard
+//! (`1.2.*.*`) or a range (`1.2.3.10-1.2.3.50`, or `1.2.3.10-50` for the last octet). List
+//! entries may carry a comment after `:>` (`1.2.3.4 :> griefing`). Anything else is kept as
+//! literal text and compared exactly, as before patterns were supported.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpPattern {
+    Exact(IpAddr),
+    Cidr {
+        network: IpAddr,
+        prefix: u8,
+    },
+    /// Per IPv4 octet; None is `*`.
+    Wildcard([Option<u8>; 4]),
+    /// Inclusive; both ends are the same address family.
+    Range {
+        start: IpAddr,
+        end: IpAddr,
+    },
+    Literal(String),
+}
+
+impl IpPattern {
+    /// Parse one pattern. Never fails: text that is not a valid pattern becomes a literal.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        Self::from_str(text).unwrap_or_else(|_| IpPattern::Literal(text.to_string()))
+    }
+
+    /// True if `text` parses as an IP or one of the pattern forms.
+    pub fn is_valid(text: &str) -> bool {
+        Self::from_str(text.trim()).is_ok()
+    }
+
+    pub fn matches(&self, ip: &str) -> bool {
+        let ip = ip.trim();
+        let addr = match parse_player_ip(ip) {
+            Some(addr) => addr,
+            None => return matches!(self, IpPattern::Literal(text) if text == ip),
+        };
+        match self {
+            IpPattern::Exact(exact) => *exact == addr,
+            IpPattern::Cidr { network, prefix } => in_cidr(addr, *network, *prefix),
+            IpPattern::Wildcard(octets) => match addr {
+                IpAddr::V4(v4) => octets
+                    .iter()
+                    .zip(v4.octets())
+                    .all(|(want, got)| want.is_none_or(|w| w == got)),
+                IpAddr::V6(_) => false,
+            },
+            IpPattern::Range { start, end } => match (start, end, addr) {
+                (IpAddr::V4(s), IpAddr::V4(e), IpAddr::V4(a)) => *s <= a && a <= *e,
+                (IpAddr::V6(s), IpAddr::V6(e), IpAddr::V6(a)) => *s <= a && a <= *e,
+                _ => false,
+            },
+            IpPattern::Literal(text) => text == ip,
+        }
+    }
+}
+
+impl FromStr for IpPattern {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        if let Some((addr, prefix)) = text.split_once('/') {
+            let network = parse_addr(addr)?;
+            let max = if network.is_ipv4() { 32 } else { 128 };
+            let prefix: u8 = prefix
+                .trim()
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("Invalid prefix length in \"{}\"", text))?;
+            return Ok(IpPattern::Cidr { network, prefix });
+        }
+        if text.contains('*') {
+            let parts: Vec<&str> = text.split('.').collect();
+            if parts.len() != 4 {
+                return Err(format!("Wildcard \"{}\" needs four IPv4 octets", text));
+            }
+            let mut octets = [None; 4];
+            for (slot, part) in octets.iter_mut().zip(&parts) {
+                *slot = match part.trim() {
+                    "*" => None,
+                    n => Some(
+                        n.parse::<u8>()
+                            .map_err(|_| format!("Invalid octet \"{}\" in \"{}\"", n, text))?,
+                    ),
+                };
+            }
+            return Ok(IpPattern::Wildcard(octets));
+        }
+        if let Some((start, end)) = text.split_once('-') {
+            let start = parse_addr(start)?;
+            let end = match (start, end.trim().parse::<u8>()) {
+                (IpAddr::V4(s), Ok(last)) => {
+                    let [a, b, c, _] = s.octets();
+                    IpAddr::from([a, b, c, last])
+                }
+                _ => parse_addr(end)?,
+            };
+            if start.is_ipv4() != end.is_ipv4() || start > end {
+                return Err(format!("Invalid range \"{}\"", text));
+            }
+            return Ok(IpPattern::Range { start, end });
+        }
+        parse_addr(text).map(IpPattern::Exact)
+    }
+}
+
+fn parse_addr(text: &str) -> Result<IpAddr, String> {
+    text.trim()
+        .parse::<IpAddr>()
+        .map(|a| a.to_canonical())
+        .map_err(|_| format!("Invalid IP address \"{}\"", text.trim()))
+}
+
+/// Player IPs may come with a port (`1.2.3.4:3568`).
+fn parse_player_ip(text: &str) -> Option<IpAddr> {
+    text.parse::<IpAddr>()
+        .ok()
+        .or_else(|| text.parse::<std::net::SocketAddr>().ok().map(|s| s.ip()))
+        .map(|a| a.to_canonical())
+}
+
+fn in_cidr(addr: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (addr, network) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(a) & mask == u32::from(n) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(n)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(a) & mask == u128::from(n) & mask
+        }
+        _ => false,
+    }
+}
+
+/// IP part of a list entry, without the `:>` comment.
+pub fn entry_ip(entry: &str) -> &str {
+    match entry.find(":>") {
+        Some(pos) => entry[..pos].trim(),
+        None => entry.trim(),
+    }
+}
+
+/// A ban list or whitelist parsed once for matching many players.
+#[derive(Debug, Clone)]
+pub struct IpList<'a, T = String> {
+    entries: Vec<(IpPattern, &'a T)>,
+}
+
+impl<'a> IpList<'a, String> {
+    /// String entries, with an optional `:>` comment.
+    pub fn new(entries: &'a [String]) -> Self {
+        Self::with(entries, |e| entry_ip(e))
+    }
+}
+
+impl<'a, T> IpList<'a, T> {
+    /// Entries of any type; `pattern` gives the IP pattern of each.
+    pub fn with(
+        entries: impl IntoIterator<Item = &'a T>,
+        pattern: impl Fn(&'a T) -> &'a str,
+    ) -> Self {
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|e| (IpPattern::parse(pattern(e)), e))
+                .collect(),
+        }
+    }
+
+    /// The first entry that matches `ip`.
+    pub fn find(&self, ip: &str) -> Option<&'a T> {
+        self.entries
+            .iter()
+            .find(|(pattern, _)| pattern.matches(ip))
+            .map(|(_, entry)| *entry)
+    }
+
+    pub fn contains(&self, ip: &str) -> bool {
+        self.find(ip).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_form() {
+        assert_eq!(
+            IpPattern::parse(" 1.2.3.4 "),
+            IpPattern::Exact("1.2.3.4".parse().unwrap())
+        );
+        assert_eq!(
+            IpPattern::parse("10.0.0.0/8"),
+            IpPattern::Cidr {
+                network: "10.0.0.0".parse().unwrap(),
+                prefix: 8
+            }
+        );
+        assert_eq!(
+            IpPattern::parse("1.2.*.*"),
+            IpPattern::Wildcard([Some(1), Some(2), None, None])
+        );
+        assert_eq!(
+            IpPattern::parse("1.2.3.10-50"),
+            IpPattern::Range {
+                start: "1.2.3.10".parse().unwrap(),
+                end: "1.2.3.50".parse().unwrap()
+            }
+        );
+        for bad in [
+            "1.2.3.0/33",
+            "1.2.*",
+            "1.2.3.50-1.2.3.10",
+            "1.2.3.4-::1",
+            "player",
+        ] {
+            assert!(!IpPattern::is_valid(bad), "{}", bad);
+            assert_eq!(IpPattern::parse(bad), IpPattern::Literal(bad.to_string()));
+        }
+    }
+
+    #[test]
+    fn matches_addresses() {
+        let cidr = IpPattern::parse("203.0.113.0/24");
+        assert!(cidr.matches("203.0.113.77"));
+        assert!(cidr.matches("203.0.113.77:3568"));
+        assert!(cidr.matches("::ffff:203.0.113.1"));
+        assert!(!cidr.matches("203.0.114.1"));
+        assert!(IpPattern::parse("0.0.0.0/0").matches("8.8.8.8"));
+        assert!(IpPattern::parse("2001:db8::/32").matches("2001:db8:1::5"));
+        assert!(!IpPattern::parse("2001:db8::/32").matches("1.2.3.4"));
+
+        let wildcard = IpPattern::parse("1.2.*.*");
+        assert!(wildcard.matches("1.2.250.3"));
+        assert!(!wildcard.matches("1.3.0.0"));
+
+        let range = IpPattern::parse("1.2.3.10-1.2.4.5");
+        assert!(range.matches("1.2.3.200"));
+        assert!(range.matches("1.2.4.5"));
+        assert!(!range.matches("1.2.4.6"));
+
+        assert!(IpPattern::parse("1.2.3.4").matches(" 1.2.3.4"));
+        assert!(IpPattern::parse("not-an-ip").matches("not-an-ip"));
+        assert!(!IpPattern::parse("not-an-ip").matches("1.2.3.4"));
+    }
+
+    #[test]
+    fn list_reports_matching_entry_with_comment() {
+        let entries = vec![
+            "5.6.7.8".to_string(),
+            "1.2.0.0/16 :> dynamic griefer".to_string(),
+        ];
+        let list = IpList::new(&entries);
+        assert_eq!(
+            list.find("1.2.99.1").map(String::as_str),
+            Some("1.2.0.0/16 :> dynamic griefer")
+        );
+        assert!(list.contains("5.6.7.8"));
+        assert!(!list.contains("9.9.9.9"));
+        assert!(IpList::new(&[]).is_empty());
+    }
+}
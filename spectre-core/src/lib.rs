@@ -1,7 +1,25 @@
+pub mod atomic_file;
+pub mod audit;
+pub mod auth;
+pub mod auto_kick;
+pub mod ban_subscriptions;
+pub mod bans;
+pub mod ds_console;
 pub mod ds_launch;
+pub mod enforce;
 pub mod dta_unpacker;
 pub mod gamedata;
 pub mod inventory;
+pub mod ip_match;
 pub mod items;
+pub mod list_io;
+pub mod logging;
+pub mod messages;
+pub mod metrics;
 pub mod mpmaplist;
+pub mod name_match;
+pub mod notify;
+pub mod player_history;
+pub mod player_slots;
 pub mod server;
+pub mod util;
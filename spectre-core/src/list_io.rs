@@ -0,0 +1,421 @@
+//! Import and export of the ban lists and whitelists as CSV, JSON or plain text, so
+//! communities can share lists between servers.
+//!
+//! - CSV: `ip,reason,added_by,created_at,expires_at`, with a header row. Imports without a
+//!   header (no column named `ip`) read the columns in that order; only `ip` is required.
+//! - JSON: an array of ban entries; strings (`"1.2.3.4 :> griefing"`) are accepted too.
+//! - Text: one IP or pattern per line. Imports skip blank lines and `#` comments and keep a
+//!   `:>` comment as the reason.
+//!
+//! Imports merge into the existing list: entries whose IP is already listed are skipped.
+
+use crate::bans::BanEntry;
+use crate::ip_match::entry_ip;
+use crate::server::{ServerConfig, ServerLauncherData};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+const CSV_COLUMNS: [&str; 5] = ["ip", "reason", "added_by", "created_at", "expires_at"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListFormat {
+    Csv,
+    #[default]
+    Json,
+    #[serde(alias = "txt")]
+    Text,
+}
+
+impl ListFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ListFormat::Csv => "csv",
+            ListFormat::Json => "json",
+            ListFormat::Text => "txt",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ListFormat::Csv => "text/csv; charset=utf-8",
+            ListFormat::Json => "application/json",
+            ListFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+impl FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(ListFormat::Csv),
+            "json" => Ok(ListFormat::Json),
+            "text" | "txt" => Ok(ListFormat::Text),
+            other => Err(format!(
+                "Unknown list format \"{}\" (csv, json or text)",
+                other
+            )),
+        }
+    }
+}
+
+/// The lists that can be imported and exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListName {
+    /// Per server config.
+    BanList,
+    /// Applies to every server.
+    ForcedBanList,
+    /// Per server config; entries are kept as strings.
+    Whitelist,
+}
+
+impl ListName {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListName::BanList => "ban_list",
+            ListName::ForcedBanList => "forced_ban_list",
+            ListName::Whitelist => "whitelist",
+        }
+    }
+}
+
+impl FromStr for ListName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "ban_list" => Ok(ListName::BanList),
+            "forced_ban_list" => Ok(ListName::ForcedBanList),
+            "whitelist" => Ok(ListName::Whitelist),
+            other => Err(format!(
+                "Unknown list \"{}\" (ban_list, forced_ban_list or whitelist)",
+                other
+            )),
+        }
+    }
+}
+
+/// Write `entries` in `format`.
+pub fn export(entries: &[BanEntry], format: ListFormat) -> String {
+    match format {
+        ListFormat::Csv => {
+            let mut out = CSV_COLUMNS.join(",") + "\n";
+            for e in entries {
+                let row = [
+                    e.ip.clone(),
+                    e.reason.clone(),
+                    e.added_by.clone(),
+                    e.created_at.to_string(),
+                    e.expires_at.map(|t| t.to_string()).unwrap_or_default(),
+                ];
+                let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            out
+        }
+        ListFormat::Json => {
+            serde_json::to_string_pretty(entries).unwrap_or_else(|_| "[]".into()) + "\n"
+        }
+        ListFormat::Text => entries.iter().map(|e| format!("{}\n", e.ip)).collect(),
+    }
+}
+
+/// Parse `text` in `format`. Entries without an IP are dropped.
+pub fn import(text: &str, format: ListFormat) -> Result<Vec<BanEntry>, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let entries = match format {
+        ListFormat::Csv => import_csv(text)?,
+        ListFormat::Json => serde_json::from_str::<Vec<BanEntry>>(text)
+            .map_err(|e| format!("Invalid JSON list: {}", e))?,
+        ListFormat::Text => text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(BanEntry::from_legacy)
+            .collect(),
+    };
+    Ok(entries.into_iter().filter(|e| !e.ip.is_empty()).collect())
+}
+
+fn import_csv(text: &str) -> Result<Vec<BanEntry>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let Some(first) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let is_header = first.iter().any(|f| f.trim().eq_ignore_ascii_case("ip"));
+    let columns: Vec<Option<usize>> = if is_header {
+        CSV_COLUMNS
+            .iter()
+            .map(|name| {
+                first
+                    .iter()
+                    .position(|f| f.trim().eq_ignore_ascii_case(name))
+            })
+            .collect()
+    } else {
+        (0..CSV_COLUMNS.len()).map(Some).collect()
+    };
+    let data_rows = if is_header { None } else { Some(first) };
+    let mut entries = Vec::new();
+    for (n, row) in data_rows.into_iter().chain(rows).enumerate() {
+        let field = |col: usize| {
+            columns[col]
+                .and_then(|i| row.get(i))
+                .map(|f| f.trim())
+                .unwrap_or_default()
+        };
+        let number = |col: usize| -> Result<Option<u64>, String> {
+            match field(col) {
+                "" => Ok(None),
+                v => v.parse().map(Some).map_err(|_| {
+                    format!(
+                        "CSV row {}: {} \"{}\" is not a Unix time",
+                        n + 1 + is_header as usize,
+                        CSV_COLUMNS[col],
+                        v
+                    )
+                }),
+            }
+        };
+        entries.push(BanEntry {
+            ip: entry_ip(field(0)).to_string(),
+            reason: field(1).to_string(),
+            added_by: field(2).to_string(),
+            created_at: number(3)?.unwrap_or(0),
+            expires_at: number(4)?,
+        });
+    }
+    Ok(entries)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Rows of RFC 4180 CSV; quoted fields may hold commas, quotes and newlines. Blank lines are
+/// skipped.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.trim().is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.trim().is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Append the entries of `imported` whose IP is not in `list` yet; returns how many were added.
+pub fn merge<T>(
+    list: &mut Vec<T>,
+    imported: impl IntoIterator<Item = T>,
+    ip: impl Fn(&T) -> &str,
+) -> usize {
+    let before = list.len();
+    for entry in imported {
+        let key = ip(&entry).trim().to_string();
+        if !list.iter().any(|e| ip(e).trim() == key) {
+            list.push(entry);
+        }
+    }
+    list.len() - before
+}
+
+/// Merge into a whitelist, which holds `ip :> comment` strings.
+pub fn merge_whitelist(list: &mut Vec<String>, imported: Vec<BanEntry>) -> usize {
+    merge(list, imported.iter().map(BanEntry::label), |e| entry_ip(e))
+}
+
+fn config<'a>(
+    data: &'a ServerLauncherData,
+    server_id: Option<&str>,
+    config: Option<&str>,
+) -> Result<&'a ServerConfig, String> {
+    let server_id = server_id.ok_or("server_id is required for per-server lists")?;
+    let server = data
+        .server_by_id(server_id)
+        .ok_or_else(|| format!("No server {}", server_id))?;
+    let name = config.unwrap_or(&server.current_config);
+    server
+        .configs
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("No config \"{}\" on {}", name, server.name))
+}
+
+fn config_mut<'a>(
+    data: &'a mut ServerLauncherData,
+    server_id: Option<&str>,
+    config_name: Option<&str>,
+) -> Result<&'a mut ServerConfig, String> {
+    let name = config(data, server_id, config_name)?.name.clone();
+    let server_id = server_id.unwrap_or_default();
+    data.servers
+        .iter_mut()
+        .find(|s| s.id == server_id)
+        .and_then(|s| s.configs.iter_mut().find(|c| c.name == name))
+        .ok_or_else(|| format!("No config \"{}\"", name))
+}
+
+/// Entries of one list. `server_id` is needed for per-server lists; `config_name` defaults to
+/// the server's current config.
+pub fn list_entries(
+    data: &ServerLauncherData,
+    list: ListName,
+    server_id: Option<&str>,
+    config_name: Option<&str>,
+) -> Result<Vec<BanEntry>, String> {
+    Ok(match list {
+        ListName::ForcedBanList => data.server_manager.forced_ban_list.clone(),
+        ListName::BanList => config(data, server_id, config_name)?.ban_list.clone(),
+        ListName::Whitelist => config(data, server_id, config_name)?
+            .whitelist
+            .iter()
+            .map(|e| BanEntry::from_legacy(e))
+            .collect(),
+    })
+}
+
+/// Merge `imported` into one list (see [`list_entries`]); returns how many were added.
+pub fn import_into(
+    data: &mut ServerLauncherData,
+    list: ListName,
+    server_id: Option<&str>,
+    config_name: Option<&str>,
+    imported: Vec<BanEntry>,
+) -> Result<usize, String> {
+    Ok(match list {
+        ListName::ForcedBanList => merge(&mut data.server_manager.forced_ban_list, imported, |e| {
+            &e.ip
+        }),
+        ListName::BanList => merge(
+            &mut config_mut(data, server_id, config_name)?.ban_list,
+            imported,
+            |e| &e.ip,
+        ),
+        ListName::Whitelist => merge_whitelist(
+            &mut config_mut(data, server_id, config_name)?.whitelist,
+            imported,
+        ),
+    })
+}
+
+/// A list sent through the Server Utility IPC: "import_list" parses `content` and merges it
+/// into `entries`, "export_list" writes `entries` in `format`.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ListTransfer {
+    pub name: Option<ListName>,
+    #[serde(default)]
+    pub format: ListFormat,
+    #[serde(default)]
+    pub content: String,
+    /// The list as the client currently has it (whitelist strings are accepted).
+    #[serde(default)]
+    pub entries: Vec<BanEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<BanEntry> {
+        vec![
+            BanEntry {
+                added_by: "alice".to_string(),
+                created_at: 100,
+                expires_at: Some(200),
+                ..BanEntry::new("1.2.3.4", "teamkilling, twice")
+            },
+            BanEntry::new("10.0.0.0/8", "say \"hi\""),
+        ]
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        for format in [ListFormat::Csv, ListFormat::Json] {
+            let text = export(&sample(), format);
+            assert_eq!(import(&text, format).unwrap(), sample(), "{:?}", format);
+        }
+        let text = export(&sample(), ListFormat::Text);
+        assert_eq!(text, "1.2.3.4\n10.0.0.0/8\n");
+        let ips: Vec<String> = import(&text, ListFormat::Text)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.ip)
+            .collect();
+        assert_eq!(ips, vec!["1.2.3.4", "10.0.0.0/8"]);
+    }
+
+    #[test]
+    fn imports_loose_input() {
+        let text = "# shared list\n\n5.6.7.8 :> aimbot\r\n  9.9.9.9  \n";
+        let entries = import(text, ListFormat::Text).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                BanEntry::new("5.6.7.8", "aimbot"),
+                BanEntry::new("9.9.9.9", "")
+            ]
+        );
+
+        let csv = "\u{feff}Reason,IP\r\nspam,1.1.1.1\n\n,\n";
+        assert_eq!(
+            import(csv, ListFormat::Csv).unwrap(),
+            vec![BanEntry::new("1.1.1.1", "spam")]
+        );
+        let csv = "2.2.2.2,griefing\n3.3.3.3\n";
+        assert_eq!(
+            import(csv, ListFormat::Csv).unwrap()[1],
+            BanEntry::new("3.3.3.3", "")
+        );
+        assert!(import("ip,expires_at\n1.1.1.1,tomorrow\n", ListFormat::Csv).is_err());
+
+        let json = r#"["4.4.4.4 :> old style", {"ip": "5.5.5.5"}]"#;
+        assert_eq!(import(json, ListFormat::Json).unwrap().len(), 2);
+        assert!(import("{", ListFormat::Json).is_err());
+        assert_eq!("TXT".parse::<ListFormat>(), Ok(ListFormat::Text));
+    }
+
+    #[test]
+    fn merge_skips_listed_ips() {
+        let mut list = vec![BanEntry::new("1.2.3.4", "existing")];
+        let added = merge(&mut list, sample().into_iter().chain(sample()), |e| &e.ip);
+        assert_eq!(added, 1);
+        assert_eq!(list[0].reason, "existing");
+        assert_eq!(list[1].ip, "10.0.0.0/8");
+
+        let mut whitelist = vec!["1.2.3.4:>friend".to_string()];
+        assert_eq!(merge_whitelist(&mut whitelist, sample()), 1);
+        assert_eq!(whitelist[1], "10.0.0.0/8 :> say \"hi\"");
+    }
+}
@@ -0,0 +1,436 @@
+//! Leveled JSON-lines log files with size and age based rotation.
+//!
+//! Each record is one JSON object per line. When the current file would grow past the size
+//! limit, or its first record is older than the age limit, it is renamed to `<name>.1`,
+//! pushing older archives to `<name>.2` .. `<name>.N`; the oldest beyond N is deleted.
+//! Readers also accept the plain text lines written by older versions.
+
+use crate::atomic_file::backup_path;
+use crate::util::unix_now;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Records returned by [`read_records`] when the filter sets no limit.
+pub const DEFAULT_READ_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Unix time in seconds; 0 for lines carried over from the old plain text format.
+    pub ts: u64,
+    pub level: Level,
+    /// Subsystem that wrote the record, e.g. "DS-Helper", "Watchdog", "HTTP".
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_id: Option<String>,
+    pub message: String,
+}
+
+impl LogRecord {
+    pub fn new(level: Level, target: &str, message: impl Into<String>) -> Self {
+        Self {
+            ts: unix_now(),
+            level,
+            target: target.to_string(),
+            server_id: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn server(mut self, id: &str) -> Self {
+        self.server_id = Some(id.to_string());
+        self
+    }
+
+    /// Parse one line of a log file. Lines that are not JSON records (older plain text logs)
+    /// become info records with the whole line as the message.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            return None;
+        }
+        serde_json::from_str(line).ok().or_else(|| {
+            Some(Self {
+                ts: 0,
+                level: Level::Info,
+                target: String::new(),
+                server_id: None,
+                message: line.to_string(),
+            })
+        })
+    }
+}
+
+/// When the current log file is archived. Zero disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationPolicy {
+    pub max_bytes: u64,
+    pub max_age_days: u32,
+    /// Archives kept as `<name>.1` .. `<name>.N`. 0 = the file is simply started over.
+    pub archives: u32,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 10 * 1024 * 1024,
+            max_age_days: 0,
+            archives: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    #[serde(default)]
+    pub server_id: Option<String>,
+    /// Minimum level.
+    #[serde(default)]
+    pub level: Option<Level>,
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Most recent matching records to return; defaults to [`DEFAULT_READ_LIMIT`].
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// When set, only records about these servers are kept (records about no server are left
+    /// out too); None for readers who may see everything.
+    #[serde(skip)]
+    pub visible_servers: Option<HashSet<String>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        self.server_id
+            .as_ref()
+            .is_none_or(|id| record.server_id.as_ref() == Some(id))
+            && self.level.is_none_or(|level| record.level >= level)
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|t| record.target.eq_ignore_ascii_case(t))
+            && self.visible_servers.as_ref().is_none_or(|visible| {
+                record
+                    .server_id
+                    .as_ref()
+                    .is_some_and(|id| visible.contains(id))
+            })
+    }
+}
+
+struct LoggerState {
+    policy: RotationPolicy,
+    min_level: Level,
+    /// Timestamp of the first record in the current file, once known.
+    started: Option<u64>,
+}
+
+/// A log file shared by every thread that writes to it.
+pub struct Logger {
+    path: PathBuf,
+    state: Mutex<LoggerState>,
+}
+
+impl Logger {
+    pub fn new(path: PathBuf, policy: RotationPolicy, min_level: Level) -> Self {
+        Self {
+            path,
+            state: Mutex::new(LoggerState {
+                policy,
+                min_level,
+                started: None,
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Apply changed settings; they take effect with the next record.
+    pub fn configure(&self, policy: RotationPolicy, min_level: Level) {
+        if let Ok(mut state) = self.state.lock() {
+            state.policy = policy;
+            state.min_level = min_level;
+        }
+    }
+
+    pub fn enabled(&self, level: Level) -> bool {
+        self.state
+            .lock()
+            .map(|s| level >= s.min_level)
+            .unwrap_or(false)
+    }
+
+    /// Append `record`, rotating first if it would break the size or age limit. Records
+    /// below the minimum level are dropped.
+    pub fn write(&self, record: &LogRecord) -> Result<(), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "Log lock poisoned".to_string())?;
+        if record.level < state.min_level {
+            return Ok(());
+        }
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| format!("Failed to serialize log record: {}", e))?;
+        line.push('\n');
+
+        let len = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if len == 0 {
+            state.started = Some(record.ts);
+        } else if state.started.is_none() {
+            state.started = Some(first_record_ts(&self.path).unwrap_or(record.ts));
+        }
+        let policy = state.policy;
+        let too_big = policy.max_bytes > 0 && len + line.len() as u64 > policy.max_bytes;
+        let too_old = policy.max_age_days > 0
+            && state
+                .started
+                .is_some_and(|s| record.ts >= s + policy.max_age_days as u64 * 24 * 3600);
+        if len > 0 && (too_big || too_old) {
+            self.rotate(policy.archives)?;
+            state.started = Some(record.ts);
+        }
+
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    fn rotate(&self, archives: u32) -> Result<(), String> {
+        let archives = archives as usize;
+        if archives == 0 {
+            return fs::remove_file(&self.path)
+                .map_err(|e| format!("Failed to rotate {}: {}", self.path.display(), e));
+        }
+        let _ = fs::remove_file(backup_path(&self.path, archives));
+        for n in (1..archives).rev() {
+            let from = backup_path(&self.path, n);
+            if from.exists() {
+                fs::rename(&from, backup_path(&self.path, n + 1))
+                    .map_err(|e| format!("Failed to rotate {}: {}", from.display(), e))?;
+            }
+        }
+        fs::rename(&self.path, backup_path(&self.path, 1))
+            .map_err(|e| format!("Failed to rotate {}: {}", self.path.display(), e))
+    }
+}
+
+fn first_record_ts(path: &Path) -> Option<u64> {
+    let file = fs::File::open(path).ok()?;
+    let mut first = String::new();
+    BufReader::new(file).read_line(&mut first).ok()?;
+    LogRecord::parse_line(&first)
+        .map(|r| r.ts)
+        .filter(|&ts| ts > 0)
+}
+
+/// Most recent records matching `filter`, oldest first, reading back through the archives
+/// when the current file does not have enough.
+pub fn read_records(path: &Path, filter: &LogFilter) -> Vec<LogRecord> {
+    let limit = filter.limit.unwrap_or(DEFAULT_READ_LIMIT);
+    let mut newest_first = Vec::new();
+    let files = std::iter::once(path.to_path_buf()).chain((1..).map(|n| backup_path(path, n)));
+    for (i, file) in files.enumerate() {
+        if newest_first.len() >= limit {
+            break;
+        }
+        let content = match fs::read(&file) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).replace('\0', ""),
+            Err(_) if i == 0 => continue,
+            Err(_) => break,
+        };
+        newest_first.extend(
+            content
+                .lines()
+                .rev()
+                .filter_map(LogRecord::parse_line)
+                .filter(|r| filter.matches(r))
+                .take(limit - newest_first.len()),
+        );
+    }
+    newest_first.reverse();
+    newest_first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_dir;
+
+    fn record(ts: u64, level: Level, server: Option<&str>, message: &str) -> LogRecord {
+        LogRecord {
+            ts,
+            level,
+            target: "Test".to_string(),
+            server_id: server.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn size_limit_rotates_into_capped_numbered_archives() {
+        let dir = test_dir("logging_size");
+        let path = dir.join("app.log");
+        let line_len = serde_json::to_string(&record(1, Level::Info, None, "m00"))
+            .unwrap()
+            .len() as u64
+            + 1;
+        let policy = RotationPolicy {
+            max_bytes: line_len * 2,
+            max_age_days: 0,
+            archives: 2,
+        };
+        let logger = Logger::new(path.clone(), policy, Level::Info);
+        for i in 0..8 {
+            logger
+                .write(&record(1, Level::Info, None, &format!("m{:02}", i)))
+                .unwrap();
+        }
+        let lines = |p: &Path| fs::read_to_string(p).unwrap().lines().count();
+        assert_eq!(lines(&path), 2);
+        assert_eq!(lines(&backup_path(&path, 1)), 2);
+        assert_eq!(lines(&backup_path(&path, 2)), 2);
+        assert!(!backup_path(&path, 3).exists());
+
+        let all = read_records(&path, &LogFilter::default());
+        let messages: Vec<&str> = all.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["m02", "m03", "m04", "m05", "m06", "m07"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn age_limit_starts_a_new_file() {
+        let dir = test_dir("logging_age");
+        let path = dir.join("app.log");
+        let policy = RotationPolicy {
+            max_bytes: 0,
+            max_age_days: 1,
+            archives: 3,
+        };
+        let logger = Logger::new(path.clone(), policy, Level::Info);
+        logger
+            .write(&record(1_000, Level::Info, None, "day one"))
+            .unwrap();
+        logger
+            .write(&record(50_000, Level::Info, None, "same day"))
+            .unwrap();
+        logger
+            .write(&record(1_000 + 86_400, Level::Info, None, "next day"))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, 1))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // A fresh logger on an existing file takes the age from its first record.
+        let logger = Logger::new(path.clone(), policy, Level::Info);
+        logger
+            .write(&record(1_000 + 2 * 86_400, Level::Info, None, "day three"))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, 2))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filters_by_server_and_level_and_drops_below_minimum() {
+        let dir = test_dir("logging_filter");
+        let path = dir.join("app.log");
+        fs::write(&path, "[2024-01-01 10:00:00] old plain text line\n").unwrap();
+        let logger = Logger::new(path.clone(), RotationPolicy::default(), Level::Info);
+        logger
+            .write(&record(1, Level::Debug, Some("a"), "dropped"))
+            .unwrap();
+        logger
+            .write(&record(2, Level::Info, Some("a"), "a joined"))
+            .unwrap();
+        logger
+            .write(&record(3, Level::Warn, Some("a"), "a kicked"))
+            .unwrap();
+        logger
+            .write(&record(4, Level::Warn, Some("b"), "b kicked"))
+            .unwrap();
+        logger
+            .write(&record(5, Level::Error, None, "general"))
+            .unwrap();
+
+        let all = read_records(&path, &LogFilter::default());
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[0].message, "[2024-01-01 10:00:00] old plain text line");
+        assert_eq!(all[0].ts, 0);
+
+        let server_a = LogFilter {
+            server_id: Some("a".to_string()),
+            ..LogFilter::default()
+        };
+        let messages: Vec<String> = read_records(&path, &server_a)
+            .into_iter()
+            .map(|r| r.message)
+            .collect();
+        assert_eq!(messages, ["a joined", "a kicked"]);
+
+        let warnings = LogFilter {
+            level: Some(Level::Warn),
+            limit: Some(2),
+            ..LogFilter::default()
+        };
+        let messages: Vec<String> = read_records(&path, &warnings)
+            .into_iter()
+            .map(|r| r.message)
+            .collect();
+        assert_eq!(messages, ["b kicked", "general"]);
+
+        let only_b = LogFilter {
+            level: Some(Level::Info),
+            visible_servers: Some(HashSet::from(["b".to_string()])),
+            ..LogFilter::default()
+        };
+        let messages: Vec<String> = read_records(&path, &only_b)
+            .into_iter()
+            .map(|r| r.message)
+            .collect();
+        assert_eq!(messages, ["b kicked"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
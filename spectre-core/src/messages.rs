@@ -0,0 +1,295 @@
+//! Scheduled server messages: rotating announcements, a welcome on join and warnings before
+//! scheduled restarts, sent in-game with `asay` when `Server::messages` is on.
+//!
+//! Messages are templates; `{player}`, `{server}`, `{map}`, `{players}`, `{max_players}` and
+//! `{next_restart}` are filled in when sent. Unknown variables are left as written.
+
+use crate::server::ServerConfig;
+
+/// Values for the template variables.
+#[derive(Debug, Clone, Default)]
+pub struct MessageContext<'a> {
+    /// The joining player; empty outside welcome messages.
+    pub player: &'a str,
+    pub server: &'a str,
+    /// First map of the profile's rotation (the helper cannot read the current map).
+    pub map: &'a str,
+    pub players: usize,
+    pub max_players: u32,
+    /// Seconds until the next scheduled restart; None when no restart is scheduled.
+    pub next_restart: Option<u64>,
+}
+
+/// Fill in the template variables of `template` in one pass, so a value that looks like a
+/// variable (a player named `{server}`) is not expanded again.
+pub fn render(template: &str, ctx: &MessageContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(end) = rest.find('}') {
+        let (before, after) = (&rest[..end], &rest[end + 1..]);
+        match before
+            .rfind('{')
+            .and_then(|start| Some((start, variable(&before[start + 1..], ctx)?)))
+        {
+            Some((start, value)) => {
+                out.push_str(&before[..start]);
+                out.push_str(&value);
+            }
+            None => {
+                out.push_str(before);
+                out.push('}');
+            }
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The value of template variable `name`; None for an unknown one.
+fn variable(name: &str, ctx: &MessageContext) -> Option<String> {
+    Some(match name {
+        "player" => ctx.player.trim().to_string(),
+        "server" => ctx.server.trim().to_string(),
+        "map" => ctx.map.to_string(),
+        "players" => ctx.players.to_string(),
+        "max_players" => ctx.max_players.to_string(),
+        "next_restart" => ctx
+            .next_restart
+            .map(format_duration)
+            .unwrap_or_else(|| "not scheduled".to_string()),
+        _ => return None,
+    })
+}
+
+/// `1h 05m`, `12m` or `40s`.
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Split `text` into lines of at most `max` characters, breaking at spaces where possible, so
+/// a long message is sent as several `asay` lines instead of being cut off.
+pub fn split_lines(text: &str, max: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..max).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Per-server state between watchdog ticks.
+#[derive(Debug, Default)]
+pub struct MessageScheduler {
+    next: usize,
+    last_sent_at: Option<u64>,
+    /// `restart_warning_minutes` already sent for the coming restart.
+    warned: Vec<u32>,
+}
+
+impl MessageScheduler {
+    /// Messages due at `now`: the next rotating announcement every `message_interval` seconds
+    /// and each restart warning as its time comes. Nothing is sent to an empty server, but
+    /// the rotation and warnings still advance.
+    pub fn tick(&mut self, config: &ServerConfig, ctx: &MessageContext, now: u64) -> Vec<String> {
+        let mut due = Vec::new();
+
+        let announcements: Vec<&String> = config
+            .messages
+            .iter()
+            .filter(|m| !m.trim().is_empty())
+            .collect();
+        let interval = config.message_interval.max(1) as u64;
+        match self.last_sent_at {
+            None => self.last_sent_at = Some(now),
+            Some(last) if now >= last + interval && !announcements.is_empty() => {
+                let template = announcements[self.next % announcements.len()];
+                self.next = (self.next + 1) % announcements.len();
+                self.last_sent_at = Some(now);
+                due.push(render(template, ctx));
+            }
+            Some(_) => {}
+        }
+
+        match ctx.next_restart {
+            Some(remaining) => {
+                // Only the closest threshold that was crossed, so a late start sends one line.
+                let crossed = config
+                    .restart_warning_minutes
+                    .iter()
+                    .filter(|m| remaining <= **m as u64 * 60 && !self.warned.contains(m))
+                    .min()
+                    .copied();
+                if let Some(minutes) = crossed {
+                    self.warned.extend(
+                        config
+                            .restart_warning_minutes
+                            .iter()
+                            .filter(|m| **m >= minutes),
+                    );
+                    if !config.restart_warning.trim().is_empty() {
+                        due.push(render(&config.restart_warning, ctx));
+                    }
+                }
+                // A later restart is further away than every threshold: start over.
+                let furthest = config.restart_warning_minutes.iter().max().copied();
+                if furthest.is_none_or(|m| remaining > m as u64 * 60) {
+                    self.warned.clear();
+                }
+            }
+            None => self.warned.clear(),
+        }
+
+        if ctx.players == 0 {
+            due.clear();
+        }
+        due
+    }
+
+    /// The welcome line for a player who just joined, if one is set.
+    pub fn welcome(config: &ServerConfig, ctx: &MessageContext) -> Option<String> {
+        let template = config.welcome_message.trim();
+        (!template.is_empty()).then(|| render(template, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ServerConfig {
+        ServerConfig {
+            messages: vec![
+                "Welcome to {server}".to_string(),
+                " ".to_string(),
+                "{players}/{max_players} on {map}".to_string(),
+            ],
+            message_interval: 300,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_templates_and_splits_long_lines() {
+        let ctx = MessageContext {
+            player: " Pilot ",
+            server: "Alpha",
+            map: "Alps3",
+            players: 5,
+            max_players: 32,
+            next_restart: Some(3900),
+        };
+        assert_eq!(
+            render(
+                "Hi {player}, {players}/{max_players} on {map}. Restart in {next_restart} {x}",
+                &ctx
+            ),
+            "Hi Pilot, 5/32 on Alps3. Restart in 1h 05m {x}"
+        );
+        assert_eq!(format_duration(600), "10m");
+        assert_eq!(format_duration(59), "59s");
+        // Values are not expanded again, whatever a player calls themselves.
+        let named = MessageContext {
+            player: "{server}{next_restart}",
+            ..ctx.clone()
+        };
+        assert_eq!(
+            render("Welcome {player} to} {x {server}}", &named),
+            "Welcome {server}{next_restart} to} {x Alpha}"
+        );
+        let ctx = MessageContext::default();
+        assert_eq!(render("{next_restart}", &ctx), "not scheduled");
+
+        let lines = split_lines(
+            "Be nice to each other and no spawn camping on this server please",
+            20,
+        );
+        assert!(lines.iter().all(|l| l.chars().count() <= 20), "{:?}", lines);
+        assert_eq!(
+            lines.join(" "),
+            "Be nice to each other and no spawn camping on this server please"
+        );
+        assert_eq!(split_lines("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn rotates_announcements_and_warns_before_restart() {
+        let config = config();
+        let mut scheduler = MessageScheduler::default();
+        let mut ctx = MessageContext {
+            server: "Alpha",
+            map: "Alps3",
+            players: 3,
+            max_players: 32,
+            ..Default::default()
+        };
+        assert!(scheduler.tick(&config, &ctx, 0).is_empty());
+        assert!(scheduler.tick(&config, &ctx, 299).is_empty());
+        assert_eq!(scheduler.tick(&config, &ctx, 300), vec!["Welcome to Alpha"]);
+        assert_eq!(scheduler.tick(&config, &ctx, 600), vec!["3/32 on Alps3"]);
+        // Empty server: nothing sent, but the rotation moves on.
+        ctx.players = 0;
+        assert!(scheduler.tick(&config, &ctx, 900).is_empty());
+        ctx.players = 1;
+        assert_eq!(scheduler.tick(&config, &ctx, 1200), vec!["1/32 on Alps3"]);
+
+        // Default warnings at 10 and 1 minute before the restart, each once.
+        ctx.next_restart = Some(3600);
+        assert!(scheduler.tick(&config, &ctx, 1210).is_empty());
+        ctx.next_restart = Some(590);
+        assert_eq!(
+            scheduler.tick(&config, &ctx, 1220),
+            vec!["Server restarts in 9m."]
+        );
+        ctx.next_restart = Some(500);
+        assert!(scheduler.tick(&config, &ctx, 1230).is_empty());
+        ctx.next_restart = Some(45);
+        assert_eq!(
+            scheduler.tick(&config, &ctx, 1240),
+            vec!["Server restarts in 45s."]
+        );
+        // After the restart the next one is days away: warnings are armed again.
+        ctx.next_restart = Some(86_400);
+        assert!(scheduler.tick(&config, &ctx, 1250).is_empty());
+        ctx.next_restart = Some(30);
+        assert_eq!(
+            scheduler.tick(&config, &ctx, 1260),
+            vec!["Server restarts in 30s."]
+        );
+
+        ctx.player = "Pilot";
+        assert_eq!(MessageScheduler::welcome(&config, &ctx), None);
+        let config = ServerConfig {
+            welcome_message: "Welcome {player}!".to_string(),
+            ..config
+        };
+        assert_eq!(
+            MessageScheduler::welcome(&config, &ctx).as_deref(),
+            Some("Welcome Pilot!")
+        );
+    }
+}
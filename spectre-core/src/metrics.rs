@@ -0,0 +1,253 @@
+//! Prometheus text exposition (`/metrics`) for the DS fleet.
+//!
+//! Gauges come from a snapshot taken per scrape ([`ServerSample`]); counters that only the
+//! app sees happen (watchdog restarts, kicks) live in [`FleetCounters`] and reset when
+//! Spectre restarts, which Prometheus handles as a counter reset.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Content type of [`render`] output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// State of one configured server at scrape time.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSample {
+    pub id: String,
+    pub name: String,
+    pub port: u16,
+    pub up: bool,
+    pub uptime_seconds: Option<u64>,
+    /// Connected players, when the DS helper has read the slots.
+    pub players: Option<usize>,
+    pub max_clients: u32,
+    /// User plus kernel CPU time of the DS process.
+    pub cpu_seconds: Option<f64>,
+    /// Working set of the DS process.
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FleetCounters {
+    watchdog_restarts: HashMap<String, u64>,
+    /// By (server ID, reason).
+    kicks: HashMap<(String, String), u64>,
+}
+
+impl FleetCounters {
+    pub fn record_watchdog_restart(&mut self, server_id: &str) {
+        *self
+            .watchdog_restarts
+            .entry(server_id.to_string())
+            .or_default() += 1;
+    }
+
+    /// `reason` is a short fixed label such as "ban" or "whitelist", not free text.
+    pub fn record_kick(&mut self, server_id: &str, reason: &str) {
+        *self
+            .kicks
+            .entry((server_id.to_string(), reason.to_string()))
+            .or_default() += 1;
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn labels(sample: &ServerSample) -> String {
+    format!(
+        "server_id=\"{}\",server=\"{}\",port=\"{}\"",
+        escape_label(&sample.id),
+        escape_label(&sample.name),
+        sample.port
+    )
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Metrics for `samples` in the Prometheus text format. Counters are reported for every
+/// sampled server (0 when nothing happened yet) so rates work from the first scrape.
+pub fn render(samples: &[ServerSample], counters: &FleetCounters) -> String {
+    let mut out = String::new();
+
+    family(
+        &mut out,
+        "hd2_server_up",
+        "gauge",
+        "1 if the dedicated server process is running.",
+    );
+    for s in samples {
+        let _ = writeln!(out, "hd2_server_up{{{}}} {}", labels(s), s.up as u8);
+    }
+
+    family(
+        &mut out,
+        "hd2_server_uptime_seconds",
+        "gauge",
+        "Seconds since the dedicated server process started.",
+    );
+    for s in samples {
+        if let Some(uptime) = s.uptime_seconds {
+            let _ = writeln!(out, "hd2_server_uptime_seconds{{{}}} {}", labels(s), uptime);
+        }
+    }
+
+    family(
+        &mut out,
+        "hd2_server_players",
+        "gauge",
+        "Connected players.",
+    );
+    for s in samples {
+        if let Some(players) = s.players {
+            let _ = writeln!(out, "hd2_server_players{{{}}} {}", labels(s), players);
+        }
+    }
+
+    family(
+        &mut out,
+        "hd2_server_max_clients",
+        "gauge",
+        "Player slots in the active profile.",
+    );
+    for s in samples {
+        let _ = writeln!(
+            out,
+            "hd2_server_max_clients{{{}}} {}",
+            labels(s),
+            s.max_clients
+        );
+    }
+
+    family(
+        &mut out,
+        "hd2_server_watchdog_restarts_total",
+        "counter",
+        "Times the watchdog restarted the server after it exited.",
+    );
+    for s in samples {
+        let restarts = counters.watchdog_restarts.get(&s.id).copied().unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "hd2_server_watchdog_restarts_total{{{}}} {}",
+            labels(s),
+            restarts
+        );
+    }
+
+    family(
+        &mut out,
+        "hd2_server_kicks_total",
+        "counter",
+        "Players kicked by Spectre, by reason.",
+    );
+    let mut kicks: Vec<(&(String, String), &u64)> = counters.kicks.iter().collect();
+    kicks.sort();
+    for ((server_id, reason), count) in kicks {
+        if let Some(s) = samples.iter().find(|s| &s.id == server_id) {
+            let _ = writeln!(
+                out,
+                "hd2_server_kicks_total{{{},reason=\"{}\"}} {}",
+                labels(s),
+                escape_label(reason),
+                count
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "hd2_server_cpu_seconds_total",
+        "counter",
+        "CPU time used by the dedicated server process.",
+    );
+    for s in samples {
+        if let Some(cpu) = s.cpu_seconds {
+            let _ = writeln!(
+                out,
+                "hd2_server_cpu_seconds_total{{{}}} {:.3}",
+                labels(s),
+                cpu
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "hd2_server_memory_bytes",
+        "gauge",
+        "Working set of the dedicated server process.",
+    );
+    for s in samples {
+        if let Some(memory) = s.memory_bytes {
+            let _ = writeln!(out, "hd2_server_memory_bytes{{{}}} {}", labels(s), memory);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_gauges_and_counters_per_server() {
+        let samples = vec![
+            ServerSample {
+                id: "a1".to_string(),
+                name: "Main \"EU\"".to_string(),
+                port: 22000,
+                up: true,
+                uptime_seconds: Some(3600),
+                players: Some(12),
+                max_clients: 32,
+                cpu_seconds: Some(81.5),
+                memory_bytes: Some(150_000_000),
+            },
+            ServerSample {
+                id: "b2".to_string(),
+                name: "Training".to_string(),
+                port: 22001,
+                max_clients: 16,
+                ..ServerSample::default()
+            },
+        ];
+        let mut counters = FleetCounters::default();
+        counters.record_watchdog_restart("a1");
+        counters.record_watchdog_restart("a1");
+        counters.record_kick("a1", "ban");
+        counters.record_kick("a1", "whitelist");
+        counters.record_kick("a1", "ban");
+        counters.record_kick("removed", "ban");
+
+        let text = render(&samples, &counters);
+        let a = "server_id=\"a1\",server=\"Main \\\"EU\\\"\",port=\"22000\"";
+        let b = "server_id=\"b2\",server=\"Training\",port=\"22001\"";
+        for line in [
+            format!("hd2_server_up{{{}}} 1", a),
+            format!("hd2_server_up{{{}}} 0", b),
+            format!("hd2_server_uptime_seconds{{{}}} 3600", a),
+            format!("hd2_server_players{{{}}} 12", a),
+            format!("hd2_server_max_clients{{{}}} 16", b),
+            format!("hd2_server_watchdog_restarts_total{{{}}} 2", a),
+            format!("hd2_server_watchdog_restarts_total{{{}}} 0", b),
+            format!("hd2_server_kicks_total{{{},reason=\"ban\"}} 2", a),
+            format!("hd2_server_kicks_total{{{},reason=\"whitelist\"}} 1", a),
+            format!("hd2_server_cpu_seconds_total{{{}}} 81.500", a),
+            format!("hd2_server_memory_bytes{{{}}} 150000000", a),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+        assert!(!text.contains("hd2_server_uptime_seconds{server_id=\"b2\""));
+        assert!(!text.contains("removed"));
+        assert!(text.contains("# TYPE hd2_server_kicks_total counter"));
+    }
+}
@@ -0,0 +1,220 @@
+//! Player name rules: name bans (exact, case-insensitive or regex), the clan tag check that
+//! kicks players wearing `ServerConfig::clan_tag` without being on the clan roster, and the
+//! clan reserved slots.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Compiled size cap for a rule's regex, so a pasted pattern cannot blow up memory.
+const REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameMatchMode {
+    Exact,
+    #[default]
+    IgnoreCase,
+    /// Unanchored and case-insensitive; use `^...$` for a whole-name match.
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub mode: NameMatchMode,
+    /// Shown in-game with the kick, like the comment on an IP ban entry.
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl NameRule {
+    /// Error text if the pattern is empty or (for regex rules) does not compile.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pattern.trim().is_empty() {
+            return Err("Name rule has an empty pattern".to_string());
+        }
+        if self.mode == NameMatchMode::Regex {
+            compile(&self.pattern)?;
+        }
+        Ok(())
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid name pattern \"{}\": {}", pattern, e))
+}
+
+enum Compiled {
+    Exact(String),
+    IgnoreCase(String),
+    Regex(Regex),
+}
+
+/// Name rules compiled once for matching many players. Rules that fail to compile are
+/// skipped (saves reject them, so this only happens with hand-edited files).
+pub struct NameRules<'a> {
+    rules: Vec<(Compiled, &'a NameRule)>,
+}
+
+impl<'a> NameRules<'a> {
+    pub fn new(rules: &'a [NameRule]) -> Self {
+        Self {
+            rules: rules
+                .iter()
+                .filter_map(|rule| {
+                    let pattern = rule.pattern.trim();
+                    let compiled = match rule.mode {
+                        _ if pattern.is_empty() => return None,
+                        NameMatchMode::Exact => Compiled::Exact(pattern.to_string()),
+                        NameMatchMode::IgnoreCase => Compiled::IgnoreCase(pattern.to_lowercase()),
+                        NameMatchMode::Regex => Compiled::Regex(compile(pattern).ok()?),
+                    };
+                    Some((compiled, rule))
+                })
+                .collect(),
+        }
+    }
+
+    /// The first rule that matches `name`.
+    pub fn find(&self, name: &str) -> Option<&'a NameRule> {
+        let name = name.trim();
+        let lower = name.to_lowercase();
+        self.rules
+            .iter()
+            .find(|(compiled, _)| match compiled {
+                Compiled::Exact(p) => name == p,
+                Compiled::IgnoreCase(p) => lower == *p,
+                Compiled::Regex(re) => re.is_match(name),
+            })
+            .map(|(_, rule)| *rule)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// True if `name` carries `clan_tag` (case-insensitive) but is not on `roster`. Roster
+/// entries may be written with or without the tag. An empty tag or an empty roster (no roster
+/// kept, so the real clan would be kicked too) never matches.
+pub fn impersonates_clan(name: &str, clan_tag: &str, roster: &[String]) -> bool {
+    let tag = clan_tag.trim().to_lowercase();
+    if tag.is_empty() || roster.is_empty() {
+        return false;
+    }
+    let name = name.trim().to_lowercase();
+    if !name.contains(&tag) {
+        return false;
+    }
+    let untagged = name.replacen(&tag, "", 1);
+    let untagged = untagged.trim();
+    !roster.iter().any(|member| {
+        let member = member.trim().to_lowercase();
+        member == name || member == untagged
+    })
+}
+
+/// True if `name` carries `clan_tag` and, when a roster is kept, is on it.
+pub fn is_clan_member(name: &str, clan_tag: &str, roster: &[String]) -> bool {
+    let tag = clan_tag.trim().to_lowercase();
+    !tag.is_empty()
+        && name.to_lowercase().contains(&tag)
+        && !impersonates_clan(name, clan_tag, roster)
+}
+
+/// Players to kick so `reserve` slots stay open for the clan. Slots already taken by clan
+/// members count against the reserve. `connected` must be in join order; the most recently
+/// joined non-members are picked first.
+pub fn clan_reserve_kicks(
+    connected: &[(String, String)],
+    max_clients: usize,
+    reserve: usize,
+    is_member: impl Fn(&str) -> bool,
+) -> Vec<&(String, String)> {
+    let members = connected.iter().filter(|(n, _)| is_member(n)).count();
+    let open_reserve = reserve.saturating_sub(members);
+    let excess = (connected.len() + open_reserve).saturating_sub(max_clients);
+    connected
+        .iter()
+        .rev()
+        .filter(|(n, _)| !is_member(n))
+        .take(excess)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, mode: NameMatchMode) -> NameRule {
+        NameRule {
+            pattern: pattern.to_string(),
+            mode,
+            reason: String::new(),
+        }
+    }
+
+    #[test]
+    fn rules_match_by_mode() {
+        let rules = vec![
+            rule("Griefer", NameMatchMode::Exact),
+            rule("TeamKiller", NameMatchMode::IgnoreCase),
+            rule(r"^x+sniper\d*$", NameMatchMode::Regex),
+            rule("(unclosed", NameMatchMode::Regex),
+        ];
+        let compiled = NameRules::new(&rules);
+        assert_eq!(compiled.find("Griefer"), Some(&rules[0]));
+        assert_eq!(compiled.find("griefer"), None);
+        assert_eq!(compiled.find(" teamkiller "), Some(&rules[1]));
+        assert_eq!(compiled.find("XXSniper42"), Some(&rules[2]));
+        assert_eq!(compiled.find("Sniper"), None);
+        assert!(rules[3].validate().is_err());
+        assert!(rule(" ", NameMatchMode::Exact).validate().is_err());
+        assert!(rules[2].validate().is_ok());
+    }
+
+    #[test]
+    fn clan_tag_needs_roster() {
+        let roster = vec!["Pilot".to_string(), "[ABC]Medic".to_string()];
+        assert!(impersonates_clan("[ABC]Faker", "[ABC]", &roster));
+        assert!(impersonates_clan("[abc] Faker", "[ABC]", &roster));
+        assert!(!impersonates_clan("[ABC]Pilot", "[ABC]", &roster));
+        assert!(!impersonates_clan("[ABC] pilot", "[ABC]", &roster));
+        assert!(!impersonates_clan("[ABC]Medic", "[ABC]", &roster));
+        assert!(!impersonates_clan("Someone", "[ABC]", &roster));
+        assert!(!impersonates_clan("[ABC]Faker", "", &roster));
+        assert!(is_clan_member("[ABC]Pilot", "[ABC]", &roster));
+        assert!(!is_clan_member("[ABC]Faker", "[ABC]", &roster));
+        assert!(is_clan_member("[ABC]Anyone", "[ABC]", &[]));
+        // Without a roster nobody can be told apart from the clan, so nobody is kicked.
+        assert!(!impersonates_clan("[ABC]Anyone", "[ABC]", &[]));
+    }
+
+    #[test]
+    fn reserve_kicks_latest_non_members() {
+        let players: Vec<(String, String)> = ["a", "[C]b", "c", "d", "e"]
+            .iter()
+            .map(|n| (n.to_string(), "1.2.3.4".to_string()))
+            .collect();
+        let member = |n: &str| n.starts_with("[C]");
+        let names = |kicks: Vec<&(String, String)>| {
+            kicks
+                .into_iter()
+                .map(|(n, _)| n.clone())
+                .collect::<Vec<_>>()
+        };
+        // 5 connected, one member using one of 3 reserved slots: 5 + 2 > 6.
+        assert_eq!(names(clan_reserve_kicks(&players, 6, 3, member)), vec!["e"]);
+        assert_eq!(
+            names(clan_reserve_kicks(&players, 5, 3, member)),
+            vec!["e", "d"]
+        );
+        assert!(clan_reserve_kicks(&players, 8, 3, member).is_empty());
+        assert!(clan_reserve_kicks(&players, 5, 1, member).is_empty());
+    }
+}
@@ -0,0 +1,523 @@
+//! Webhook notifications for server events (crashes, restarts, kicks, full/empty servers).
+//!
+//! Webhooks are configured in `ServerManager::webhooks`. Each gets a JSON POST, either the
+//! generic [`Notification`] object or a Discord-compatible message. Plain `http://` URLs are
+//! sent over a TCP connection here; `https://` needs a [`Connector`] from the caller, which
+//! keeps TLS out of this crate. [`get_text`] uses the same transport to download shared ban
+//! lists.
+
+use crate::util::unix_now;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The DS process exited without being stopped from Spectre.
+    Crash,
+    WatchdogRestart,
+    ScheduledRestart,
+    /// A player on a ban list was kicked.
+    BannedKick,
+    ServerFull,
+    ServerEmpty,
+}
+
+impl NotificationKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            NotificationKind::Crash => "Server crashed",
+            NotificationKind::WatchdogRestart => "Watchdog restart",
+            NotificationKind::ScheduledRestart => "Scheduled restart",
+            NotificationKind::BannedKick => "Banned player kicked",
+            NotificationKind::ServerFull => "Server full",
+            NotificationKind::ServerEmpty => "Server empty",
+        }
+    }
+
+    /// Discord embed colour.
+    fn color(&self) -> u32 {
+        match self {
+            NotificationKind::Crash => 0xd9_3f_3f,
+            NotificationKind::WatchdogRestart | NotificationKind::BannedKick => 0xe6_a0_3c,
+            NotificationKind::ScheduledRestart => 0x4a_90_d9,
+            NotificationKind::ServerFull | NotificationKind::ServerEmpty => 0x5c_b8_5c,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The [`Notification`] as JSON.
+    #[default]
+    Generic,
+    /// `{"content", "embeds"}` as accepted by Discord (and Slack-compatible relays).
+    Discord,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Events sent to this webhook; empty = all.
+    #[serde(default)]
+    pub events: Vec<NotificationKind>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Webhook {
+    pub fn wants(&self, kind: NotificationKind) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&kind))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub server_id: String,
+    pub server_name: String,
+    pub message: String,
+    /// Unix time in seconds.
+    pub timestamp: u64,
+}
+
+impl Notification {
+    pub fn new(
+        kind: NotificationKind,
+        server_id: &str,
+        server_name: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            server_id: server_id.to_string(),
+            server_name: server_name.to_string(),
+            message: message.into(),
+            timestamp: unix_now(),
+        }
+    }
+
+    /// Request body for `format`.
+    pub fn payload(&self, format: WebhookFormat) -> serde_json::Value {
+        match format {
+            WebhookFormat::Generic => serde_json::to_value(self).unwrap_or_default(),
+            WebhookFormat::Discord => serde_json::json!({
+                "username": "Spectre",
+                "content": format!("**{}**: {}", self.server_name, self.kind.title()),
+                "embeds": [{
+                    "title": self.kind.title(),
+                    "description": self.message,
+                    "color": self.kind.color(),
+                    "footer": { "text": format!("{} ({})", self.server_name, self.server_id) },
+                }],
+            }),
+        }
+    }
+}
+
+/// Where an https:// webhook should be sent: opens a TLS stream to `(host, port)`.
+pub type Connector<'a> = &'a dyn Fn(&str, u16) -> Result<Box<dyn ReadWrite>, String>;
+
+pub trait ReadWrite: Read + Write {}
+impl<T: Read + Write> ReadWrite for T {}
+
+struct Target<'a> {
+    https: bool,
+    host: &'a str,
+    port: u16,
+    path: &'a str,
+}
+
+fn parse_url(url: &str) -> Result<Target<'_>, String> {
+    let (https, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        return Err(format!("URL must start with http:// or https://: {}", url));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let default_port = if https { 443 } else { 80 };
+    let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+        let (host, after) = v6
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid host: {}", authority))?;
+        match after.strip_prefix(':') {
+            Some(p) => (host, p.parse().map_err(|_| format!("Invalid port: {}", p))?),
+            None => (host, default_port),
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, p)) => (host, p.parse().map_err(|_| format!("Invalid port: {}", p))?),
+            None => (authority, default_port),
+        }
+    };
+    if host.is_empty() {
+        return Err(format!("URL has no host: {}", url));
+    }
+    Ok(Target {
+        https,
+        host,
+        port,
+        path,
+    })
+}
+
+/// `url` without its path, for logs: webhook paths usually contain the secret token.
+pub fn redact_url(url: &str) -> String {
+    match parse_url(url) {
+        Ok(t) if t.path == "/" => url.to_string(),
+        Ok(t) => format!("{}/…", &url[..url.len() - t.path.len()]),
+        Err(_) => "(invalid URL)".to_string(),
+    }
+}
+
+fn open(target: &Target, tls: Option<Connector>) -> Result<Box<dyn ReadWrite>, String> {
+    if target.https {
+        let connect = tls.ok_or_else(|| "HTTPS is not supported here".to_string())?;
+        connect(target.host, target.port)
+    } else {
+        Ok(Box::new(connect_tcp(target.host, target.port)?))
+    }
+}
+
+fn host_header(target: &Target) -> String {
+    if target.host.contains(':') {
+        format!("[{}]", target.host)
+    } else {
+        target.host.to_string()
+    }
+}
+
+/// Status code from an HTTP status line.
+fn status_code(status_line: &str) -> Option<u16> {
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+}
+
+/// POST `body` as JSON to `url`. Succeeds on any 2xx status.
+pub fn post_json(
+    url: &str,
+    body: &serde_json::Value,
+    tls: Option<Connector>,
+) -> Result<(), String> {
+    let target = parse_url(url)?;
+    let mut stream = open(&target, tls).map_err(|e| format!("Webhook {}", e))?;
+    let body = body.to_string();
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: Spectre\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        target.path,
+        host_header(&target),
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("Webhook {}: {}", target.host, e))?;
+
+    // Only the status line matters; stop reading once it is complete.
+    let mut response = Vec::new();
+    let mut buf = [0u8; 512];
+    while !response.contains(&b'\n') {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(e) => return Err(format!("Webhook {}: {}", target.host, e)),
+        }
+    }
+    let status_line = String::from_utf8_lossy(&response);
+    let status_line = status_line.lines().next().unwrap_or("");
+    match status_code(status_line) {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        Some(_) => Err(format!("Webhook {}: {}", target.host, status_line.trim())),
+        None => Err(format!("Webhook {}: no HTTP response", target.host)),
+    }
+}
+
+/// GET `url` and return the body as text. Fails on a non-2xx status (redirects are not
+/// followed) or a body larger than `max_bytes`.
+pub fn get_text(url: &str, tls: Option<Connector>, max_bytes: usize) -> Result<String, String> {
+    let target = parse_url(url)?;
+    let mut stream = open(&target, tls)?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: Spectre\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        target.path,
+        host_header(&target)
+    );
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("{}: {}", target.host, e))?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(e) => return Err(format!("{}: {}", target.host, e)),
+        }
+        if response.len() > max_bytes + 16 * 1024 {
+            return Err(format!(
+                "{}: response is larger than {} bytes",
+                target.host, max_bytes
+            ));
+        }
+    }
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| format!("{}: no HTTP response", target.host))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let mut body = response[split + 4..].to_vec();
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("");
+    match status_code(status_line) {
+        Some(code) if (200..300).contains(&code) => {}
+        Some(_) => return Err(format!("{}: {}", target.host, status_line.trim())),
+        None => return Err(format!("{}: no HTTP response", target.host)),
+    }
+    let chunked = lines.any(|l| {
+        l.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.to_ascii_lowercase().contains("chunked")
+        })
+    });
+    if chunked {
+        body =
+            dechunk(&body).ok_or_else(|| format!("{}: malformed chunked response", target.host))?;
+    }
+    if body.len() > max_bytes {
+        return Err(format!(
+            "{}: response is larger than {} bytes",
+            target.host, max_bytes
+        ));
+    }
+    String::from_utf8(body).map_err(|_| format!("{}: response is not UTF-8 text", target.host))
+}
+
+/// Body of a `Transfer-Encoding: chunked` response.
+fn dechunk(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_text = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_text.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+/// Open a TCP connection with connect, read and write timeouts.
+pub fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{}: {}", host, e))?;
+    let mut last_error = format!("{}: no address", host);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => {
+                let _ = stream.set_read_timeout(Some(TIMEOUT));
+                let _ = stream.set_write_timeout(Some(TIMEOUT));
+                return Ok(stream);
+            }
+            Err(e) => last_error = format!("{}: {}", host, e),
+        }
+    }
+    Err(last_error)
+}
+
+/// Send `notification` to every webhook that wants it. Returns one error per failed webhook,
+/// prefixed with its name; a failure does not stop the others.
+pub fn send(
+    webhooks: &[Webhook],
+    notification: &Notification,
+    tls: Option<Connector>,
+) -> Vec<String> {
+    webhooks
+        .iter()
+        .filter(|w| w.wants(notification.kind))
+        .filter_map(|w| {
+            post_json(&w.url, &notification.payload(w.format), tls)
+                .err()
+                .map(|e| format!("{}: {}", w.name, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Local HTTP stand-in: answers `statuses.len()` requests with those status codes and
+    /// hands back each request's path and JSON body.
+    fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<(String, serde_json::Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let mut stream = stream;
+                write!(stream, "HTTP/1.1 {} X\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+                tx.send((path, serde_json::from_slice(&body).unwrap()))
+                    .unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    fn webhook(
+        name: &str,
+        url: &str,
+        format: WebhookFormat,
+        events: Vec<NotificationKind>,
+    ) -> Webhook {
+        Webhook {
+            name: name.to_string(),
+            url: url.to_string(),
+            format,
+            events,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn posts_generic_and_discord_payloads_to_interested_webhooks() {
+        let (base, requests) = stand_in(vec![200, 204]);
+        let webhooks = vec![
+            webhook(
+                "ops",
+                &format!("{}/hooks/ops", base),
+                WebhookFormat::Generic,
+                vec![],
+            ),
+            webhook(
+                "discord",
+                &format!("{}/api/webhooks/1/abc", base),
+                WebhookFormat::Discord,
+                vec![NotificationKind::Crash],
+            ),
+            webhook(
+                "kicks only",
+                &format!("{}/kicks", base),
+                WebhookFormat::Generic,
+                vec![NotificationKind::BannedKick],
+            ),
+        ];
+        let crash = Notification::new(NotificationKind::Crash, "a1", "Main", "HD2DS.exe exited");
+        assert!(send(&webhooks, &crash, None).is_empty());
+
+        let (path, body) = requests.recv().unwrap();
+        assert_eq!(path, "/hooks/ops");
+        assert_eq!(body["kind"], "crash");
+        assert_eq!(body["server_id"], "a1");
+        assert_eq!(body["message"], "HD2DS.exe exited");
+
+        let (path, body) = requests.recv().unwrap();
+        assert_eq!(path, "/api/webhooks/1/abc");
+        assert_eq!(body["content"], "**Main**: Server crashed");
+        assert_eq!(body["embeds"][0]["description"], "HD2DS.exe exited");
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn reports_failed_and_unreachable_webhooks() {
+        let (base, _requests) = stand_in(vec![500]);
+        let webhooks = vec![
+            webhook("broken", &base, WebhookFormat::Generic, vec![]),
+            webhook(
+                "tls",
+                "https://example.invalid/hook",
+                WebhookFormat::Discord,
+                vec![],
+            ),
+            webhook(
+                "bad",
+                "ftp://example.invalid",
+                WebhookFormat::Generic,
+                vec![],
+            ),
+        ];
+        let full = Notification::new(NotificationKind::ServerFull, "a1", "Main", "32/32 players");
+        let errors = send(&webhooks, &full, None);
+        assert_eq!(errors.len(), 3);
+        assert!(
+            errors[0].starts_with("broken: ") && errors[0].contains("500"),
+            "{}",
+            errors[0]
+        );
+        assert!(errors[1].starts_with("tls: "));
+        assert!(errors[2].starts_with("bad: "));
+    }
+
+    #[test]
+    fn parses_hosts_ports_and_paths() {
+        let t = parse_url("https://discord.com/api/webhooks/1/x").unwrap();
+        assert!(t.https);
+        assert_eq!(
+            (t.host, t.port, t.path),
+            ("discord.com", 443, "/api/webhooks/1/x")
+        );
+        let t = parse_url("http://10.0.0.5:8080").unwrap();
+        assert_eq!((t.host, t.port, t.path), ("10.0.0.5", 8080, "/"));
+        let t = parse_url("http://[::1]:9000/hook").unwrap();
+        assert_eq!((t.host, t.port, t.path), ("::1", 9000, "/hook"));
+        assert!(parse_url("http://host:port/").is_err());
+    }
+
+    #[test]
+    fn redacted_urls_keep_only_the_host() {
+        assert_eq!(
+            redact_url("https://discord.com/api/webhooks/123/secret-token"),
+            "https://discord.com/…"
+        );
+        assert_eq!(redact_url("http://10.0.0.5:8080"), "http://10.0.0.5:8080");
+        assert_eq!(redact_url("not a url"), "(invalid URL)");
+    }
+}
@@ -0,0 +1,299 @@
+//! Player session history: joins, leaves and kicks seen by the DS helper, per server.
+//!
+//! Events are appended as JSON lines (like the audit log) and paired back into sessions when
+//! searched, so a join is on disk as soon as it happens. A session without a leave is either
+//! still going or ended when the server stopped.
+
+use crate::util::unix_now;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Sessions returned by [`search`] when the query sets no limit.
+pub const DEFAULT_QUERY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Join,
+    Leave,
+    /// Spectre kicked the player; the leave follows on a later poll.
+    Kick,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerEvent {
+    /// Unix time in seconds.
+    pub timestamp: u64,
+    pub kind: EventKind,
+    pub server_id: String,
+    pub server_name: String,
+    pub name: String,
+    pub ip: String,
+    /// Kick reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl PlayerEvent {
+    pub fn new(kind: EventKind, server_id: &str, server_name: &str, name: &str, ip: &str) -> Self {
+        Self {
+            timestamp: unix_now(),
+            kind,
+            server_id: server_id.to_string(),
+            server_name: server_name.to_string(),
+            name: name.to_string(),
+            ip: ip.trim().to_string(),
+            reason: None,
+        }
+    }
+
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// One stay of a player on a server.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerSession {
+    pub server_id: String,
+    pub server_name: String,
+    pub name: String,
+    pub ip: String,
+    pub joined_at: u64,
+    /// None while connected, or when the server stopped before the leave was seen.
+    pub left_at: Option<u64>,
+    pub duration_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kick_reason: Option<String>,
+}
+
+/// A name seen on an IP that more than one name has used.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alias {
+    pub ip: String,
+    pub name: String,
+    pub sessions: usize,
+    pub last_seen: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchResult {
+    /// Oldest first.
+    pub sessions: Vec<PlayerSession>,
+    /// Every name used on the IPs of the matched sessions, for IPs shared by several names.
+    pub aliases: Vec<Alias>,
+}
+
+/// Filters for [`search`]; every field is optional.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct HistoryQuery {
+    /// Case-insensitive substring of the player name.
+    pub name: Option<String>,
+    /// IP, or a prefix such as "203.0.113.".
+    pub ip: Option<String>,
+    pub server_id: Option<String>,
+    /// Unix seconds; sessions that joined at or after this time.
+    pub since: Option<u64>,
+    /// Most recent N sessions (default [`DEFAULT_QUERY_LIMIT`]).
+    pub limit: Option<usize>,
+    /// Servers the caller may see; None means all.
+    #[serde(skip)]
+    pub visible_servers: Option<HashSet<String>>,
+}
+
+impl HistoryQuery {
+    fn visible(&self, server_id: &str) -> bool {
+        self.visible_servers
+            .as_ref()
+            .is_none_or(|ids| ids.contains(server_id))
+    }
+
+    fn matches(&self, s: &PlayerSession) -> bool {
+        self.name
+            .as_deref()
+            .map(str::trim)
+            .is_none_or(|n| s.name.to_lowercase().contains(&n.to_lowercase()))
+            && self
+                .ip
+                .as_deref()
+                .map(str::trim)
+                .is_none_or(|ip| s.ip.starts_with(ip))
+            && self.server_id.as_deref().is_none_or(|id| s.server_id == id)
+            && self.since.is_none_or(|t| s.joined_at >= t)
+            && self.visible(&s.server_id)
+    }
+}
+
+/// Append one event as a JSON line.
+pub fn append(path: &Path, event: &PlayerEvent) -> Result<(), String> {
+    crate::util::append_json_line(path, event).map_err(|e| format!("Player history: {}", e))
+}
+
+/// All events in the file, oldest first. A missing file is an empty history; unreadable lines
+/// are skipped.
+pub fn read_events(path: &Path) -> Result<Vec<PlayerEvent>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!(
+            "Failed to read player history {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Pair joins with the following leave of the same player (name and IP) on the same server.
+/// A second join without a leave in between closes the first session with no leave time.
+pub fn sessions(events: &[PlayerEvent]) -> Vec<PlayerSession> {
+    let mut out: Vec<PlayerSession> = Vec::new();
+    let mut open: HashMap<(&str, &str, &str), usize> = HashMap::new();
+    for e in events {
+        let key = (e.server_id.as_str(), e.name.as_str(), e.ip.as_str());
+        match e.kind {
+            EventKind::Join => {
+                open.insert(key, out.len());
+                out.push(PlayerSession {
+                    server_id: e.server_id.clone(),
+                    server_name: e.server_name.clone(),
+                    name: e.name.clone(),
+                    ip: e.ip.clone(),
+                    joined_at: e.timestamp,
+                    left_at: None,
+                    duration_seconds: None,
+                    kick_reason: None,
+                });
+            }
+            EventKind::Kick => {
+                if let Some(&i) = open.get(&key) {
+                    out[i].kick_reason = e.reason.clone();
+                }
+            }
+            EventKind::Leave => {
+                if let Some(i) = open.remove(&key) {
+                    let session = &mut out[i];
+                    session.left_at = Some(e.timestamp);
+                    session.duration_seconds = Some(e.timestamp.saturating_sub(session.joined_at));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Sessions matching `query`, plus the names that shared their IPs.
+pub fn search(path: &Path, query: &HistoryQuery) -> Result<SearchResult, String> {
+    let all = sessions(&read_events(path)?);
+    let mut matched: Vec<PlayerSession> =
+        all.iter().filter(|s| query.matches(s)).cloned().collect();
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    matched.drain(..matched.len().saturating_sub(limit));
+
+    let ips: HashSet<&str> = matched.iter().map(|s| s.ip.as_str()).collect();
+    let mut by_ip: BTreeMap<&str, BTreeMap<&str, (usize, u64)>> = BTreeMap::new();
+    for s in all
+        .iter()
+        .filter(|s| ips.contains(s.ip.as_str()) && query.visible(&s.server_id))
+    {
+        let seen = s.left_at.unwrap_or(s.joined_at);
+        let entry = by_ip
+            .entry(s.ip.as_str())
+            .or_default()
+            .entry(s.name.as_str())
+            .or_default();
+        entry.0 += 1;
+        entry.1 = entry.1.max(seen);
+    }
+    let aliases = by_ip
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .flat_map(|(ip, names)| {
+            names
+                .into_iter()
+                .map(move |(name, (sessions, last_seen))| Alias {
+                    ip: ip.to_string(),
+                    name: name.to_string(),
+                    sessions,
+                    last_seen,
+                })
+        })
+        .collect();
+    Ok(SearchResult {
+        sessions: matched,
+        aliases,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_dir;
+
+    fn event(ts: u64, kind: EventKind, server: &str, name: &str, ip: &str) -> PlayerEvent {
+        let mut e = PlayerEvent::new(kind, server, "Main", name, ip);
+        e.timestamp = ts;
+        e
+    }
+
+    #[test]
+    fn events_pair_into_sessions() {
+        let events = vec![
+            event(100, EventKind::Join, "a1", "Griefer", "10.0.0.5"),
+            event(110, EventKind::Join, "a1", "Pilot", "10.0.0.9"),
+            event(150, EventKind::Kick, "a1", "Griefer", "10.0.0.5").reason("ban list"),
+            event(152, EventKind::Leave, "a1", "Griefer", "10.0.0.5"),
+            event(200, EventKind::Join, "a1", "Pilot", "10.0.0.9"),
+        ];
+        let s = sessions(&events);
+        assert_eq!(s.len(), 3);
+        assert_eq!(s[0].duration_seconds, Some(52));
+        assert_eq!(s[0].kick_reason.as_deref(), Some("ban list"));
+        assert_eq!(s[1].left_at, None);
+        assert_eq!(s[2].joined_at, 200);
+    }
+
+    #[test]
+    fn search_finds_names_sharing_an_ip() {
+        let dir = test_dir("history");
+        let path = dir.join("history.jsonl");
+        assert_eq!(
+            search(&path, &HistoryQuery::default()).unwrap(),
+            SearchResult::default()
+        );
+        for e in [
+            event(100, EventKind::Join, "a1", "Griefer", "10.0.0.5"),
+            event(160, EventKind::Leave, "a1", "Griefer", "10.0.0.5"),
+            event(300, EventKind::Join, "b2", "NewName", "10.0.0.5"),
+            event(400, EventKind::Join, "a1", "Pilot", "10.0.0.9"),
+        ] {
+            append(&path, &e).unwrap();
+        }
+
+        let by_name = HistoryQuery {
+            name: Some("grief".to_string()),
+            ..HistoryQuery::default()
+        };
+        let result = search(&path, &by_name).unwrap();
+        assert_eq!(result.sessions.len(), 1);
+        let names: Vec<&str> = result.aliases.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Griefer", "NewName"]);
+        assert_eq!(result.aliases[0].last_seen, 160);
+
+        let by_ip = HistoryQuery {
+            ip: Some("10.0.0.".to_string()),
+            visible_servers: Some(["a1".to_string()].into_iter().collect()),
+            ..HistoryQuery::default()
+        };
+        let result = search(&path, &by_ip).unwrap();
+        let names: Vec<&str> = result.sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Griefer", "Pilot"]);
+        assert!(result.aliases.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
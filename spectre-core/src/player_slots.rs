@@ -0,0 +1,242 @@
+//! The DS player slot table: where it is in DS memory and how a slot is laid out, and a
+//! [`PlayerSource`] that yields the connected players.
+//!
+//! Only the `HD2DS.exe` layout is known; Sabre Squadron servers are read with it too. Parsing
+//! works on a plain byte buffer, so a table captured from a running server can be replayed with
+//! [`BufferSource`] without a DS, e.g. to confirm the ping and activity offsets.
+
+use crate::auto_kick::PlayerSample;
+use crate::server::Server;
+
+/// Pings above this are not believed (a layout that does not match the DS).
+pub const PING_MAX_PLAUSIBLE: u32 = 10_000;
+
+/// Where a DS keeps its player slot table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLayout {
+    pub name: &'static str,
+    /// Address of the pointer to the slot table.
+    pub pointer_addr: u32,
+    pub slot_count: usize,
+    /// Bytes per slot.
+    pub stride: usize,
+    /// IPv4 address, 4 bytes in network order.
+    pub ip_offset: usize,
+    /// NUL-terminated name of at most `name_len` bytes; empty for a free slot.
+    pub name_offset: usize,
+    pub name_len: usize,
+    /// Ping in ms, u32 little-endian; None while the offset is not confirmed.
+    pub ping_offset: Option<usize>,
+    /// A u32 the DS bumps on player input; None while the offset is not confirmed.
+    pub activity_offset: Option<usize>,
+}
+
+impl SlotLayout {
+    /// Hidden & Dangerous 2 (`HD2DS.exe`). Ping and activity are not read: their offsets have not
+    /// been confirmed from a captured table, so auto-kick leaves both limits to the DS.
+    pub const HD2DS: SlotLayout = SlotLayout {
+        name: "HD2DS",
+        pointer_addr: 0x009D6A4C + 4,
+        slot_count: 32,
+        stride: 196,
+        ip_offset: 4,
+        name_offset: 8,
+        // The rest of the slot, as names were always read.
+        name_len: 188,
+        ping_offset: None,
+        activity_offset: None,
+    };
+
+    /// The layout to read `server`'s DS with. Only HD2DS is known, so Sabre Squadron servers are
+    /// read with it too.
+    pub fn for_server(_server: &Server) -> &'static SlotLayout {
+        &SlotLayout::HD2DS
+    }
+
+    /// Size of the whole table in bytes.
+    pub fn table_len(&self) -> usize {
+        self.slot_count * self.stride
+    }
+
+    /// Every slot of a table read from DS memory, free ones included (with an empty name).
+    /// A short buffer yields only the slots it holds completely.
+    pub fn parse(&self, buffer: &[u8]) -> Vec<PlayerSlot> {
+        buffer
+            .chunks_exact(self.stride)
+            .take(self.slot_count)
+            .map(|slot| self.parse_slot(slot))
+            .collect()
+    }
+
+    fn parse_slot(&self, slot: &[u8]) -> PlayerSlot {
+        let ip = match slot.get(self.ip_offset..self.ip_offset + 4) {
+            Some(b) => format!("{}.{}.{}.{}", b[0], b[1], b[2], b[3]),
+            None => String::new(),
+        };
+        let name_end = (self.name_offset + self.name_len).min(slot.len());
+        let name_bytes = slot.get(self.name_offset..name_end).unwrap_or_default();
+        let nul = name_bytes
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(name_bytes.len());
+        let name = String::from_utf8_lossy(&name_bytes[..nul])
+            .trim()
+            .to_string();
+        let ping = self.ping_offset.and_then(|o| read_u32(slot, o));
+        PlayerSlot {
+            name,
+            ip,
+            sample: PlayerSample {
+                ping: ping.filter(|p| (1..=PING_MAX_PLAUSIBLE).contains(p)),
+                activity: self.activity_offset.and_then(|o| read_u32(slot, o)),
+            },
+        }
+    }
+}
+
+fn read_u32(slot: &[u8], offset: usize) -> Option<u32> {
+    let bytes = slot.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerSlot {
+    /// Empty for a free slot.
+    pub name: String,
+    pub ip: String,
+    pub sample: PlayerSample,
+}
+
+impl PlayerSlot {
+    pub fn is_free(&self) -> bool {
+        self.name.is_empty()
+    }
+}
+
+/// Where the helper gets the player slots of one server.
+pub trait PlayerSource {
+    /// All slots in table order, free ones included.
+    fn read_slots(&mut self) -> Result<Vec<PlayerSlot>, String>;
+
+    /// How the slots are read; decides which auto-kick limits can be enforced.
+    fn layout(&self) -> &SlotLayout;
+}
+
+/// A slot table already in memory, e.g. captured from a running server.
+#[derive(Debug, Clone)]
+pub struct BufferSource {
+    pub layout: SlotLayout,
+    pub buffer: Vec<u8>,
+}
+
+impl BufferSource {
+    pub fn new(layout: SlotLayout, buffer: Vec<u8>) -> Self {
+        Self { layout, buffer }
+    }
+}
+
+impl PlayerSource for BufferSource {
+    fn read_slots(&mut self) -> Result<Vec<PlayerSlot>, String> {
+        if self.buffer.len() < self.layout.table_len() {
+            return Err(format!(
+                "{} slot table needs {} bytes, got {}",
+                self.layout.name,
+                self.layout.table_len(),
+                self.buffer.len()
+            ));
+        }
+        Ok(self.layout.parse(&self.buffer))
+    }
+
+    fn layout(&self) -> &SlotLayout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A layout with ping and activity at the end of the slot, to test sample parsing; not a
+    /// claim about where any DS keeps them.
+    const WITH_SAMPLES: SlotLayout = SlotLayout {
+        name: "test",
+        name_len: 180,
+        ping_offset: Some(188),
+        activity_offset: Some(192),
+        ..SlotLayout::HD2DS
+    };
+
+    /// A table laid out as `layout` says, with `players` in the first slots. Ping and activity
+    /// are only written where the layout has them.
+    fn table(layout: &SlotLayout, players: &[(&str, [u8; 4], u32, u32)]) -> Vec<u8> {
+        let mut buffer = vec![0u8; layout.table_len()];
+        for (i, (name, ip, ping, activity)) in players.iter().enumerate() {
+            let slot = &mut buffer[i * layout.stride..(i + 1) * layout.stride];
+            slot[layout.ip_offset..layout.ip_offset + 4].copy_from_slice(ip);
+            slot[layout.name_offset..layout.name_offset + name.len()]
+                .copy_from_slice(name.as_bytes());
+            if let Some(at) = layout.ping_offset {
+                slot[at..at + 4].copy_from_slice(&ping.to_le_bytes());
+            }
+            if let Some(at) = layout.activity_offset {
+                slot[at..at + 4].copy_from_slice(&activity.to_le_bytes());
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn parses_slots_from_raw_table() {
+        let layout = SlotLayout::HD2DS;
+        let buffer = table(
+            &layout,
+            &[
+                ("Pilot", [203, 0, 113, 7], 85, 4),
+                (" [ABC]Sniper ", [10, 0, 0, 2], 0, 9),
+            ],
+        );
+        let slots = BufferSource::new(layout, buffer.clone())
+            .read_slots()
+            .unwrap();
+        assert_eq!(slots.len(), 32);
+        assert_eq!(slots[0].name, "Pilot");
+        assert_eq!(slots[0].ip, "203.0.113.7");
+        assert_eq!(slots[1].name, "[ABC]Sniper");
+        assert!(slots[2].is_free());
+        assert_eq!(slots[2].ip, "0.0.0.0");
+        // Ping and activity are unconfirmed for HD2DS, so nothing is sampled.
+        assert!(slots.iter().all(|s| s.sample == PlayerSample::default()));
+
+        // A name filling its whole field has no NUL and is cut at `name_len`.
+        let mut full = buffer;
+        full[layout.name_offset..layout.name_offset + layout.name_len].fill(b'x');
+        let slots = layout.parse(&full);
+        assert_eq!(slots[0].name.len(), layout.name_len);
+    }
+
+    #[test]
+    fn samples_and_short_tables() {
+        let layout = WITH_SAMPLES;
+        let buffer = table(
+            &layout,
+            &[
+                ("Pilot", [1, 2, 3, 4], 85, 4),
+                ("Idle", [1, 2, 3, 5], 0, 9),
+                ("Lagger", [192, 0, 2, 1], 4_000_000, 1),
+            ],
+        );
+        let slots = layout.parse(&buffer);
+        assert_eq!(slots[0].sample.ping, Some(85));
+        assert_eq!(slots[0].sample.activity, Some(4));
+        // Unset and implausible pings are unknown rather than wrong.
+        assert_eq!(slots[1].sample.ping, None);
+        assert_eq!(slots[2].sample.ping, None);
+        assert_eq!(slots[2].sample.activity, Some(1));
+
+        assert_eq!(layout.parse(&buffer[..layout.stride * 2 + 5]).len(), 2);
+        assert!(BufferSource::new(layout, buffer[..100].to_vec())
+            .read_slots()
+            .is_err());
+    }
+}
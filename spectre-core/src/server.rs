@@ -1,7 +1,29 @@
+use crate::atomic_file;
+use crate::auth::{self, Privilege};
+use crate::bans::{self, BanEntry};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Mutex;
+
+/// Schema version written to hd2_server_config.json. Bump when the on-disk shape changes
+/// and append a step to `MIGRATIONS`.
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Serializes read-modify-write cycles on config files within this process (UI, HTTP and
+/// watchdog threads all go through `ServerLauncherData::update_file`).
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+type Migration = fn(&mut serde_json::Value) -> Result<(), String>;
+
+/// Migration steps; `MIGRATIONS[n]` upgrades a schema `n` document to schema `n + 1`.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerManager {
@@ -9,19 +31,98 @@ pub struct ServerManager {
     #[serde(default)]
     pub restart_interval_days: u32,
     pub enable_forced_ban_list: bool,
-    pub forced_ban_list: Vec<String>,
-    /// Rotate (clear) app log file after this many days to save space. 0 = no rotation.
+    pub forced_ban_list: Vec<BanEntry>,
+    /// Archive the app log and start a new file after this many days. 0 = no time limit.
     #[serde(default)]
     pub log_rotation_days: u32,
+    /// Archive the app log once it reaches this size. 0 = no size limit.
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u32,
+    /// Archived app logs kept as `<file>.1` .. `<file>.N`.
+    #[serde(default = "default_log_archive_count")]
+    pub log_archive_count: u32,
+    /// Records below this level are not written.
+    #[serde(default)]
+    pub log_level: crate::logging::Level,
+    /// Where crash, restart, kick and full/empty notifications are POSTed.
+    #[serde(default)]
+    pub webhooks: Vec<crate::notify::Webhook>,
+    /// Shared ban lists enforced with `forced_ban_list` (see `ban_subscriptions`).
+    #[serde(default)]
+    pub ban_list_subscriptions: Vec<crate::ban_subscriptions::BanListSubscription>,
+    /// Previous versions of the config file kept as `<file>.1` .. `<file>.N`. 0 = no backups.
+    #[serde(default = "default_config_backup_count")]
+    pub config_backup_count: u32,
+}
+
+fn default_config_backup_count() -> u32 {
+    5
+}
+
+fn default_log_max_size_mb() -> u32 {
+    10
+}
+
+fn default_log_archive_count() -> u32 {
+    5
+}
+
+fn default_auto_kick_grace() -> u16 {
+    30
+}
+
+fn default_message_interval() -> u32 {
+    300
+}
+
+fn default_restart_warning() -> String {
+    "Server restarts in {next_restart}.".to_string()
+}
+
+fn default_restart_warning_minutes() -> Vec<u32> {
+    vec![10, 1]
+}
+
+impl ServerManager {
+    /// Cut webhook URLs and URL ban list subscription sources down to their host, since they
+    /// usually carry a token: for users who cannot edit them and for the audit log.
+    pub fn redact_urls(&mut self) {
+        for webhook in self.webhooks.iter_mut() {
+            webhook.url = crate::notify::redact_url(&webhook.url);
+        }
+        for subscription in self.ban_list_subscriptions.iter_mut() {
+            subscription.source = subscription.redacted_source();
+        }
+    }
+
+    pub fn log_rotation(&self) -> crate::logging::RotationPolicy {
+        crate::logging::RotationPolicy {
+            max_bytes: self.log_max_size_mb as u64 * 1024 * 1024,
+            max_age_days: self.log_rotation_days,
+            archives: self.log_archive_count,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub username: String,
-    pub password: String,
+    /// Argon2 PHC string (see `auth::hash_password`); empty means the user cannot log in.
+    #[serde(default)]
+    pub password_hash: String,
     pub privilege_level: u8,
 }
 
+impl User {
+    pub fn privilege(&self) -> Privilege {
+        Privilege::from_level(self.privilege_level)
+    }
+
+    pub fn can_log_in(&self) -> bool {
+        !self.password_hash.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ServerConfig {
@@ -54,25 +155,55 @@ pub struct ServerConfig {
     pub max_inactivity: u16,
     pub voice_chat: u8,
     pub maps: Vec<String>,
+    /// Announcements sent in turn every `message_interval` seconds (see `messages`).
     pub messages: Vec<String>,
+    #[serde(default = "default_message_interval")]
+    pub message_interval: u32,
+    /// Sent when a player joins; empty = no welcome.
+    #[serde(default)]
+    pub welcome_message: String,
+    /// Sent `restart_warning_minutes` before a scheduled restart; empty = no warning.
+    #[serde(default = "default_restart_warning")]
+    pub restart_warning: String,
+    #[serde(default = "default_restart_warning_minutes")]
+    pub restart_warning_minutes: Vec<u32>,
     #[serde(alias = "banList")]
-    pub ban_list: Vec<String>,
+    pub ban_list: Vec<BanEntry>,
     #[serde(default)]
     pub enable_whitelist: bool,
     #[serde(default)]
     pub whitelist: Vec<String>,
     pub enable_auto_kick: bool,
+    /// Seconds between the auto-kick warning and the kick.
+    #[serde(default = "default_auto_kick_grace")]
+    pub auto_kick_grace: u16,
+    /// Kicks players whose name matches a rule, checked after the IP ban lists.
+    #[serde(default)]
+    pub name_ban_list: Vec<crate::name_match::NameRule>,
     pub clan_tag: String,
     pub clan_side: String,
     pub clan_reserve: u8,
+    /// Kick players wearing `clan_tag` who are not on `clan_roster`.
+    #[serde(default)]
+    pub enforce_clan_tag: bool,
+    /// Clan member names, with or without the tag.
+    #[serde(default)]
+    pub clan_roster: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Server {
+    /// Stable identifier (UUID) used by clients and runtime state; assigned on first save
+    /// and never changes, unlike name, port or position in the list.
+    #[serde(default)]
+    pub id: String,
     pub name: String,
     pub running: bool,
     pub watchdog: bool,
+    /// Send the current profile's scheduled messages (see `messages`).
     pub messages: bool,
+    /// Usernames that may see and control this server in the Server Utility; empty means
+    /// admins only. Admins always have access.
     pub users: Vec<String>,
     pub port: u16,
     pub use_sabre_squadron: bool,
@@ -88,8 +219,59 @@ pub struct Server {
     pub configs: Vec<ServerConfig>,
 }
 
+/// What `ServerLauncherData::upgrade_file` did.
+#[derive(Debug, Default)]
+pub struct Upgrade {
+    /// The file was on an older schema and has been rewritten.
+    pub rewritten: bool,
+    /// What the next save will refuse until it is fixed, e.g. two servers on one port.
+    pub problems: Vec<String>,
+}
+
+/// Why `ServerLauncherData::update_file` did not save.
+#[derive(Debug)]
+pub enum UpdateError {
+    /// The file changed since the caller loaded it; holds what is on disk now.
+    Conflict(Box<ServerLauncherData>),
+    /// The change names a server that is not (or no longer) in the file.
+    NotFound(String),
+    /// The change was refused, e.g. two servers on one port.
+    Invalid(String),
+    /// The file could not be read or written.
+    Io(String),
+}
+
+/// Errors from the `update_file` closure are refusals unless it says otherwise.
+impl From<String> for UpdateError {
+    fn from(e: String) -> Self {
+        UpdateError::Invalid(e)
+    }
+}
+
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Conflict(current) => write!(
+                f,
+                "Config was changed elsewhere (now at revision {})",
+                current.revision
+            ),
+            UpdateError::NotFound(e) | UpdateError::Invalid(e) | UpdateError::Io(e) => {
+                f.write_str(e)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerLauncherData {
+    /// Missing in files written before versioning (treated as 0).
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Incremented on every save through `update_file`; clients echo the revision they
+    /// loaded so a save based on stale data is rejected instead of clobbering newer edits.
+    #[serde(default)]
+    pub revision: u64,
     pub server_manager: ServerManager,
     pub users: Vec<User>,
     pub servers: Vec<Server>,
@@ -103,6 +285,12 @@ impl Default for ServerManager {
             enable_forced_ban_list: true,
             forced_ban_list: Vec::new(),
             log_rotation_days: 0,
+            log_max_size_mb: default_log_max_size_mb(),
+            log_archive_count: default_log_archive_count(),
+            log_level: crate::logging::Level::Info,
+            webhooks: Vec::new(),
+            ban_list_subscriptions: Vec::new(),
+            config_backup_count: default_config_backup_count(),
         }
     }
 }
@@ -140,13 +328,21 @@ impl Default for ServerConfig {
             voice_chat: 0,
             maps: vec!["Alps3".to_string()],
             messages: Vec::new(),
+            message_interval: default_message_interval(),
+            welcome_message: String::new(),
+            restart_warning: default_restart_warning(),
+            restart_warning_minutes: default_restart_warning_minutes(),
             ban_list: Vec::new(),
             enable_whitelist: false,
             whitelist: Vec::new(),
             enable_auto_kick: false,
+            auto_kick_grace: default_auto_kick_grace(),
+            name_ban_list: Vec::new(),
             clan_tag: String::new(),
             clan_side: "axis".to_string(),
             clan_reserve: 0,
+            enforce_clan_tag: false,
+            clan_roster: Vec::new(),
         }
     }
 }
@@ -154,6 +350,7 @@ impl Default for ServerConfig {
 impl Default for Server {
     fn default() -> Self {
         Self {
+            id: String::new(),
             name: String::new(),
             running: false,
             watchdog: false,
@@ -171,31 +368,544 @@ impl Default for Server {
     }
 }
 
+/// A new random server ID.
+pub fn new_server_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl Server {
+    /// Admins see every server; other users only the servers that list them.
+    pub fn is_accessible_by(&self, user: &User) -> bool {
+        user.privilege() == Privilege::Admin
+            || self
+                .users
+                .iter()
+                .any(|u| u.trim().eq_ignore_ascii_case(user.username.trim()))
+    }
+}
+
 impl ServerLauncherData {
     /// Load config from JSON; default if missing.
-    /// Migrates legacy manager-level paths onto each server if the server's paths are empty.
+    /// Older schema versions are migrated in memory (see `migrate`); an unreadable or
+    /// unparsable file is an error so callers never save defaults over it.
     pub fn load_from_file(path: &Path) -> Result<Self, String> {
         if !path.exists() {
             return Ok(Self::default());
         }
         let content =
             fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
-        let data: ServerLauncherData =
-            serde_json::from_str(&content).map_err(|e| format!("Invalid config JSON: {}", e))?;
-        Ok(data)
+        Self::from_json(&content).map_err(|e| format!("{} ({})", e, path.display()))
+    }
+
+    /// Parse config JSON of any known schema version.
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("Invalid config JSON: {}", e))?;
+        let value = migrate(value)?;
+        serde_json::from_value(value).map_err(|e| format!("Invalid config JSON: {}", e))
     }
 
-    /// Save config as pretty-printed JSON.
+    /// Save config as pretty-printed JSON. The write is atomic and the previous file is
+    /// kept as a rotating backup (`server_manager.config_backup_count`).
     pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
         let content = serde_json::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
-        fs::write(path, content).map_err(|e| format!("Failed to write config file: {}", e))
+        atomic_file::write_with_backups(
+            path,
+            content.as_bytes(),
+            self.server_manager.config_backup_count as usize,
+        )
+        .map_err(|e| format!("Failed to write config file: {}", e))
+    }
+
+    /// Load, modify and save the config file as one step, bumping `revision`.
+    /// With `expected_revision` set, the save is refused with `UpdateError::Conflict` if the
+    /// file is no longer at that revision (another tab or the app saved in between).
+    pub fn update_file<F>(
+        path: &Path,
+        expected_revision: Option<u64>,
+        apply: F,
+    ) -> Result<Self, UpdateError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), UpdateError>,
+    {
+        let _guard = UPDATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut data = Self::load_from_file(path).map_err(UpdateError::Io)?;
+        if let Some(expected) = expected_revision {
+            if expected != data.revision {
+                return Err(UpdateError::Conflict(Box::new(data)));
+            }
+        }
+        let next_revision = data.revision + 1;
+        apply(&mut data)?;
+        data.validate_ports().map_err(UpdateError::Invalid)?;
+        data.validate_name_rules().map_err(UpdateError::Invalid)?;
+        data.validate_clan_reserve().map_err(UpdateError::Invalid)?;
+        data.assign_server_ids();
+        data.schema_version = SCHEMA_VERSION;
+        data.revision = next_revision;
+        data.save_to_file(path).map_err(UpdateError::Io)?;
+        Ok(data)
+    }
+
+    /// Restore backup `n` (1 = most recent) over the config file. The backup must parse;
+    /// the config it replaces is itself kept as the newest backup, so a restore can be undone.
+    pub fn restore_backup(path: &Path, n: usize) -> Result<Self, String> {
+        let backup = atomic_file::backup_path(path, n);
+        let content = fs::read_to_string(&backup)
+            .map_err(|e| format!("No backup {} to restore ({})", backup.display(), e))?;
+        let restored =
+            Self::from_json(&content).map_err(|e| format!("{} ({})", e, backup.display()))?;
+        Self::update_file(path, None, |data| {
+            *data = restored;
+            Ok(())
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    /// Rewrite the file if it is on an older schema, so migrations that must not stay
+    /// in memory only (e.g. plaintext passwords, new server IDs) are persisted.
+    pub fn upgrade_file(path: &Path) -> Result<Upgrade, String> {
+        if !path.exists() {
+            return Ok(Upgrade::default());
+        }
+        let _guard = UPDATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
+        let version = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|v| v.get("schema_version").and_then(|v| v.as_u64()))
+            .unwrap_or(0);
+        if version >= SCHEMA_VERSION as u64 {
+            return Ok(Upgrade::default());
+        }
+        // Not through `update_file`: a legacy file it would refuse (two servers on one port)
+        // must still be migrated, or the IDs given to its servers would change on every load.
+        let mut data = Self::from_json(&content)?;
+        data.assign_server_ids();
+        data.schema_version = SCHEMA_VERSION;
+        data.revision += 1;
+        data.save_to_file(path)?;
+        let problems = [
+            data.validate_ports(),
+            data.validate_name_rules(),
+            data.validate_clan_reserve(),
+        ]
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+        Ok(Upgrade {
+            rewritten: true,
+            problems,
+        })
+    }
+
+    pub fn server_by_id(&self, id: &str) -> Option<&Server> {
+        if id.is_empty() {
+            return None;
+        }
+        self.servers.iter().find(|s| s.id == id)
+    }
+
+    /// Each server needs its own game port; two DS processes cannot share one.
+    pub fn validate_ports(&self) -> Result<(), String> {
+        let mut conflicts = Vec::new();
+        for (i, server) in self.servers.iter().enumerate() {
+            if let Some(other) = self.servers[..i].iter().find(|s| s.port == server.port) {
+                conflicts.push(format!(
+                    "\"{}\" and \"{}\" both use port {}",
+                    other.name, server.name, server.port
+                ));
+            }
+        }
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Port conflict: {}", conflicts.join("; ")))
+        }
+    }
+
+    /// True if any ban list holds an entry that expired by `now`.
+    pub fn has_expired_bans(&self, now: u64) -> bool {
+        self.server_manager
+            .forced_ban_list
+            .iter()
+            .chain(
+                self.servers
+                    .iter()
+                    .flat_map(|s| s.configs.iter().flat_map(|c| c.ban_list.iter())),
+            )
+            .any(|e| e.is_expired(now))
+    }
+
+    /// Drop expired bans from every list. Returns each removed entry with where it was
+    /// ("forced ban list" or "<server> / <config>").
+    pub fn prune_expired_bans(&mut self, now: u64) -> Vec<(String, BanEntry)> {
+        let mut removed: Vec<(String, BanEntry)> =
+            bans::prune_expired(&mut self.server_manager.forced_ban_list, now)
+                .into_iter()
+                .map(|e| ("forced ban list".to_string(), e))
+                .collect();
+        for server in self.servers.iter_mut() {
+            for config in server.configs.iter_mut() {
+                let scope = format!("{} / {}", server.name, config.name);
+                removed.extend(
+                    bans::prune_expired(&mut config.ban_list, now)
+                        .into_iter()
+                        .map(|e| (scope.clone(), e)),
+                );
+            }
+        }
+        removed
+    }
+
+    /// Record `actor` as the author of bans added since `before` (see `bans::stamp_new`).
+    pub fn stamp_new_bans(&mut self, before: &ServerLauncherData, actor: &str) {
+        let now = crate::util::unix_now();
+        bans::stamp_new(
+            &mut self.server_manager.forced_ban_list,
+            &before.server_manager.forced_ban_list,
+            actor,
+            now,
+        );
+        for server in self.servers.iter_mut() {
+            for config in server.configs.iter_mut() {
+                let old = before
+                    .server_by_id(&server.id)
+                    .and_then(|s| s.configs.iter().find(|c| c.name == config.name))
+                    .map(|c| c.ban_list.as_slice())
+                    .unwrap_or_default();
+                bans::stamp_new(&mut config.ban_list, old, actor, now);
+            }
+        }
+    }
+
+    /// Every name rule must have a pattern, and regex rules must compile.
+    pub fn validate_name_rules(&self) -> Result<(), String> {
+        for server in &self.servers {
+            for config in &server.configs {
+                for rule in &config.name_ban_list {
+                    rule.validate()
+                        .map_err(|e| format!("{} / {}: {}", server.name, config.name, e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Clan reserved slots must leave at least one slot for everyone else; otherwise every
+    /// non-member would be kicked on every poll.
+    pub fn validate_clan_reserve(&self) -> Result<(), String> {
+        for server in &self.servers {
+            for config in &server.configs {
+                if config.clan_reserve > 0 && config.clan_reserve >= config.max_clients {
+                    return Err(format!(
+                        "{} / {}: {} clan reserved slots need more than {} max clients",
+                        server.name, config.name, config.clan_reserve, config.max_clients
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Give servers added by a client (no ID yet, or a duplicate from copying one) a fresh ID.
+    pub fn assign_server_ids(&mut self) {
+        let mut seen = std::collections::HashSet::new();
+        for server in self.servers.iter_mut() {
+            if server.id.trim().is_empty() || !seen.insert(server.id.clone()) {
+                server.id = new_server_id();
+                seen.insert(server.id.clone());
+            }
+        }
+    }
+
+    /// The user with this name (case-insensitive) if `password` matches.
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<&User> {
+        self.find_user(username)
+            .filter(|u| auth::verify_password(&u.password_hash, password))
+    }
+
+    pub fn find_user(&self, username: &str) -> Option<&User> {
+        let username = username.trim();
+        self.users
+            .iter()
+            .find(|u| u.username.eq_ignore_ascii_case(username))
+    }
+
+    /// Add or update a user. `password` of `None` keeps the current hash; `Some("")` clears
+    /// it (the user can no longer log in). The last admin, and the last admin who can log in,
+    /// cannot be demoted or have their password cleared.
+    pub fn set_user(
+        &mut self,
+        username: &str,
+        password: Option<&str>,
+        privilege_level: u8,
+    ) -> Result<(), String> {
+        let username = username.trim();
+        if username.is_empty() {
+            return Err("Username is required".to_string());
+        }
+        let password_hash = match password {
+            Some("") => Some(String::new()),
+            Some(p) => Some(auth::hash_password(p)?),
+            None => None,
+        };
+        let privilege = Privilege::from_level(privilege_level);
+        match self
+            .users
+            .iter()
+            .position(|u| u.username.eq_ignore_ascii_case(username))
+        {
+            Some(i) => {
+                let current = &self.users[i];
+                let is_admin = current.privilege() == Privilege::Admin;
+                let keeps_login = password_hash
+                    .as_ref()
+                    .map_or(current.can_log_in(), |h| !h.is_empty());
+                if privilege < Privilege::Admin && is_admin && self.admin_total() == 1 {
+                    return Err("Cannot demote the last admin".to_string());
+                }
+                if is_admin
+                    && current.can_log_in()
+                    && (privilege < Privilege::Admin || !keeps_login)
+                    && self.admin_count() == 1
+                {
+                    return Err("Cannot lock out the last admin who can log in".to_string());
+                }
+                let user = &mut self.users[i];
+                user.privilege_level = privilege.level();
+                if let Some(hash) = password_hash {
+                    user.password_hash = hash;
+                }
+            }
+            None => self.users.push(User {
+                username: username.to_string(),
+                password_hash: password_hash.unwrap_or_default(),
+                privilege_level: privilege.level(),
+            }),
+        }
+        Ok(())
+    }
+
+    /// Remove a user. The last admin, and the last admin who can log in, cannot be removed.
+    pub fn remove_user(&mut self, username: &str) -> Result<(), String> {
+        let username = username.trim();
+        let i = self
+            .users
+            .iter()
+            .position(|u| u.username.eq_ignore_ascii_case(username))
+            .ok_or_else(|| format!("No user named {}", username))?;
+        let user = &self.users[i];
+        if user.privilege() == Privilege::Admin
+            && (self.admin_total() == 1 || (user.can_log_in() && self.admin_count() == 1))
+        {
+            return Err("Cannot remove the last admin".to_string());
+        }
+        self.users.remove(i);
+        Ok(())
+    }
+
+    /// Replace the servers `user` can access with `servers`, keeping the ones hidden from
+    /// them in place, so a scoped client saving its view cannot drop other users' servers.
+    pub fn replace_servers_for(&mut self, user: &User, servers: Vec<Server>) {
+        let mut incoming = servers.into_iter();
+        let mut merged = Vec::new();
+        for existing in self.servers.drain(..) {
+            if existing.is_accessible_by(user) {
+                merged.extend(incoming.next());
+            } else {
+                merged.push(existing);
+            }
+        }
+        merged.extend(incoming);
+        self.servers = merged;
+    }
+
+    /// Admins that can log in. 0 for a fresh config, whose default admin has no password.
+    pub fn admin_count(&self) -> usize {
+        self.users
+            .iter()
+            .filter(|u| u.privilege() == Privilege::Admin && u.can_log_in())
+            .count()
+    }
+
+    fn admin_total(&self) -> usize {
+        self.users
+            .iter()
+            .filter(|u| u.privilege() == Privilege::Admin)
+            .count()
+    }
+}
+
+impl Default for ServerLauncherData {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            revision: 0,
+            server_manager: ServerManager::default(),
+            users: vec![User {
+                username: "Admin".to_string(),
+                password_hash: String::new(),
+                privilege_level: Privilege::Admin.level(),
+            }],
+            servers: Vec::new(),
+        }
+    }
+}
+
+/// Upgrade a raw config document to `SCHEMA_VERSION`, one step at a time.
+pub fn migrate(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !value.is_object() {
+        return Err("Invalid config JSON: expected an object at the top level".to_string());
+    }
+    let version = match value.get("schema_version") {
+        None | Some(serde_json::Value::Null) => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| format!("Invalid config JSON: schema_version {} is not a number", v))?,
+    };
+    if version > SCHEMA_VERSION as u64 {
+        return Err(format!(
+            "Config schema version {} is newer than this Spectre supports ({})",
+            version, SCHEMA_VERSION
+        ));
+    }
+    for (step, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&mut value)
+            .map_err(|e| format!("Config migration {} -> {} failed: {}", step, step + 1, e))?;
+    }
+    value["schema_version"] = serde_json::Value::from(SCHEMA_VERSION);
+    Ok(value)
+}
+
+/// Unversioned files: DS/mpmaplist paths lived on `server_manager` and were shared by all
+/// servers; copy them onto each server whose own path is empty. Ban lists may still use
+/// the `banList` key.
+fn migrate_v0_to_v1(value: &mut serde_json::Value) -> Result<(), String> {
+    const LEGACY_PATH_KEYS: [&str; 3] =
+        ["hd2ds_path", "hd2ds_sabresquadron_path", "mpmaplist_path"];
+    let mut legacy_paths = Vec::new();
+    if let Some(manager) = value
+        .get_mut("server_manager")
+        .and_then(|m| m.as_object_mut())
+    {
+        for key in LEGACY_PATH_KEYS {
+            if let Some(serde_json::Value::String(path)) = manager.remove(key) {
+                if !path.trim().is_empty() {
+                    legacy_paths.push((key, path));
+                }
+            }
+        }
+    }
+    let Some(servers) = value.get_mut("servers").and_then(|s| s.as_array_mut()) else {
+        return Ok(());
+    };
+    for server in servers {
+        let server = server
+            .as_object_mut()
+            .ok_or_else(|| "server entry is not an object".to_string())?;
+        for (key, path) in &legacy_paths {
+            let empty = server
+                .get(*key)
+                .and_then(|v| v.as_str())
+                .is_none_or(|s| s.trim().is_empty());
+            if empty {
+                server.insert((*key).to_string(), serde_json::Value::from(path.as_str()));
+            }
+        }
+        if let Some(configs) = server.get_mut("configs").and_then(|c| c.as_array_mut()) {
+            for config in configs.iter_mut().filter_map(|c| c.as_object_mut()) {
+                if let Some(list) = config.remove("banList") {
+                    config.entry("ban_list").or_insert(list);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Users had a plaintext `password`; replace it with an Argon2 `password_hash`.
+fn migrate_v1_to_v2(value: &mut serde_json::Value) -> Result<(), String> {
+    let Some(users) = value.get_mut("users").and_then(|u| u.as_array_mut()) else {
+        return Ok(());
+    };
+    for user in users {
+        let user = user
+            .as_object_mut()
+            .ok_or_else(|| "user entry is not an object".to_string())?;
+        let password = match user.remove("password") {
+            Some(serde_json::Value::String(p)) => p,
+            _ => String::new(),
+        };
+        if !user.contains_key("password_hash") {
+            let hash = if password.is_empty() {
+                String::new()
+            } else {
+                auth::hash_password(&password)?
+            };
+            user.insert("password_hash".to_string(), serde_json::Value::from(hash));
+        }
+    }
+    Ok(())
+}
+
+/// Servers had no ID and were addressed by list position.
+fn migrate_v2_to_v3(value: &mut serde_json::Value) -> Result<(), String> {
+    let Some(servers) = value.get_mut("servers").and_then(|s| s.as_array_mut()) else {
+        return Ok(());
+    };
+    for server in servers {
+        let server = server
+            .as_object_mut()
+            .ok_or_else(|| "server entry is not an object".to_string())?;
+        let has_id = server
+            .get("id")
+            .and_then(|v| v.as_str())
+            .is_some_and(|id| !id.trim().is_empty());
+        if !has_id {
+            server.insert("id".to_string(), serde_json::Value::from(new_server_id()));
+        }
     }
+    Ok(())
+}
+
+/// Ban list entries were strings (`"1.2.3.4 :> reason"`); store them as `BanEntry` objects.
+fn migrate_v3_to_v4(value: &mut serde_json::Value) -> Result<(), String> {
+    fn convert(list: Option<&mut serde_json::Value>) -> Result<(), String> {
+        let Some(list) = list.and_then(|l| l.as_array_mut()) else {
+            return Ok(());
+        };
+        for item in list.iter_mut() {
+            if let Some(text) = item.as_str() {
+                *item =
+                    serde_json::to_value(BanEntry::from_legacy(text)).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+    convert(
+        value
+            .get_mut("server_manager")
+            .and_then(|m| m.get_mut("forced_ban_list")),
+    )?;
+    let Some(servers) = value.get_mut("servers").and_then(|s| s.as_array_mut()) else {
+        return Ok(());
+    };
+    for server in servers {
+        if let Some(configs) = server.get_mut("configs").and_then(|c| c.as_array_mut()) {
+            for config in configs {
+                convert(config.get_mut("ban_list"))?;
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::test_dir;
 
     #[test]
     fn default_roundtrip_json() {
@@ -203,19 +913,390 @@ mod tests {
         let json = serde_json::to_string_pretty(&data).unwrap();
         let loaded: ServerLauncherData = serde_json::from_str(&json).unwrap();
         assert_eq!(data.servers.len(), loaded.servers.len());
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
     }
-}
 
-impl Default for ServerLauncherData {
-    fn default() -> Self {
-        Self {
-            server_manager: ServerManager::default(),
-            users: vec![User {
-                username: "Admin".to_string(),
-                password: String::new(),
-                privilege_level: 2,
-            }],
-            servers: Vec::new(),
+    #[test]
+    fn v0_manager_level_paths_move_onto_servers() {
+        let json = r#"{
+            "server_manager": {
+                "enable_watchdog": true,
+                "enable_forced_ban_list": false,
+                "forced_ban_list": [],
+                "hd2ds_path": "C:\\HD2\\HD2DS.exe",
+                "mpmaplist_path": "C:\\HD2\\mpmaplist.txt"
+            },
+            "users": [],
+            "servers": [
+                {"name": "A", "running": false, "watchdog": false, "messages": false, "users": [],
+                 "port": 22000, "use_sabre_squadron": false, "current_config": "", "configs": []},
+                {"name": "B", "running": false, "watchdog": false, "messages": false, "users": [],
+                 "port": 22001, "use_sabre_squadron": false, "hd2ds_path": "D:\\Other\\HD2DS.exe",
+                 "current_config": "", "configs": []}
+            ]
+        }"#;
+        let data = ServerLauncherData::from_json(json).unwrap();
+        assert_eq!(data.schema_version, SCHEMA_VERSION);
+        assert_eq!(data.servers[0].hd2ds_path, "C:\\HD2\\HD2DS.exe");
+        assert_eq!(data.servers[0].mpmaplist_path, "C:\\HD2\\mpmaplist.txt");
+        assert_eq!(data.servers[1].hd2ds_path, "D:\\Other\\HD2DS.exe");
+        assert_eq!(data.servers[1].mpmaplist_path, "C:\\HD2\\mpmaplist.txt");
+        assert!(data.servers[1].hd2ds_sabresquadron_path.is_empty());
+    }
+
+    #[test]
+    fn v0_camel_case_ban_list_is_renamed() {
+        let json = r#"{
+            "server_manager": {"enable_watchdog": true, "enable_forced_ban_list": true, "forced_ban_list": []},
+            "users": [],
+            "servers": [
+                {"name": "A", "running": false, "watchdog": false, "messages": false, "users": [],
+                 "port": 22000, "use_sabre_squadron": true, "current_config": "Default",
+                 "configs": [{"name": "Default", "banList": ["1.2.3.4 :> griefing"]}]}
+            ]
+        }"#;
+        let value = migrate(serde_json::from_str(json).unwrap()).unwrap();
+        let config = &value["servers"][0]["configs"][0];
+        assert!(config.get("banList").is_none());
+        assert_eq!(config["ban_list"][0]["ip"], "1.2.3.4");
+        assert_eq!(config["ban_list"][0]["reason"], "griefing");
+        let data: ServerLauncherData = serde_json::from_value(value).unwrap();
+        assert_eq!(data.servers[0].configs[0].ban_list.len(), 1);
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let mut data = ServerLauncherData::default();
+        data.servers.push(Server {
+            name: "Current".to_string(),
+            hd2ds_path: "C:\\HD2\\HD2DS.exe".to_string(),
+            ..Default::default()
+        });
+        let json = serde_json::to_string(&data).unwrap();
+        let loaded = ServerLauncherData::from_json(&json).unwrap();
+        assert_eq!(loaded.servers[0].name, "Current");
+        assert_eq!(loaded.servers[0].hd2ds_path, data.servers[0].hd2ds_path);
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let json = format!(
+            r#"{{"schema_version": {}, "server_manager": {{}}, "users": [], "servers": []}}"#,
+            SCHEMA_VERSION + 1
+        );
+        let err = ServerLauncherData::from_json(&json).unwrap_err();
+        assert!(err.contains("newer"), "{}", err);
+    }
+
+    #[test]
+    fn restore_backup_swaps_in_previous_config() {
+        let dir = test_dir("restore");
+        let path = dir.join("hd2_server_config.json");
+        let mut data = ServerLauncherData::default();
+        data.servers.push(Server {
+            name: "First".to_string(),
+            ..Default::default()
+        });
+        data.save_to_file(&path).unwrap();
+        data.servers[0].name = "Second".to_string();
+        data.save_to_file(&path).unwrap();
+
+        let restored = ServerLauncherData::restore_backup(&path, 1).unwrap();
+        assert_eq!(restored.servers[0].name, "First");
+        let on_disk = ServerLauncherData::load_from_file(&path).unwrap();
+        assert_eq!(on_disk.servers[0].name, "First");
+        let undo = ServerLauncherData::restore_backup(&path, 1).unwrap();
+        assert_eq!(undo.servers[0].name, "Second");
+        assert!(undo.revision > restored.revision);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stale_revision_is_rejected() {
+        let dir = test_dir("revision");
+        let path = dir.join("hd2_server_config.json");
+        let first = ServerLauncherData::update_file(&path, Some(0), |d| {
+            d.servers.push(Server::default());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(first.revision, 1);
+
+        // A second tab still holding revision 0 must not overwrite the first save.
+        match ServerLauncherData::update_file(&path, Some(0), |d| {
+            d.servers.clear();
+            Ok(())
+        }) {
+            Err(UpdateError::Conflict(current)) => {
+                assert_eq!(current.revision, 1);
+                assert_eq!(current.servers.len(), 1);
+            }
+            other => panic!("expected conflict, got {:?}", other.map(|d| d.revision)),
+        }
+        let second = ServerLauncherData::update_file(&path, Some(1), |d| {
+            d.servers.clear();
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(second.revision, 2);
+        assert!(ServerLauncherData::load_from_file(&path)
+            .unwrap()
+            .servers
+            .is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn v1_plaintext_passwords_are_hashed() {
+        let json = r#"{
+            "schema_version": 1,
+            "server_manager": {
+                "enable_watchdog": true,
+                "enable_forced_ban_list": false,
+                "forced_ban_list": []
+            },
+            "users": [
+                {"username": "Admin", "password": "secret", "privilege_level": 2},
+                {"username": "Guest", "password": "", "privilege_level": 0}
+            ],
+            "servers": []
+        }"#;
+        let data = ServerLauncherData::from_json(json).unwrap();
+        assert!(data.users[0].password_hash.starts_with("$argon2"));
+        assert!(!serde_json::to_string(&data).unwrap().contains("secret"));
+        assert_eq!(
+            data.authenticate("admin", "secret").map(|u| u.privilege()),
+            Some(Privilege::Admin)
+        );
+        assert!(data.authenticate("Admin", "wrong").is_none());
+        assert!(!data.users[1].can_log_in());
+        assert!(data.authenticate("Guest", "").is_none());
+    }
+
+    #[test]
+    fn last_admin_is_kept() {
+        let mut data = ServerLauncherData::default();
+        assert!(data.set_user("Admin", None, 0).is_err());
+        assert!(data.remove_user("Admin").is_err());
+        data.set_user("Ops", Some("pw"), 1).unwrap();
+        data.set_user("Second", None, 2).unwrap();
+        data.remove_user("admin").unwrap();
+        assert_eq!(data.users.len(), 2);
+        assert!(data.authenticate("ops", "pw").is_some());
+        data.set_user("Ops", Some(""), 1).unwrap();
+        assert!(data.authenticate("ops", "pw").is_none());
+    }
+
+    #[test]
+    fn last_admin_who_can_log_in_is_kept() {
+        let mut data = ServerLauncherData::default();
+        assert_eq!(data.admin_count(), 0);
+        data.set_user("Ops", Some("pw"), 2).unwrap();
+        assert_eq!(data.admin_count(), 1);
+        // The default admin has no password, so Ops is the only way in.
+        assert!(data.remove_user("Ops").is_err());
+        assert!(data.set_user("Ops", None, 1).is_err());
+        assert!(data.set_user("Ops", Some(""), 2).is_err());
+        assert!(data.authenticate("Ops", "pw").is_some());
+
+        data.set_user("Admin", Some("secret"), 2).unwrap();
+        assert_eq!(data.admin_count(), 2);
+        data.remove_user("Ops").unwrap();
+        assert!(data.set_user("Admin", Some(""), 2).is_err());
+    }
+
+    #[test]
+    fn servers_are_scoped_to_listed_users() {
+        let user = |name: &str, level: u8| User {
+            username: name.to_string(),
+            password_hash: String::new(),
+            privilege_level: level,
+        };
+        let server = |name: &str, users: &[&str]| Server {
+            name: name.to_string(),
+            users: users.iter().map(|u| u.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut data = ServerLauncherData {
+            servers: vec![
+                server("Shared", &[]),
+                server("Alpha", &["alpha"]),
+                server("Bravo", &["Bravo"]),
+            ],
+            ..Default::default()
+        };
+        let alpha = user("Alpha", 1);
+        let visible: Vec<_> = data
+            .servers
+            .iter()
+            .filter(|s| s.is_accessible_by(&alpha))
+            .map(|s| s.name.as_str())
+            .collect();
+        // A server listing nobody is for admins only.
+        assert_eq!(visible, ["Alpha"]);
+        assert!(data.servers[0].is_accessible_by(&user("Root", 2)));
+        assert!(data.servers[2].is_accessible_by(&user("Root", 2)));
+
+        data.replace_servers_for(
+            &alpha,
+            vec![server("Alpha2", &["alpha"]), server("New", &["alpha"])],
+        );
+        let names: Vec<_> = data.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Shared", "Alpha2", "Bravo", "New"]);
+    }
+
+    #[test]
+    fn server_ids_are_assigned_and_kept() {
+        let json = r#"{
+            "schema_version": 2,
+            "server_manager": {
+                "enable_watchdog": true,
+                "enable_forced_ban_list": false,
+                "forced_ban_list": []
+            },
+            "users": [],
+            "servers": [
+                {"name": "A", "running": false, "watchdog": false, "messages": false, "users": [],
+                 "port": 22000, "use_sabre_squadron": false, "current_config": "", "configs": []}
+            ]
+        }"#;
+        let mut data = ServerLauncherData::from_json(json).unwrap();
+        let id = data.servers[0].id.clone();
+        assert_eq!(id.len(), 36);
+        assert_eq!(data.server_by_id(&id).map(|s| s.name.as_str()), Some("A"));
+
+        let copy = data.servers[0].clone();
+        data.servers.push(copy);
+        data.servers.push(Server::default());
+        data.assign_server_ids();
+        assert_eq!(data.servers[0].id, id);
+        assert_ne!(data.servers[1].id, id);
+        assert!(!data.servers[2].id.is_empty());
+        assert!(data.server_by_id("").is_none());
+    }
+
+    #[test]
+    fn duplicate_ports_are_rejected_on_save() {
+        let dir = test_dir("port");
+        let path = dir.join("hd2_server_config.json");
+        let server = |name: &str, port: u16| Server {
+            name: name.to_string(),
+            port,
+            ..Server::default()
+        };
+        ServerLauncherData::update_file(&path, None, |d| {
+            d.servers = vec![server("A", 22000), server("B", 22001)];
+            Ok(())
+        })
+        .unwrap();
+
+        match ServerLauncherData::update_file(&path, None, |d| {
+            d.servers.push(server("C", 22001));
+            Ok(())
+        }) {
+            Err(UpdateError::Invalid(e)) => {
+                assert!(e.contains("\"B\" and \"C\" both use port 22001"), "{}", e)
+            }
+            other => panic!(
+                "expected port conflict, got {:?}",
+                other.map(|d| d.revision)
+            ),
         }
+        let saved = ServerLauncherData::load_from_file(&path).unwrap();
+        assert_eq!(saved.servers.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn clan_reserve_must_leave_free_slots() {
+        let dir = test_dir("clan_reserve");
+        let path = dir.join("hd2_server_config.json");
+        let with_reserve = |max_clients: u8, clan_reserve: u8| {
+            move |d: &mut ServerLauncherData| {
+                d.servers = vec![Server {
+                    name: "A".to_string(),
+                    configs: vec![ServerConfig {
+                        name: "Coop".to_string(),
+                        max_clients,
+                        clan_reserve,
+                        ..Default::default()
+                    }],
+                    ..Server::default()
+                }];
+                Ok(())
+            }
+        };
+        ServerLauncherData::update_file(&path, None, with_reserve(4, 3)).unwrap();
+        for (max_clients, clan_reserve) in [(4, 4), (0, 1)] {
+            match ServerLauncherData::update_file(
+                &path,
+                None,
+                with_reserve(max_clients, clan_reserve),
+            ) {
+                Err(UpdateError::Invalid(e)) => assert!(e.starts_with("A / Coop:"), "{}", e),
+                other => panic!("expected invalid, got {:?}", other.map(|d| d.revision)),
+            }
+        }
+        // No reserve is fine whatever the slot count.
+        ServerLauncherData::update_file(&path, None, with_reserve(0, 0)).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn legacy_duplicate_ports_are_upgraded_once() {
+        let dir = test_dir("upgrade");
+        let path = dir.join("hd2_server_config.json");
+        fs::write(
+            &path,
+            r#"{
+                "schema_version": 2,
+                "server_manager": {
+                    "enable_watchdog": false,
+                    "enable_forced_ban_list": false,
+                    "forced_ban_list": []
+                },
+                "users": [],
+                "servers": [
+                    {"name": "A", "running": false, "watchdog": false, "messages": false,
+                     "users": [], "port": 22000, "use_sabre_squadron": false,
+                     "current_config": "", "configs": []},
+                    {"name": "B", "running": false, "watchdog": false, "messages": false,
+                     "users": [], "port": 22000, "use_sabre_squadron": false,
+                     "current_config": "", "configs": []}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let upgrade = ServerLauncherData::upgrade_file(&path).unwrap();
+        assert!(upgrade.rewritten);
+        assert_eq!(upgrade.problems.len(), 1);
+        assert!(
+            upgrade.problems[0].contains("both use port 22000"),
+            "{:?}",
+            upgrade.problems
+        );
+        let ids = |data: ServerLauncherData| -> Vec<String> {
+            data.servers.into_iter().map(|s| s.id).collect()
+        };
+        let first = ids(ServerLauncherData::load_from_file(&path).unwrap());
+        assert_eq!(first.len(), 2);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(
+            ids(ServerLauncherData::load_from_file(&path).unwrap()),
+            first
+        );
+        assert!(!ServerLauncherData::upgrade_file(&path).unwrap().rewritten);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unparsable_file_is_an_error() {
+        let dir = test_dir("config");
+        let path = dir.join("hd2_server_config.json");
+        fs::write(&path, "{ \"servers\": [ truncated").unwrap();
+        let err = ServerLauncherData::load_from_file(&path).unwrap_err();
+        assert!(err.starts_with("Invalid config JSON"), "{}", err);
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -4,6 +4,8 @@ use std::path::Path;
 
 const CONFIG_DIR: &str = "content";
 const CONFIG_FILE: &str = "content/spectre_config.json";
+/// Rotating backups of spectre_config.json kept next to it (`.1` = previous save).
+const CONFIG_BACKUPS: usize = 3;
 
 /// Stable machine ID (Windows: MachineGuid; else hostname).
 pub fn get_machine_id() -> String {
@@ -41,6 +43,17 @@ pub struct Config {
     pub server_utility_http_port: u16,
     #[serde(default = "default_server_utility_log_max_mb")]
     pub server_utility_log_max_mb: f32,
+    /// Address the Server Utility web server listens on. Loopback only unless changed.
+    #[serde(default = "default_server_utility_bind_address")]
+    pub server_utility_bind_address: String,
+    /// PEM certificate chain and PKCS#8 private key; the web server uses HTTPS when both are set.
+    #[serde(default)]
+    pub server_utility_tls_cert_path: String,
+    #[serde(default)]
+    pub server_utility_tls_key_path: String,
+    /// Bearer token Prometheus sends to scrape `/metrics`. Empty = a logged-in session is needed.
+    #[serde(default)]
+    pub server_utility_metrics_token: String,
 }
 
 fn default_server_utility_http_port() -> u16 {
@@ -51,6 +64,10 @@ fn default_server_utility_log_max_mb() -> f32 {
     10.0
 }
 
+fn default_server_utility_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
 fn default_fullscreen_dialogs() -> bool {
     false
 }
@@ -64,6 +81,10 @@ impl Default for Config {
             server_utility_wizard_completed: false,
             server_utility_http_port: 8765,
             server_utility_log_max_mb: 10.0,
+            server_utility_bind_address: default_server_utility_bind_address(),
+            server_utility_tls_cert_path: String::new(),
+            server_utility_tls_key_path: String::new(),
+            server_utility_metrics_token: String::new(),
         }
     }
 }
@@ -104,7 +125,14 @@ impl Config {
 
     pub fn save(&self) {
         if let Ok(json) = serde_json::to_string_pretty(self) {
-            if fs::create_dir_all(CONFIG_DIR).is_ok() && fs::write(CONFIG_FILE, json).is_ok() {
+            if fs::create_dir_all(CONFIG_DIR).is_ok()
+                && spectre_core::atomic_file::write_with_backups(
+                    Path::new(CONFIG_FILE),
+                    json.as_bytes(),
+                    CONFIG_BACKUPS,
+                )
+                .is_ok()
+            {
                 println!("[Spectre.dbg] Config saved to {}", CONFIG_FILE);
             } else {
                 println!("[Spectre.dbg] Failed to save config to {}", CONFIG_FILE);
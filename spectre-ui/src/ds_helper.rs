@@ -1,26 +1,19 @@
-//! HD2 DS helper: read player list from process memory, enforce ban/whitelist via console commands.
+//! HD2 DS helper: read the player list from process memory and type commands into the DS console
+//! window. What a poll enforces is in `spectre_core::enforce`.
 
 #![cfg(windows)]
 
-use spectre_core::server::{ServerConfig, ServerManager};
-use std::collections::HashSet;
-use std::io::Write;
+use spectre_core::ds_console::DsConsole;
+use spectre_core::player_slots::{PlayerSlot, PlayerSource, SlotLayout};
 use windows::Win32::Foundation::{CloseHandle, HANDLE, LPARAM, WPARAM};
 use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
 use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_RETURN;
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetWindowTextW, GetWindowThreadProcessId, PostMessageW, SetForegroundWindow,
-    WM_CHAR, WM_KEYDOWN,
+    EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindow, PostMessageW,
+    SetForegroundWindow, WM_CHAR, WM_KEYDOWN,
 };
 
-const PLAYER_BUFFER_POINTER_ADDR: u32 = 0x009D6A4C + 4;
-const SLOT_COUNT: usize = 32;
-const SLOT_STRIDE: usize = 196;
-const SLOT_IP_OFFSET: usize = 4;
-const SLOT_NAME_OFFSET: usize = 8;
-const NAME_MAX: usize = SLOT_STRIDE - SLOT_NAME_OFFSET;
-
 /// Main/console window for a process by PID; prefers title containing "Console".
 pub fn find_main_window_by_pid(pid: u32) -> Option<windows::Win32::Foundation::HWND> {
     if pid == 0 {
@@ -99,54 +92,114 @@ unsafe extern "system" fn enum_callback(
 }
 
 /// Types a command into the DS console window (PostMessage WM_CHAR + Enter).
-/// This focuses the DS console briefly so keyboard input is accepted.
-pub fn send_command_to_ds(hwnd: windows::Win32::Foundation::HWND, command: &str) {
+/// This focuses the DS console briefly so keyboard input is accepted. Fails if the window is
+/// gone or a message cannot be posted; whether the DS ran the command is not known.
+fn send_command_to_ds(
+    hwnd: windows::Win32::Foundation::HWND,
+    command: &str,
+) -> Result<(), String> {
+    if !unsafe { IsWindow(hwnd) }.as_bool() {
+        return Err("DS console window is gone".to_string());
+    }
     let _ = unsafe { SetForegroundWindow(hwnd) };
     std::thread::sleep(std::time::Duration::from_millis(120));
     for ch in command.chars() {
         let code = ch as u32;
         if code <= 0xFFFF {
-            let _ = unsafe { PostMessageW(hwnd, WM_CHAR, WPARAM(code as _), LPARAM(0)) };
+            unsafe { PostMessageW(hwnd, WM_CHAR, WPARAM(code as _), LPARAM(0)) }
+                .map_err(|e| format!("Sending to DS console failed: {}", e))?;
             std::thread::sleep(std::time::Duration::from_millis(10));
         }
     }
-    let _ = unsafe { PostMessageW(hwnd, WM_KEYDOWN, WPARAM(VK_RETURN.0 as _), LPARAM(0)) };
+    unsafe { PostMessageW(hwnd, WM_KEYDOWN, WPARAM(VK_RETURN.0 as _), LPARAM(0)) }
+        .map_err(|e| format!("Sending to DS console failed: {}", e))?;
     std::thread::sleep(std::time::Duration::from_millis(60));
+    Ok(())
 }
 
-pub fn get_player_count(pid: u32, max_clients: u32) -> Option<(u32, u32)> {
-    if pid == 0 {
-        return None;
+/// The DS console window of a process, driven by window messages (see `send_command_to_ds`).
+/// Commands can only be typed in: the window's output is never read back, so `read_output` is
+/// always empty.
+pub struct WindowConsole {
+    hwnd: windows::Win32::Foundation::HWND,
+}
+
+impl WindowConsole {
+    pub fn for_pid(pid: u32) -> Option<Self> {
+        find_main_window_by_pid(pid).map(|hwnd| Self { hwnd })
     }
-    let access = PROCESS_VM_READ | PROCESS_QUERY_INFORMATION;
-    let handle = unsafe { OpenProcess(access, false, pid) }.ok()?;
-    let slots = read_player_slots(handle)?;
-    let _ = unsafe { CloseHandle(handle) };
-    let active = slots.iter().filter(|(name, _)| !name.is_empty()).count() as u32;
-    Some((active, max_clients))
 }
 
-pub fn get_player_list(pid: u32) -> Option<Vec<(String, String)>> {
-    if pid == 0 {
-        return None;
+impl DsConsole for WindowConsole {
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        send_command_to_ds(self.hwnd, command)
+    }
+
+    /// Always empty: the console window's text cannot be read with window messages.
+    fn read_output(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn pause(&mut self, duration: std::time::Duration) {
+        std::thread::sleep(duration);
     }
-    let access = PROCESS_VM_READ | PROCESS_QUERY_INFORMATION;
-    let handle = unsafe { OpenProcess(access, false, pid) }.ok()?;
-    let slots = read_player_slots(handle)?;
-    let _ = unsafe { CloseHandle(handle) };
+}
+
+/// Player slots read from a running DS's memory.
+pub struct ProcessSource {
+    pid: u32,
+    layout: &'static SlotLayout,
+}
+
+impl ProcessSource {
+    pub fn new(pid: u32, layout: &'static SlotLayout) -> Self {
+        Self { pid, layout }
+    }
+}
+
+impl PlayerSource for ProcessSource {
+    fn read_slots(&mut self) -> Result<Vec<PlayerSlot>, String> {
+        if self.pid == 0 {
+            return Err("No DS process".to_string());
+        }
+        let access = PROCESS_VM_READ | PROCESS_QUERY_INFORMATION;
+        let handle = unsafe { OpenProcess(access, false, self.pid) }
+            .map_err(|e| format!("OpenProcess: {}", e))?;
+        let table = read_slot_table(handle, self.layout);
+        let _ = unsafe { CloseHandle(handle) };
+        table
+            .map(|buffer| self.layout.parse(&buffer))
+            .ok_or_else(|| format!("ReadProcessMemory failed ({} layout)", self.layout.name))
+    }
+
+    fn layout(&self) -> &SlotLayout {
+        self.layout
+    }
+}
+
+pub fn get_player_count(pid: u32, layout: &'static SlotLayout, max_clients: u32) -> Option<(u32, u32)> {
+    let slots = ProcessSource::new(pid, layout).read_slots().ok()?;
+    let active = slots.iter().filter(|s| !s.is_free()).count() as u32;
+    Some((active, max_clients))
+}
+
+pub fn get_player_list(pid: u32, layout: &'static SlotLayout) -> Option<Vec<(String, String)>> {
+    let slots = ProcessSource::new(pid, layout).read_slots().ok()?;
     let list: Vec<(String, String)> = slots
         .into_iter()
-        .filter(|(name, _)| !name.is_empty())
+        .filter(|s| !s.is_free())
+        .map(|s| (s.name, s.ip))
         .collect();
     Some(list)
 }
 
-pub fn read_player_slots(process_handle: HANDLE) -> Option<Vec<(String, String)>> {
+/// The raw slot table of `layout`, `layout.table_len()` bytes.
+fn read_slot_table(process_handle: HANDLE, layout: &SlotLayout) -> Option<Vec<u8>> {
     let mut ptr_buf: [u8; 4] = [0; 4];
     let read_ok = unsafe {
         ReadProcessMemory(
             process_handle,
-            PLAYER_BUFFER_POINTER_ADDR as *const _,
+            layout.pointer_addr as *const _,
             ptr_buf.as_mut_ptr() as *mut _,
             4,
             None,
@@ -159,7 +212,7 @@ pub fn read_player_slots(process_handle: HANDLE) -> Option<Vec<(String, String)>
     if base_ptr == 0 {
         return None;
     }
-    let mut buffer = vec![0u8; SLOT_COUNT * SLOT_STRIDE];
+    let mut buffer = vec![0u8; layout.table_len()];
     let read_ok = unsafe {
         ReadProcessMemory(
             process_handle,
@@ -172,209 +225,5 @@ pub fn read_player_slots(process_handle: HANDLE) -> Option<Vec<(String, String)>
     if read_ok.is_err() {
         return None;
     }
-    let mut slots = Vec::with_capacity(SLOT_COUNT);
-    for i in 0..SLOT_COUNT {
-        let base = i * SLOT_STRIDE;
-        let ip_bytes: [u8; 4] = buffer[base + SLOT_IP_OFFSET..base + SLOT_IP_OFFSET + 4]
-            .try_into()
-            .unwrap_or([0, 0, 0, 0]);
-        let ip = format!(
-            "{}.{}.{}.{}",
-            ip_bytes[0], ip_bytes[1], ip_bytes[2], ip_bytes[3]
-        );
-        let name_start = base + SLOT_NAME_OFFSET;
-        let name_end = (name_start + NAME_MAX).min(buffer.len());
-        let name_slice = &buffer[name_start..name_end];
-        let nul = name_slice
-            .iter()
-            .position(|&b| b == 0)
-            .unwrap_or(name_slice.len());
-        let name = String::from_utf8_lossy(&name_slice[..nul])
-            .trim()
-            .to_string();
-        slots.push((name, ip));
-    }
-    Some(slots)
-}
-
-fn entry_ip(entry: &str) -> &str {
-    if let Some(pos) = entry.find(":>") {
-        entry[..pos].trim()
-    } else {
-        entry.trim()
-    }
-}
-
-fn entry_comment(entry: &str) -> Option<&str> {
-    entry
-        .find(":>")
-        .map(|pos| entry[pos + 2..].trim())
-        .filter(|s| !s.is_empty())
-}
-
-pub const ASA_MAX_LEN: usize = 43;
-pub const BAN_REASON_MAX_LEN: usize = 21;
-
-fn asay_message_for_kick(
-    player_name: &str,
-    kick_reason: &str,
-    matching_entry: Option<&str>,
-) -> String {
-    let name = player_name.trim();
-    let msg = if kick_reason == "not in whitelist" {
-        format!("{} not in whitelist.", name)
-    } else {
-        let reason = matching_entry.and_then(entry_comment).unwrap_or("(none)");
-        let reason_trim = reason.chars().take(BAN_REASON_MAX_LEN).collect::<String>();
-        format!("{} is banned. Reason: {}", name, reason_trim)
-    };
-    msg.chars().take(ASA_MAX_LEN).collect()
-}
-
-/// Enforces ban/whitelist: kicks matching players, sends asay then kickplayer via console.
-pub fn enforce_player_lists(
-    pid: u32,
-    port: u16,
-    config: &ServerConfig,
-    manager: &ServerManager,
-    kicked: &mut HashSet<String>,
-    previous_slots: Option<&[(String, String)]>,
-    log_line: Option<&dyn Fn(&str)>,
-    _use_sabre_squadron: bool,
-) -> Result<Vec<(String, String)>, String> {
-    let access = PROCESS_VM_READ | PROCESS_QUERY_INFORMATION;
-    let handle =
-        unsafe { OpenProcess(access, false, pid) }.map_err(|e| format!("OpenProcess: {}", e))?;
-    let slots = match read_player_slots(handle) {
-        Some(s) => s,
-        None => {
-            let _ = unsafe { CloseHandle(handle) };
-            return Err("ReadProcessMemory failed".to_string());
-        }
-    };
-    let _ = unsafe { CloseHandle(handle) };
-
-    let current_connected: Vec<(String, String)> = slots
-        .iter()
-        .filter(|(n, _)| !n.is_empty())
-        .cloned()
-        .collect();
-
-    let previous_set: HashSet<(String, String)> = previous_slots
-        .map(|s| s.iter().cloned().collect())
-        .unwrap_or_default();
-
-    for (name, ip) in &current_connected {
-        if !previous_set.contains(&(name.clone(), ip.clone())) {
-            let msg = format!("[DS-Helper] player joined: \"{}\" ({})", name, ip);
-            println!("{}", msg);
-            let _ = std::io::stdout().flush();
-            if let Some(log) = log_line {
-                log(&msg);
-            }
-        }
-    }
-
-    let current_names: HashSet<String> = current_connected.iter().map(|(n, _)| n.clone()).collect();
-    kicked.retain(|name| current_names.contains(name));
-
-    let should_do_forced_ban =
-        manager.enable_forced_ban_list && !manager.forced_ban_list.is_empty();
-    let should_do_ban = !config.ban_list.is_empty();
-    let should_do_whitelist = config.enable_whitelist;
-
-    if !should_do_forced_ban && !should_do_ban && !should_do_whitelist {
-        return Ok(current_connected);
-    }
-
-    let hwnd = find_main_window_by_pid(pid);
-    if hwnd.is_none() {
-        let msg = format!(
-            "[DS-Helper] port {}: Could not find DS window (kick command will not be sent)",
-            port
-        );
-        println!("{}", msg);
-        if let Some(log) = log_line {
-            log(&msg);
-        }
-    }
-
-    if should_do_ban && !current_connected.is_empty() {
-        let msg = format!(
-            "[DS-Helper] port {} ban_list has {} entries (first: {:?})",
-            port,
-            config.ban_list.len(),
-            config.ban_list.first().map(|s| s.as_str())
-        );
-        println!("{}", msg);
-        let _ = std::io::stdout().flush();
-        if let Some(log) = log_line {
-            log(&msg);
-        }
-    }
-
-    for (slot_index, (name, ip)) in slots.into_iter().enumerate() {
-        if name.is_empty() {
-            continue;
-        }
-        if kicked.contains(&name) {
-            continue;
-        }
-
-        let ip_trimmed = ip.trim();
-
-        let mut should_kick = false;
-        let mut kick_reason = String::new();
-        let mut matching_entry: Option<&str> = None;
-
-        if should_do_forced_ban {
-            for entry in &manager.forced_ban_list {
-                if ip_trimmed == entry_ip(entry) {
-                    should_kick = true;
-                    kick_reason = format!("forced_ban list (entry: {})", entry);
-                    matching_entry = Some(entry);
-                    break;
-                }
-            }
-        }
-        if !should_kick && should_do_ban {
-            for entry in &config.ban_list {
-                if ip_trimmed == entry_ip(entry) {
-                    should_kick = true;
-                    kick_reason = format!("ban list (entry: {})", entry);
-                    matching_entry = Some(entry);
-                    break;
-                }
-            }
-        }
-        if !should_kick && should_do_whitelist {
-            let in_whitelist = config.whitelist.iter().any(|e| ip_trimmed == entry_ip(e));
-            if !in_whitelist {
-                should_kick = true;
-                kick_reason = "not in whitelist".to_string();
-            }
-        }
-
-        if should_kick {
-            let msg = format!(
-                "[DS-Helper] KICK slot {} \"{}\" ({}) reason: {}",
-                slot_index, name, ip_trimmed, kick_reason
-            );
-            println!("{}", msg);
-            let _ = std::io::stdout().flush();
-            if let Some(log) = log_line {
-                log(&msg);
-            }
-            let asay_msg = asay_message_for_kick(&name, &kick_reason, matching_entry);
-            if let Some(h) = hwnd {
-                send_command_to_ds(h, &format!("asay {}", asay_msg));
-                std::thread::sleep(std::time::Duration::from_millis(400));
-                let cmd = format!("kickplayer {}", name.trim());
-                send_command_to_ds(h, &cmd);
-                kicked.insert(name);
-            }
-        }
-    }
-
-    Ok(current_connected)
+    Some(buffer)
 }
@@ -16,6 +16,20 @@ use modules::{
     DtaUnpacker, GamedataEditor, InventoryEditor, ItemsEditor, Module, MpmaplistEditor,
     ServerLauncher,
 };
+#[cfg(windows)]
+use spectre_core::logging::{Level, LogRecord};
+#[cfg(windows)]
+use spectre_core::ds_console::{self, DsConsole};
+#[cfg(windows)]
+use spectre_core::enforce::{enforce_player_lists, LogLineFn, PlayerEvent, PlayerEventFn, PollContext};
+#[cfg(windows)]
+use spectre_core::messages::{MessageContext, MessageScheduler};
+#[cfg(windows)]
+use spectre_core::player_slots::SlotLayout;
+#[cfg(windows)]
+use spectre_core::notify::{Notification, NotificationKind};
+#[cfg(windows)]
+use spectre_core::player_history::{EventKind, PlayerEvent as PlayerHistoryEvent};
 use splash::SplashScreen;
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
@@ -31,16 +45,48 @@ const ABOUT: &str = "Spectre is a toolkit for Hidden & Dangerous 2, providing va
 #[derive(serde::Deserialize)]
 struct IpcSaveMessage {
     action: String,
+    /// Full server list; only read by "save" and "refresh_mpmaplist". Other actions use the
+    /// config on disk.
+    #[serde(default)]
     servers: Vec<spectre_core::server::Server>,
+    /// `Server::id` for per-server actions ("start", "stop", "get_players").
     #[serde(default)]
-    server_index: Option<usize>,
+    server_id: Option<String>,
     #[serde(default)]
     server_manager: Option<spectre_core::server::ServerManager>,
     /// For action "browse_hd2_dir": "hd2ds" or "sabre"
     #[serde(default)]
     browse_which: Option<String>,
+    /// Revision the client last loaded; a save against an older revision is rejected.
+    #[serde(default)]
+    revision: Option<u64>,
+    /// For actions "set_user" and "delete_user"
+    #[serde(default)]
+    user: Option<IpcUserEdit>,
+    /// For action "get_log_content": minimum level shown (with `server_id` to pick a server).
+    #[serde(default)]
+    log_level: Option<spectre_core::logging::Level>,
+    /// For action "search_player_history"
+    #[serde(default)]
+    history: Option<spectre_core::player_history::HistoryQuery>,
+    /// For actions "import_list" and "export_list"
+    #[serde(default)]
+    list: Option<spectre_core::list_io::ListTransfer>,
 }
 
+#[cfg(windows)]
+#[derive(serde::Deserialize)]
+pub(crate) struct IpcUserEdit {
+    username: String,
+    /// None keeps the current password.
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    privilege_level: u8,
+}
+
+#[cfg(windows)]
+mod server_events;
 #[cfg(windows)]
 mod server_utility_http;
 
@@ -89,41 +135,135 @@ pub(crate) fn ensure_log_file_exists(path: &std::path::Path) {
         .open(path);
 }
 
-/// Append a timestamped line to the app log. If rotation_days > 0 and the file is older than that many days, the file is truncated first.
 #[cfg(windows)]
-fn write_app_log(state: &Arc<Mutex<(std::path::PathBuf, u32)>>, line: &str) {
-    let (path, rotation_days) = match state.lock() {
-        Ok(guard) => (guard.0.clone(), guard.1),
-        Err(_) => return,
-    };
-    use std::io::Write;
-    let now = chrono::Local::now();
-    let timestamp = now.format("%Y-%m-%d %H:%M:%S");
-    let full_line = format!("[{}] {}\n", timestamp, line);
-    if rotation_days > 0 && path.exists() {
-        if let Ok(meta) = std::fs::metadata(&path) {
-            if let Ok(modified) = meta.modified() {
-                let age = std::time::SystemTime::now()
-                    .duration_since(modified)
-                    .unwrap_or_default();
-                let rotation_secs = rotation_days as u64 * 24 * 3600;
-                if age.as_secs() >= rotation_secs {
-                    let _ = std::fs::OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .open(&path);
-                }
+static APP_LOG: std::sync::OnceLock<spectre_core::logging::Logger> = std::sync::OnceLock::new();
+
+/// The app log (`spectre_app.log`), shared by the UI, the web server and the watchdog.
+#[cfg(windows)]
+pub(crate) fn app_logger() -> &'static spectre_core::logging::Logger {
+    APP_LOG.get_or_init(|| {
+        let manager = spectre_core::server::ServerManager::default();
+        spectre_core::logging::Logger::new(
+            app_log_path(&server_utility_config_path()),
+            manager.log_rotation(),
+            manager.log_level,
+        )
+    })
+}
+
+/// Apply the rotation and level settings from the saved config.
+#[cfg(windows)]
+pub(crate) fn configure_app_log(manager: &spectre_core::server::ServerManager) {
+    app_logger().configure(manager.log_rotation(), manager.log_level);
+}
+
+/// Print a record, append it to the app log and push it to live web clients.
+#[cfg(windows)]
+pub(crate) fn write_app_log(events: &server_events::EventBus, record: LogRecord) {
+    if !log_app(&record) {
+        return;
+    }
+    events.publish(server_events::ServerEvent::Log {
+        level: record.level,
+        server_id: record.server_id.clone(),
+        line: format_log_record(&record),
+    });
+}
+
+/// Print `record` and write it to the app log if its level is enabled (returns whether it
+/// was), without publishing it: for background threads and helpers that have no event bus.
+#[cfg(windows)]
+pub(crate) fn log_app(record: &LogRecord) -> bool {
+    println!("[{}] {}", record.target, record.message);
+    let logger = app_logger();
+    if !logger.enabled(record.level) {
+        return false;
+    }
+    if let Err(e) = logger.write(record) {
+        println!("[Log] {}", e);
+    }
+    true
+}
+
+/// Persist schema migrations of the server utility config at `path` and log the outcome,
+/// including anything the upgraded file still needs fixed before it can be saved.
+#[cfg(windows)]
+pub(crate) fn upgrade_server_utility_config(path: &std::path::Path) {
+    match spectre_core::server::ServerLauncherData::upgrade_file(path) {
+        Ok(upgrade) if upgrade.rewritten => {
+            log_app(&LogRecord::new(
+                Level::Info,
+                "Config",
+                format!("Server utility config upgraded to schema {}", spectre_core::server::SCHEMA_VERSION),
+            ));
+            for problem in &upgrade.problems {
+                log_app(&LogRecord::new(
+                    Level::Warn,
+                    "Config",
+                    format!("Server utility config needs fixing before it can be saved: {}", problem),
+                ));
             }
         }
+        Ok(_) => {}
+        Err(e) => {
+            log_app(&LogRecord::new(Level::Error, "Config", format!("Server utility config upgrade failed: {}", e)));
+        }
     }
-    if let Ok(mut f) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&path)
-    {
-        let _ = f.write_all(full_line.as_bytes());
-        let _ = f.flush();
+}
+
+/// Send `notification` to the configured webhooks on a background thread; failures are logged.
+#[cfg(windows)]
+pub(crate) fn send_notification(
+    manager: &spectre_core::server::ServerManager,
+    notification: spectre_core::notify::Notification,
+) {
+    if !manager.webhooks.iter().any(|w| w.wants(notification.kind)) {
+        return;
+    }
+    let webhooks = manager.webhooks.clone();
+    std::thread::spawn(move || {
+        for error in spectre_core::notify::send(&webhooks, &notification, Some(&tls_connect)) {
+            log_app(&LogRecord::new(Level::Warn, "Notify", error).server(&notification.server_id));
+        }
+    });
+}
+
+/// TLS for https:// webhooks and ban list subscriptions.
+#[cfg(windows)]
+fn tls_connect(host: &str, port: u16) -> Result<Box<dyn spectre_core::notify::ReadWrite>, String> {
+    let tcp = spectre_core::notify::connect_tcp(host, port)?;
+    let connector = native_tls::TlsConnector::new().map_err(|e| format!("{}: {}", host, e))?;
+    let stream = connector
+        .connect(host, tcp)
+        .map_err(|e| format!("{}: {}", host, e))?;
+    Ok(Box::new(stream))
+}
+
+/// One line of the log viewer: `[local time] LEVEL [target] message`. Lines from the old
+/// plain text log are shown as they were written.
+#[cfg(windows)]
+pub(crate) fn format_log_record(record: &LogRecord) -> String {
+    use chrono::TimeZone;
+    if record.ts == 0 {
+        return record.message.clone();
     }
+    let time = chrono::Local
+        .timestamp_opt(record.ts as i64, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    format!("[{}] {:<5} [{}] {}", time, record.level.name(), record.target, record.message)
+}
+
+/// Recent app log records matching `filter`, formatted for the log viewer.
+#[cfg(windows)]
+pub(crate) fn read_app_log(filter: &spectre_core::logging::LogFilter) -> String {
+    let logger = app_logger();
+    ensure_log_file_exists(logger.path());
+    spectre_core::logging::read_records(logger.path(), filter)
+        .iter()
+        .map(|r| format_log_record(r) + "\n")
+        .collect()
 }
 
 #[cfg(windows)]
@@ -202,6 +342,390 @@ pub(crate) fn ensure_server_utility_has_defaults(data: &mut spectre_core::server
     }
 }
 
+/// Fill in runtime-only fields (running flag, maps parsed from each server's mpmaplist) before
+/// handing config state to the Server Utility UI.
+#[cfg(windows)]
+pub(crate) fn annotate_server_utility_state(
+    data: &mut spectre_core::server::ServerLauncherData,
+    server_pids: &Mutex<HashMap<String, u32>>,
+) {
+    ensure_server_utility_has_defaults(data);
+    strip_password_hashes(data);
+    if let Ok(pids) = server_pids.lock() {
+        for server in data.servers.iter_mut() {
+            server.running = pids.contains_key(&server.id);
+        }
+    }
+    for server in data.servers.iter_mut() {
+        let path_str = server.mpmaplist_path.trim().trim_matches('"').trim();
+        server.available_maps_by_style = if path_str.is_empty() {
+            HashMap::new()
+        } else {
+            spectre_core::mpmaplist::load_from_path(std::path::Path::new(path_str))
+        };
+    }
+}
+
+/// IPC reply for a config save: `STATE:{revision,servers}` on success, `CONFLICT:{config}` when
+/// the client saved against a stale revision (carrying the current config so it can reload).
+#[cfg(windows)]
+pub(crate) fn server_utility_save_response(
+    result: Result<spectre_core::server::ServerLauncherData, spectre_core::server::UpdateError>,
+    server_pids: &Mutex<HashMap<String, u32>>,
+) -> String {
+    use spectre_core::server::UpdateError;
+    match result {
+        Ok(mut data) => {
+            annotate_server_utility_state(&mut data, server_pids);
+            let state = serde_json::json!({ "revision": data.revision, "servers": data.servers });
+            format!("STATE:{}", state)
+        }
+        Err(UpdateError::Conflict(mut current)) => {
+            annotate_server_utility_state(&mut current, server_pids);
+            match serde_json::to_string(&current) {
+                Ok(json) => format!("CONFLICT:{}", json),
+                Err(e) => format!("Save failed: {}", e),
+            }
+        }
+        Err(e) => format!("Save failed: {}", e),
+    }
+}
+
+/// The saved server with `id`, for per-server IPC actions. Clients never supply the server
+/// itself, so a stale tab cannot start a server with outdated settings.
+#[cfg(windows)]
+pub(crate) fn server_utility_server_by_id(
+    config_path: &std::path::Path,
+    id: Option<&str>,
+) -> Result<spectre_core::server::Server, String> {
+    let data = spectre_core::server::ServerLauncherData::load_from_file(config_path)?;
+    data.server_by_id(id.unwrap_or(""))
+        .cloned()
+        .ok_or_else(|| "Unknown server (save it first)".to_string())
+}
+
+/// Config state sent to a client must never carry password hashes.
+#[cfg(windows)]
+pub(crate) fn strip_password_hashes(data: &mut spectre_core::server::ServerLauncherData) {
+    for user in data.users.iter_mut() {
+        user.password_hash.clear();
+    }
+}
+
+/// Who is acting, for the audit log: a logged-in user and their IP, or the desktop app.
+#[cfg(windows)]
+pub(crate) struct AuditSource {
+    pub actor: String,
+    pub source: String,
+}
+
+#[cfg(windows)]
+impl AuditSource {
+    pub fn local() -> Self {
+        Self {
+            actor: "local".to_string(),
+            source: "local".to_string(),
+        }
+    }
+
+    pub fn entry(&self, action: &str) -> spectre_core::audit::AuditEntry {
+        spectre_core::audit::AuditEntry::new(&self.actor, &self.source, action)
+    }
+}
+
+/// Audit log (one JSON entry per line) next to hd2_server_config.json.
+#[cfg(windows)]
+pub(crate) fn audit_log_path(config_path: &std::path::Path) -> std::path::PathBuf {
+    config_path.with_file_name("server_utility_audit.jsonl")
+}
+
+/// Append to the audit log. A failed write is logged, never allowed to block the action.
+#[cfg(windows)]
+pub(crate) fn record_audit(config_path: &std::path::Path, entry: spectre_core::audit::AuditEntry) {
+    if let Err(e) = spectre_core::audit::append(&audit_log_path(config_path), &entry) {
+        log_app(&LogRecord::new(Level::Error, "Audit", e));
+    }
+}
+
+/// Remove bans whose time is up from the config file and audit each one.
+#[cfg(windows)]
+fn prune_expired_bans(config_path: &std::path::Path, events: &server_events::EventBus) {
+    let now = spectre_core::util::unix_now();
+    let mut removed = Vec::new();
+    let result = spectre_core::server::ServerLauncherData::update_file(config_path, None, |data| {
+        removed = data.prune_expired_bans(now);
+        Ok(())
+    });
+    match result {
+        Ok(data) => {
+            events.publish(server_events::ServerEvent::Config { revision: data.revision });
+            for (scope, entry) in removed {
+                write_app_log(events, LogRecord::new(Level::Info, "Watchdog", format!("Ban expired: {} ({})", entry.label(), scope)));
+                record_audit(
+                    config_path,
+                    AuditSource::local()
+                        .entry("ban_expired")
+                        .target(entry.ip.clone())
+                        .detail(format!("{}: {}", scope, entry.reason)),
+                );
+            }
+        }
+        Err(e) => write_app_log(events, LogRecord::new(Level::Warn, "Watchdog", format!("Pruning expired bans failed: {}", e))),
+    }
+}
+
+/// When the scheduled restart of all servers last ran (Unix seconds), next to the config.
+fn last_restart_path(config_path: &std::path::Path) -> std::path::PathBuf {
+    config_path
+        .parent()
+        .map(|p| p.join("last_restart.txt"))
+        .unwrap_or_else(|| std::path::PathBuf::from("last_restart.txt"))
+}
+
+/// Unix seconds of the next scheduled restart, for restart warnings; None when scheduled
+/// restarts are off or the first has not been recorded yet.
+#[cfg(windows)]
+fn next_scheduled_restart(
+    config_path: &std::path::Path,
+    manager: &spectre_core::server::ServerManager,
+) -> Option<u64> {
+    if manager.restart_interval_days == 0 {
+        return None;
+    }
+    let then: u64 = std::fs::read_to_string(last_restart_path(config_path))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    (then > 0).then(|| then + manager.restart_interval_days as u64 * 86400)
+}
+
+/// Lists fetched for `ServerManager::ban_list_subscriptions`, next to hd2_server_config.json.
+#[cfg(windows)]
+pub(crate) fn ban_subscriptions_path(config_path: &std::path::Path) -> std::path::PathBuf {
+    config_path.with_file_name("server_utility_ban_subscriptions.json")
+}
+
+/// Fetched ban list subscriptions, shared with the refresh thread.
+#[cfg(windows)]
+#[derive(Clone)]
+struct BanSubscriptions {
+    cache: Arc<Mutex<spectre_core::ban_subscriptions::SubscriptionCache>>,
+    refreshing: Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(windows)]
+impl BanSubscriptions {
+    fn load(config_path: &std::path::Path) -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(spectre_core::ban_subscriptions::SubscriptionCache::load(
+                &ban_subscriptions_path(config_path),
+            ))),
+            refreshing: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        }
+    }
+
+    /// Entries of the enabled subscriptions, enforced with the forced ban list.
+    fn entries(&self, manager: &spectre_core::server::ServerManager) -> Vec<spectre_core::bans::BanEntry> {
+        self.cache
+            .lock()
+            .map(|cache| cache.entries(&manager.ban_list_subscriptions).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Fetch the subscriptions that are due on a background thread, one refresh at a time.
+    fn refresh(&self, config_path: &std::path::Path, manager: &spectre_core::server::ServerManager) {
+        use std::sync::atomic::Ordering;
+        let now = spectre_core::util::unix_now();
+        let due: Vec<spectre_core::ban_subscriptions::BanListSubscription> = match self.cache.lock() {
+            Ok(cache) => cache.due(&manager.ban_list_subscriptions, now).into_iter().cloned().collect(),
+            Err(_) => return,
+        };
+        if due.is_empty() || self.refreshing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let subscribed = manager.ban_list_subscriptions.clone();
+        let path = ban_subscriptions_path(config_path);
+        let this = self.clone();
+        std::thread::spawn(move || {
+            let results: Vec<_> = due.iter().map(|s| (s, s.fetch(Some(&tls_connect)))).collect();
+            if let Ok(mut cache) = this.cache.lock() {
+                for (subscription, result) in results {
+                    match &result {
+                        Ok(entries) => {
+                            let line = format!("Ban list subscription \"{}\": {} entries", subscription.name, entries.len());
+                            log_app(&LogRecord::new(Level::Debug, "Bans", line));
+                        }
+                        Err(e) => {
+                            let line = format!("Ban list subscription \"{}\" failed: {}", subscription.name, e);
+                            log_app(&LogRecord::new(Level::Warn, "Bans", line));
+                        }
+                    }
+                    cache.record(&subscription.source, result, spectre_core::util::unix_now());
+                }
+                cache.retain(&subscribed);
+                if let Err(e) = cache.save(&path) {
+                    log_app(&LogRecord::new(Level::Error, "Bans", e));
+                }
+            }
+            this.refreshing.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Reply to "get_ban_subscriptions": `BAN_SUBSCRIPTIONS:{name: {checked_at, updated_at,
+/// count, error}}` from the cache on disk.
+#[cfg(windows)]
+pub(crate) fn ban_subscription_status(config_path: &std::path::Path) -> String {
+    let cache = spectre_core::ban_subscriptions::SubscriptionCache::load(&ban_subscriptions_path(config_path));
+    let subscriptions = spectre_core::server::ServerLauncherData::load_from_file(config_path)
+        .map(|data| data.server_manager.ban_list_subscriptions)
+        .unwrap_or_default();
+    // Keyed by name: the source may be a URL with a token, which Viewers must not see.
+    let status: serde_json::Map<String, serde_json::Value> = subscriptions
+        .iter()
+        .filter_map(|s| Some((s, cache.sources.get(s.source.trim())?)))
+        .map(|(subscription, state)| {
+            (
+                subscription.name.clone(),
+                serde_json::json!({
+                    "checked_at": state.checked_at,
+                    "updated_at": state.updated_at,
+                    "count": state.entries.len(),
+                    "error": state.error,
+                }),
+            )
+        })
+        .collect();
+    format!("BAN_SUBSCRIPTIONS:{}", serde_json::Value::Object(status))
+}
+
+/// Player join/leave/kick history (one JSON event per line) next to hd2_server_config.json.
+#[cfg(windows)]
+pub(crate) fn player_history_path(config_path: &std::path::Path) -> std::path::PathBuf {
+    config_path.with_file_name("server_utility_player_history.jsonl")
+}
+
+#[cfg(windows)]
+fn record_player_event(config_path: &std::path::Path, event: spectre_core::player_history::PlayerEvent) {
+    if let Err(e) = spectre_core::player_history::append(&player_history_path(config_path), &event) {
+        log_app(&LogRecord::new(Level::Error, "History", e).server(&event.server_id));
+    }
+}
+
+/// Reply to "search_player_history": `PLAYER_HISTORY:{sessions, aliases}` or an error line.
+#[cfg(windows)]
+pub(crate) fn search_player_history(
+    config_path: &std::path::Path,
+    query: &spectre_core::player_history::HistoryQuery,
+) -> String {
+    match spectre_core::player_history::search(&player_history_path(config_path), query) {
+        Ok(result) => format!(
+            "PLAYER_HISTORY:{}",
+            serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string())
+        ),
+        Err(e) => format!("Player history failed: {}", e),
+    }
+}
+
+/// Reply to "import_list" (`LIST_IMPORTED:{name, added, entries}`, the client's list with the
+/// parsed content merged in) and "export_list" (`LIST_EXPORT:{name, filename, content}`).
+#[cfg(windows)]
+pub(crate) fn server_utility_list_ipc(action: &str, transfer: Option<&spectre_core::list_io::ListTransfer>) -> String {
+    use spectre_core::list_io::{self, ListName};
+    let Some(transfer) = transfer else {
+        return "List transfer failed: missing list".to_string();
+    };
+    let Some(name) = transfer.name else {
+        return "List transfer failed: missing list name".to_string();
+    };
+    if action == "export_list" {
+        return format!(
+            "LIST_EXPORT:{}",
+            serde_json::json!({
+                "name": name,
+                "filename": format!("{}.{}", name.as_str(), transfer.format.extension()),
+                "content": list_io::export(&transfer.entries, transfer.format),
+            })
+        );
+    }
+    let imported = match list_io::import(&transfer.content, transfer.format) {
+        Ok(imported) => imported,
+        Err(e) => return format!("List import failed: {}", e),
+    };
+    let mut entries = transfer.entries.clone();
+    let (added, entries) = if name == ListName::Whitelist {
+        let mut whitelist: Vec<String> = entries.iter().map(|e| e.label()).collect();
+        let added = list_io::merge_whitelist(&mut whitelist, imported);
+        (added, serde_json::json!(whitelist))
+    } else {
+        let added = list_io::merge(&mut entries, imported, |e| &e.ip);
+        (added, serde_json::json!(entries))
+    };
+    format!(
+        "LIST_IMPORTED:{}",
+        serde_json::json!({ "name": name, "added": added, "entries": entries })
+    )
+}
+
+/// Handle the user management actions ("get_users", "set_user", "delete_user"). Replies with
+/// `USERS:[{username, privilege_level, has_password}]` or "User update failed: ...".
+#[cfg(windows)]
+pub(crate) fn server_utility_user_ipc(
+    config_path: &std::path::Path,
+    action: &str,
+    edit: Option<&IpcUserEdit>,
+    who: &AuditSource,
+) -> String {
+    let result = match (action, edit) {
+        ("get_users", _) => spectre_core::server::ServerLauncherData::load_from_file(config_path),
+        ("set_user", Some(edit)) => {
+            spectre_core::server::ServerLauncherData::update_file(config_path, None, |data| {
+                Ok(data.set_user(&edit.username, edit.password.as_deref(), edit.privilege_level)?)
+            })
+            .map_err(|e| e.to_string())
+        }
+        ("delete_user", Some(edit)) => {
+            spectre_core::server::ServerLauncherData::update_file(config_path, None, |data| {
+                Ok(data.remove_user(&edit.username)?)
+            })
+            .map_err(|e| e.to_string())
+        }
+        _ => Err("missing user".to_string()),
+    };
+    match result {
+        Ok(data) => {
+            if let (true, Some(edit)) = (action != "get_users", edit) {
+                log_app(&LogRecord::new(Level::Info, "Service", format!("User {} -> {}", action, config_path.display())));
+                let mut entry = who.entry(action).target(edit.username.trim());
+                if action == "set_user" {
+                    let password = match edit.password.as_deref() {
+                        None => "",
+                        Some("") => ", password cleared",
+                        Some(_) => ", password changed",
+                    };
+                    let privilege = spectre_core::auth::Privilege::from_level(edit.privilege_level);
+                    entry = entry.detail(format!("privilege {}{}", privilege.name(), password));
+                }
+                record_audit(config_path, entry);
+            }
+            let users: Vec<serde_json::Value> = data
+                .users
+                .iter()
+                .map(|u| {
+                    serde_json::json!({
+                        "username": u.username,
+                        "privilege_level": u.privilege_level,
+                        "has_password": u.can_log_in(),
+                    })
+                })
+                .collect();
+            format!("USERS:{}", serde_json::Value::from(users))
+        }
+        Err(e) => format!("User update failed: {}", e),
+    }
+}
+
 const CREDITS: &[&str] = &[
     "Xevrac - Spectre",
     "Fis - Source code and concepts",
@@ -410,6 +934,49 @@ fn process_is_alive(pid: u32) -> bool {
     ok && exit_code == 259 // STILL_ACTIVE
 }
 
+/// Counters shared by the watchdog, the DS helper and the web server's `/metrics`.
+#[cfg(windows)]
+pub(crate) type SharedFleetCounters = Arc<Mutex<spectre_core::metrics::FleetCounters>>;
+
+#[cfg(windows)]
+pub(crate) struct ProcessStats {
+    /// Unix time in seconds.
+    pub started_at: u64,
+    pub cpu_seconds: f64,
+    pub memory_bytes: u64,
+}
+
+/// Start time, CPU time and working set of a running process.
+#[cfg(windows)]
+pub(crate) fn process_stats(pid: u32) -> Option<ProcessStats> {
+    use windows::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows::Win32::System::ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::{
+        GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+    };
+    const FILETIME_TICKS_PER_SEC: u64 = 10_000_000;
+    const FILETIME_UNIX_EPOCH_SECS: u64 = 11_644_473_600;
+    let ticks = |ft: &FILETIME| ((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64;
+    if pid == 0 {
+        return None;
+    }
+    let h = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ, false, pid) }.ok()?;
+    let (mut created, mut exited, mut kernel, mut user) = Default::default();
+    let times = unsafe { GetProcessTimes(h, &mut created, &mut exited, &mut kernel, &mut user) };
+    let mut memory = PROCESS_MEMORY_COUNTERS {
+        cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ..Default::default()
+    };
+    let memory_ok = unsafe { K32GetProcessMemoryInfo(h, &mut memory, memory.cb) }.as_bool();
+    let _ = unsafe { CloseHandle(h) };
+    times.ok()?;
+    Some(ProcessStats {
+        started_at: (ticks(&created) / FILETIME_TICKS_PER_SEC).saturating_sub(FILETIME_UNIX_EPOCH_SECS),
+        cpu_seconds: (ticks(&kernel) + ticks(&user)) as f64 / FILETIME_TICKS_PER_SEC as f64,
+        memory_bytes: if memory_ok { memory.WorkingSetSize as u64 } else { 0 },
+    })
+}
+
 #[cfg(windows)]
 pub(crate) fn kill_process_by_pid(pid: u32) -> bool {
     use windows::Win32::Foundation::CloseHandle;
@@ -888,7 +1455,7 @@ struct SpectreApp {
     #[cfg(windows)]
     webview_repaint_frames: u8,
     #[cfg(windows)]
-    server_pids: Arc<Mutex<HashMap<u16, u32>>>,
+    server_pids: Arc<Mutex<HashMap<String, u32>>>,
     #[cfg(windows)]
     last_watchdog_check: Option<Instant>,
     #[cfg(windows)]
@@ -905,12 +1472,23 @@ struct SpectreApp {
     #[cfg(windows)]
     saved_tray_rect: Option<(i32, i32, i32, i32)>,
     #[cfg(windows)]
-    helper_kicked: Arc<Mutex<HashMap<u16, HashSet<String>>>>,
+    helper_kicked: Arc<Mutex<HashMap<String, HashSet<String>>>>,
+    #[cfg(windows)]
+    helper_last_slots: Arc<Mutex<HashMap<String, Vec<(String, String)>>>>,
+    /// Auto-kick state per server, with the DS pid it belongs to (reset when the DS restarts).
     #[cfg(windows)]
-    helper_last_slots: Arc<Mutex<HashMap<u16, Vec<(String, String)>>>>,
-    /// (log file path, rotation_days) for app log. Set when Server Launcher webview is created.
+    helper_auto_kick: Mutex<HashMap<String, (u32, spectre_core::auto_kick::AutoKickTracker)>>,
+    /// Scheduled message state per server, with the DS pid it belongs to.
     #[cfg(windows)]
-    log_state: Option<Arc<Mutex<(std::path::PathBuf, u32)>>>,
+    helper_messages: Mutex<HashMap<String, (u32, spectre_core::messages::MessageScheduler)>>,
+    /// Process, player and log events pushed to Server Utility web clients.
+    #[cfg(windows)]
+    server_events: server_events::EventBus,
+    /// Watchdog restarts and kicks, reported on `/metrics`.
+    #[cfg(windows)]
+    fleet_counters: SharedFleetCounters,
+    #[cfg(windows)]
+    ban_subscriptions: BanSubscriptions,
     #[cfg(windows)]
     background_timer_set: bool,
     splash_screen: Option<SplashScreen>,
@@ -945,6 +1523,10 @@ impl SpectreApp {
 
         let config = Config::load();
         println!("[Spectre.dbg] Configuration loaded");
+        // Persist schema migrations (password hashes, server IDs) before anything reads the
+        // server config, so IDs generated during migration stay stable.
+        #[cfg(windows)]
+        upgrade_server_utility_config(&server_utility_config_path());
 
         Self::apply_theme(ctx);
 
@@ -1000,7 +1582,15 @@ impl SpectreApp {
             #[cfg(windows)]
             helper_last_slots: Arc::new(Mutex::new(HashMap::new())),
             #[cfg(windows)]
-            log_state: None,
+            helper_auto_kick: Mutex::new(HashMap::new()),
+            #[cfg(windows)]
+            helper_messages: Mutex::new(HashMap::new()),
+            #[cfg(windows)]
+            server_events: server_events::EventBus::new(),
+            #[cfg(windows)]
+            fleet_counters: SharedFleetCounters::default(),
+            #[cfg(windows)]
+            ban_subscriptions: BanSubscriptions::load(&server_utility_config_path()),
             background_timer_set: false,
             splash_screen: Some(splash),
             window_centered: false,
@@ -1474,11 +2064,65 @@ impl SpectreApp {
                             }
                         });
 
-                        if running {
+                        ui.add_enabled_ui(!running, |ui| {
+                            let mut changed = false;
+                            ui.horizontal(|ui| {
+                                ui.label("Bind address:");
+                                changed |= ui
+                                    .add(egui::TextEdit::singleline(&mut self.config.server_utility_bind_address).desired_width(120.0))
+                                    .lost_focus();
+                                ui.label(egui::RichText::new("127.0.0.1 = this PC only, 0.0.0.0 = all networks").small().weak());
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("TLS certificate (PEM):");
+                                changed |= ui
+                                    .add(egui::TextEdit::singleline(&mut self.config.server_utility_tls_cert_path).desired_width(240.0))
+                                    .lost_focus();
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("TLS private key (PEM):");
+                                changed |= ui
+                                    .add(egui::TextEdit::singleline(&mut self.config.server_utility_tls_key_path).desired_width(240.0))
+                                    .lost_focus();
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Metrics token:");
+                                changed |= ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut self.config.server_utility_metrics_token)
+                                            .password(true)
+                                            .desired_width(240.0),
+                                    )
+                                    .lost_focus();
+                                ui.label(egui::RichText::new("Bearer token for /metrics; empty = login required").small().weak());
+                            });
+                            if changed {
+                                self.config.save();
+                            }
+                        });
+                        let remote = self
+                            .config
+                            .server_utility_bind_address
+                            .trim()
+                            .parse::<std::net::IpAddr>()
+                            .map_or(true, |ip| !ip.is_loopback());
+                        let tls_configured = !self.config.server_utility_tls_cert_path.trim().is_empty()
+                            && !self.config.server_utility_tls_key_path.trim().is_empty();
+                        if remote && !tls_configured {
+                            ui.label(
+                                egui::RichText::new("Reachable from other machines over plain HTTP: passwords and sessions are sent unencrypted. Set a TLS certificate and key.")
+                                    .small()
+                                    .color(egui::Color32::from_rgb(230, 160, 60)),
+                            );
+                        }
+
+                        if let Some(ref h) = self.server_utility_http {
                             ui.horizontal(|ui| {
                                 ui.label(egui::RichText::new(format!(
-                                    "Access at: http://localhost:{}",
-                                    current_port.unwrap_or(0)
+                                    "Access at: {} (listening on {}:{})",
+                                    h.local_url(),
+                                    h.bind_ip,
+                                    h.port
                                 )).small());
                             });
                         }
@@ -1502,28 +2146,45 @@ impl SpectreApp {
                                         .unwrap_or_else(|| std::path::PathBuf::from("content/server_utility/http_server.log"));
                                     let log_max_size_bytes =
                                         (self.config.server_utility_log_max_mb * 1024.0 * 1024.0) as u64;
+                                    let request_logger = (log_max_size_bytes > 0).then(|| {
+                                        Arc::new(spectre_core::logging::Logger::new(
+                                            log_file_path,
+                                            spectre_core::logging::RotationPolicy {
+                                                max_bytes: log_max_size_bytes,
+                                                ..Default::default()
+                                            },
+                                            spectre_core::logging::Level::Info,
+                                        ))
+                                    });
                                     let state = server_utility_http::ServerUtilityHttpState {
                                         config_path: config_path.clone(),
                                         server_pids: self.server_pids.clone(),
-                                        log_state: self.log_state.clone(),
                                         helper_kicked: Some(self.helper_kicked.clone()),
                                         helper_last_slots: Some(self.helper_last_slots.clone()),
+                                        events: self.server_events.clone(),
+                                        fleet_counters: self.fleet_counters.clone(),
+                                        metrics_token: self.config.server_utility_metrics_token.trim().to_string(),
                                         request_log: Arc::new(Mutex::new(Vec::new())),
-                                        log_file_path: Some(log_file_path),
-                                        log_max_size_bytes,
+                                        request_logger,
+                                    };
+                                    let cert = self.config.server_utility_tls_cert_path.trim();
+                                    let key = self.config.server_utility_tls_key_path.trim();
+                                    let options = server_utility_http::ListenOptions {
+                                        bind_address: self.config.server_utility_bind_address.clone(),
+                                        port: self.config.server_utility_http_port,
+                                        tls: if cert.is_empty() || key.is_empty() {
+                                            None
+                                        } else {
+                                            Some((std::path::PathBuf::from(cert), std::path::PathBuf::from(key)))
+                                        },
                                     };
-                                    match server_utility_http::start(self.config.server_utility_http_port, state) {
+                                    match server_utility_http::start(&options, state) {
                                         Ok(handle) => {
                                             self.server_utility_http = Some(handle);
-                                            ensure_log_file_exists(&app_log_path(&config_path));
-                                            let mut data = spectre_core::server::ServerLauncherData::load_from_file(&config_path)
-                                                .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-                                            ensure_server_utility_has_defaults(&mut data);
-                                            let rotation_days = data.server_manager.log_rotation_days;
-                                            self.log_state = Some(Arc::new(Mutex::new((
-                                                app_log_path(&config_path),
-                                                rotation_days,
-                                            ))));
+                                            if let Ok(d) = spectre_core::server::ServerLauncherData::load_from_file(&config_path) {
+                                                configure_app_log(&d.server_manager);
+                                            }
+                                            ensure_log_file_exists(app_logger().path());
                                         }
                                         Err(e) => {
                                             println!("[Server Utility HTTP] Start failed: {}", e);
@@ -1532,7 +2193,7 @@ impl SpectreApp {
                                 }
                             }
                             if let Some(ref h) = self.server_utility_http {
-                                let url = format!("http://localhost:{}", h.port);
+                                let url = h.local_url();
                                 if ui.button("Open in browser").clicked() {
                                     let _ = std::process::Command::new("cmd")
                                         .args(["/c", "start", "", &url])
@@ -2286,6 +2947,7 @@ impl SpectreApp {
         #[cfg(windows)]
         if frame_ref.is_some() {
             if let Some(card_name) = self.webview_pending_creation.take() {
+                let mut config_load_error: Option<String> = None;
                 let initial_json = if card_name == "server_utility" {
                     let config_path = server_utility_config_path();
                     let path_exists = config_path.exists();
@@ -2297,71 +2959,80 @@ impl SpectreApp {
                     } else {
                         println!("[Service] Server utility: config file not found at {} (using defaults)", config_path.display());
                     }
-                    let mut data =
-                        spectre_core::server::ServerLauncherData::load_from_file(&config_path)
-                            .unwrap_or_else(|e| {
-                                println!("[Service] Load failed (using defaults): {}", e);
-                                spectre_core::server::ServerLauncherData::default()
-                            });
-                    ensure_server_utility_has_defaults(&mut data);
-                    if let Ok(pids) = self.server_pids.lock() {
-                        for server in data.servers.iter_mut() {
-                            server.running = pids.contains_key(&server.port);
-                        }
-                    }
-                    for (i, server) in data.servers.iter_mut().enumerate() {
-                        let maps = if server.mpmaplist_path.is_empty() {
-                            std::collections::HashMap::new()
-                        } else {
-                            let path = std::path::Path::new(&server.mpmaplist_path);
-                            let resolved = spectre_core::mpmaplist::resolve_mpmaplist_path(path);
-                            let maps = spectre_core::mpmaplist::load_from_path(path);
-                            let total: usize = maps.values().map(|v| v.len()).sum();
-                            if total > 0 {
-                                for (style, list) in &maps {
-                                    println!(
-                                        "[Service] mpmaplist server {} style {}: {} maps",
-                                        i,
-                                        style,
-                                        list.len()
-                                    );
+                    upgrade_server_utility_config(&config_path);
+                    match spectre_core::server::ServerLauncherData::load_from_file(&config_path) {
+                        Ok(mut data) => {
+                            ensure_server_utility_has_defaults(&mut data);
+                            if let Ok(pids) = self.server_pids.lock() {
+                                for server in data.servers.iter_mut() {
+                                    server.running = pids.contains_key(&server.id);
                                 }
-                                println!(
-                                    "[Service] mpmaplist server {} total: {} maps from {}",
-                                    i,
-                                    total,
-                                    resolved.display()
-                                );
-                            } else if !server.mpmaplist_path.is_empty() {
-                                println!(
-                                    "[Service] mpmaplist server {}: no maps from {}",
-                                    i,
-                                    resolved.display()
-                                );
                             }
-                            maps
-                        };
-                        server.available_maps_by_style = maps;
-                    }
-                    match serde_json::to_value(&data) {
-                        Ok(value) => match serde_json::to_string(&value) {
-                            Ok(json) => {
-                                let source = if path_exists { "from file" } else { "defaults" };
-                                println!(
-                                    "[Service] Initial state: {} servers, {} bytes ({})",
-                                    data.servers.len(),
-                                    json.len(),
-                                    source
-                                );
-                                Some(json)
+                            for (i, server) in data.servers.iter_mut().enumerate() {
+                                let maps = if server.mpmaplist_path.is_empty() {
+                                    std::collections::HashMap::new()
+                                } else {
+                                    let path = std::path::Path::new(&server.mpmaplist_path);
+                                    let resolved = spectre_core::mpmaplist::resolve_mpmaplist_path(path);
+                                    let maps = spectre_core::mpmaplist::load_from_path(path);
+                                    let total: usize = maps.values().map(|v| v.len()).sum();
+                                    if total > 0 {
+                                        for (style, list) in &maps {
+                                            println!(
+                                                "[Service] mpmaplist server {} style {}: {} maps",
+                                                i,
+                                                style,
+                                                list.len()
+                                            );
+                                        }
+                                        println!(
+                                            "[Service] mpmaplist server {} total: {} maps from {}",
+                                            i,
+                                            total,
+                                            resolved.display()
+                                        );
+                                    } else if !server.mpmaplist_path.is_empty() {
+                                        println!(
+                                            "[Service] mpmaplist server {}: no maps from {}",
+                                            i,
+                                            resolved.display()
+                                        );
+                                    }
+                                    maps
+                                };
+                                server.available_maps_by_style = maps;
                             }
-                            Err(e) => {
-                                println!("[Service] Serialize initial state failed: {}", e);
-                                None
+                            strip_password_hashes(&mut data);
+                            match serde_json::to_value(&data) {
+                                Ok(value) => match serde_json::to_string(&value) {
+                                    Ok(json) => {
+                                        let source = if path_exists { "from file" } else { "defaults" };
+                                        println!(
+                                            "[Service] Initial state: {} servers, {} bytes ({})",
+                                            data.servers.len(),
+                                            json.len(),
+                                            source
+                                        );
+                                        Some(json)
+                                    }
+                                    Err(e) => {
+                                        println!("[Service] Serialize initial state failed: {}", e);
+                                        None
+                                    }
+                                },
+                                Err(e) => {
+                                    println!("[Service] Serialize initial state failed: {}", e);
+                                    None
+                                }
                             }
-                        },
+                        }
                         Err(e) => {
-                            println!("[Service] Serialize initial state failed: {}", e);
+                            println!("[Service] Load failed: {}", e);
+                            config_load_error = Some(format!(
+                                "Server config could not be loaded:\n{}\n\nFix or remove {} and open the Server Utility again. The file has not been modified.",
+                                e,
+                                config_path.display()
+                            ));
                             None
                         }
                     }
@@ -2373,7 +3044,9 @@ impl SpectreApp {
                     initial_json.as_deref(),
                     cfg!(debug_assertions),
                 );
-                if let Ok(html) = html_result {
+                if let Some(e) = config_load_error {
+                    self.card_launch_error = Some(e);
+                } else if let Ok(html) = html_result {
                     if let Some(ref frame) = frame_opt {
                         let scale = ctx
                             .input(|i| i.viewport().native_pixels_per_point)
@@ -2390,7 +3063,7 @@ impl SpectreApp {
                         let (ipc_tx, ipc_rx) = mpsc::channel();
                         let shared_pids = self.server_pids.clone();
                         let shared_helper_kicked: Option<
-                            Arc<Mutex<HashMap<u16, HashSet<String>>>>,
+                            Arc<Mutex<HashMap<String, HashSet<String>>>>,
                         > = {
                             #[cfg(windows)]
                             {
@@ -2403,6 +3076,7 @@ impl SpectreApp {
                         };
                         #[cfg(windows)]
                         let shared_helper_last_slots = self.helper_last_slots.clone();
+                        let shared_events = self.server_events.clone();
                         let builder = wry::WebViewBuilder::new_as_child(frame)
                     .with_bounds(bounds)
                     .with_ipc_handler({
@@ -2412,6 +3086,7 @@ impl SpectreApp {
                         let shared_helper_kicked = shared_helper_kicked.clone();
                         #[cfg(windows)]
                         let shared_helper_last_slots = shared_helper_last_slots.clone();
+                        let shared_events = shared_events.clone();
                         move |request: http::Request<String>| {
                             let body = request.body();
                             let t0 = Instant::now();
@@ -2425,73 +3100,82 @@ impl SpectreApp {
                             match serde_json::from_str::<IpcSaveMessage>(body) {
                                 Ok(msg) if msg.action == "save" => {
                                     println!("[Service] Save: {} servers", msg.servers.len());
-                                    let mut data = spectre_core::server::ServerLauncherData::load_from_file(&config_path)
-                                        .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-                                    data.servers = msg.servers;
-                                    if let Some(sm) = msg.server_manager {
-                                        data.server_manager = sm;
-                                    }
-                                    if let Some(parent) = config_path.parent() {
-                                        let _ = std::fs::create_dir_all(parent);
-                                    }
-                                    let result = data.save_to_file(&config_path).map_err(|e| e.to_string());
-                                    let status = if result.is_ok() {
-                                        println!("[Service] Save OK -> {}", config_path.display());
-                                        let mut data = spectre_core::server::ServerLauncherData::load_from_file(&config_path)
-                                            .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-                                        ensure_server_utility_has_defaults(&mut data);
-                                        if let Ok(pids) = shared_pids.lock() {
-                                            for server in data.servers.iter_mut() {
-                                                server.running = pids.contains_key(&server.port);
-                                            }
-                                        }
-                                        for server in data.servers.iter_mut() {
-                                            let maps = if server.mpmaplist_path.is_empty() {
-                                                std::collections::HashMap::new()
-                                            } else {
-                                                let path = std::path::Path::new(&server.mpmaplist_path);
-                                                spectre_core::mpmaplist::load_from_path(path)
-                                            };
-                                            server.available_maps_by_style = maps;
+                                    let servers = msg.servers;
+                                    let server_manager = msg.server_manager;
+                                    let mut changes = Vec::new();
+                                    let result = spectre_core::server::ServerLauncherData::update_file(&config_path, msg.revision, |data| {
+                                        let before = data.clone();
+                                        data.servers = servers;
+                                        if let Some(sm) = server_manager {
+                                            data.server_manager = sm;
                                         }
-                                        match serde_json::to_string(&data.servers) {
-                                            Ok(json) => format!("STATE:{}", json),
-                                            Err(_) => "Saved OK".to_string(),
+                                        data.stamp_new_bans(&before, "local");
+                                        changes = spectre_core::audit::config_changes(&before, data);
+                                        Ok(())
+                                    });
+                                    match &result {
+                                        Ok(data) => {
+                                            write_app_log(
+                                                &shared_events,
+                                                LogRecord::new(
+                                                    Level::Info,
+                                                    "Service",
+                                                    format!("Save OK -> {} (revision {})", config_path.display(), data.revision),
+                                                ),
+                                            );
+                                            shared_events.publish(server_events::ServerEvent::Config { revision: data.revision });
+                                            if !changes.is_empty() {
+                                                record_audit(&config_path, AuditSource::local().entry("save").changes(changes));
+                                            }
                                         }
-                                    } else {
-                                        println!("[Service] Save failed: {:?}", result);
-                                        result.unwrap_err()
-                                    };
+                                        Err(e) => write_app_log(&shared_events, LogRecord::new(Level::Warn, "Service", format!("Save refused: {}", e))),
+                                    }
+                                    let status = server_utility_save_response(result, &shared_pids);
                                     if perf && t0.elapsed().as_millis() >= 1 {
                                         println!("[Spectre.dbg] IPC save took {} ms", t0.elapsed().as_millis());
                                     }
                                     let _ = ipc_tx.send(status);
                                 }
                                 Ok(msg) if msg.action == "start" => {
-                                    let idx = msg.server_index.unwrap_or(0);
-                                    match spectre_core::server::ServerLauncherData::load_from_file(&config_path) {
-                                        Ok(_) => match msg.servers.get(idx).cloned() {
-                                            Some(server) => {
-                                                let ipc_tx_b = ipc_tx.clone();
-                                                let pids_b = shared_pids.clone();
-                                                std::thread::spawn(move || {
-                                                    let result = spectre_core::ds_launch::start_ds(&server).map(|pid| (server.port, pid));
-                                                    if let Ok((port, pid)) = &result {
-                                                        if let Ok(mut pids) = pids_b.lock() {
-                                                            pids.insert(*port, *pid);
-                                                        }
-                                                        println!("[Service] Start server {} OK (port {} pid {})", idx, port, pid);
-                                                    } else {
-                                                        println!("[Service] Start server failed: {:?}", result);
+                                    match server_utility_server_by_id(&config_path, msg.server_id.as_deref()) {
+                                        Ok(server) => {
+                                            let ipc_tx_b = ipc_tx.clone();
+                                            let pids_b = shared_pids.clone();
+                                            let events_b = shared_events.clone();
+                                            let config_path_b = config_path.clone();
+                                            std::thread::spawn(move || {
+                                                let result = spectre_core::ds_launch::start_ds(&server);
+                                                if let Ok(pid) = &result {
+                                                    if let Ok(mut pids) = pids_b.lock() {
+                                                        pids.insert(server.id.clone(), *pid);
                                                     }
-                                                    let status = result.map_or_else(|e| e, |_| "Started OK".to_string());
-                                                    let _ = ipc_tx_b.send(status);
-                                                });
-                                            }
-                                            None => {
-                                                let _ = ipc_tx.send(format!("Invalid server index {}", idx));
-                                            }
-                                        },
+                                                    events_b.publish(server_events::ServerEvent::Process {
+                                                        server_id: server.id.clone(),
+                                                        running: true,
+                                                        pid: Some(*pid),
+                                                        reason: "start",
+                                                    });
+                                                    record_audit(&config_path_b, AuditSource::local().entry("start").server(&server.id, &server.name));
+                                                    write_app_log(
+                                                        &events_b,
+                                                        LogRecord::new(
+                                                            Level::Info,
+                                                            "Service",
+                                                            format!("Start server {} OK (port {} pid {})", server.name, server.port, pid),
+                                                        )
+                                                        .server(&server.id),
+                                                    );
+                                                } else if let Err(e) = &result {
+                                                    write_app_log(
+                                                        &events_b,
+                                                        LogRecord::new(Level::Error, "Service", format!("Start server {} failed: {}", server.name, e))
+                                                            .server(&server.id),
+                                                    );
+                                                }
+                                                let status = result.map_or_else(|e| e, |_| "Started OK".to_string());
+                                                let _ = ipc_tx_b.send(status);
+                                            });
+                                        }
                                         Err(e) => {
                                             let _ = ipc_tx.send(e);
                                         }
@@ -2525,7 +3209,7 @@ impl SpectreApp {
                                 }
                                 Ok(msg) if msg.action == "start_all" => {
                                     let pre = match spectre_core::server::ServerLauncherData::load_from_file(&config_path) {
-                                        Ok(_) => Some(msg.servers.clone()),
+                                        Ok(data) => Some(data.servers),
                                         Err(e) => {
                                             let _ = ipc_tx.send(e);
                                             None
@@ -2534,20 +3218,28 @@ impl SpectreApp {
                                     if let Some(servers) = pre {
                                         let ipc_tx_b = ipc_tx.clone();
                                         let pids_b = shared_pids.clone();
+                                        let events_b = shared_events.clone();
+                                        let config_path_b = config_path.clone();
                                         std::thread::spawn(move || {
                                             let mut errs = Vec::new();
                                             let mut started = Vec::new();
                                             for server in &servers {
                                                 match spectre_core::ds_launch::start_ds(server) {
-                                                    Ok(pid) => started.push((server.port, pid)),
+                                                    Ok(pid) => {
+                                                        started.push((server.id.clone(), pid));
+                                                        record_audit(&config_path_b, AuditSource::local().entry("start").server(&server.id, &server.name));
+                                                    }
                                                     Err(e) => errs.push(format!("{}: {}", server.name, e)),
                                                 }
                                             }
                                             if let Ok(mut pids) = pids_b.lock() {
-                                                for (port, pid) in started {
-                                                    pids.insert(port, pid);
+                                                for (id, pid) in &started {
+                                                    pids.insert(id.clone(), *pid);
                                                 }
                                             }
+                                            for (id, pid) in started {
+                                                events_b.publish(server_events::ServerEvent::Process { server_id: id, running: true, pid: Some(pid), reason: "start" });
+                                            }
                                             if errs.is_empty() {
                                                 println!("[Service] Start all servers OK");
                                             } else {
@@ -2559,9 +3251,8 @@ impl SpectreApp {
                                     }
                                 }
                                 Ok(msg) if msg.action == "stop" => {
-                                    let idx = msg.server_index.unwrap_or(0);
-                                    let status = match msg.servers.get(idx) {
-                                        Some(server) => {
+                                    let status = match server_utility_server_by_id(&config_path, msg.server_id.as_deref()) {
+                                        Ok(server) => {
                                             let port = server.port;
                                             let mut pids = match shared_pids.lock() {
                                                 Ok(g) => g,
@@ -2570,18 +3261,32 @@ impl SpectreApp {
                                                     return;
                                                 }
                                             };
-                                            if let Some(&pid) = pids.get(&port) {
-                                                pids.remove(&port);
+                                            if let Some(pid) = pids.remove(&server.id) {
                                                 if let Some(ref k) = shared_helper_kicked {
-                                                    let _ = k.lock().map(|mut m| m.remove(&port));
+                                                    let _ = k.lock().map(|mut m| m.remove(&server.id));
                                                 }
                                                 #[cfg(windows)]
                                                 if let Ok(mut last) = shared_helper_last_slots.lock() {
-                                                    last.remove(&port);
+                                                    last.remove(&server.id);
                                                 }
                                                 drop(pids);
+                                                shared_events.publish(server_events::ServerEvent::Process {
+                                                    server_id: server.id.clone(),
+                                                    running: false,
+                                                    pid: None,
+                                                    reason: "stop",
+                                                });
+                                                record_audit(&config_path, AuditSource::local().entry("stop").server(&server.id, &server.name));
                                                 if kill_process_by_pid(pid) {
-                                                    println!("[Service] Stopped server {} (port {} pid {})", idx, port, pid);
+                                                    write_app_log(
+                                                        &shared_events,
+                                                        LogRecord::new(
+                                                            Level::Info,
+                                                            "Service",
+                                                            format!("Stopped server {} (port {} pid {})", server.name, port, pid),
+                                                        )
+                                                        .server(&server.id),
+                                                    );
                                                     "Stopped OK".to_string()
                                                 } else {
                                                     println!("[Service] Stop: process {} already gone", pid);
@@ -2591,7 +3296,7 @@ impl SpectreApp {
                                                 "Server not running".to_string()
                                             }
                                         }
-                                        None => "Invalid server index".to_string(),
+                                        Err(e) => e,
                                     };
                                     if perf && t0.elapsed().as_millis() >= 1 {
                                         println!("[Spectre.dbg] IPC stop took {} ms", t0.elapsed().as_millis());
@@ -2599,6 +3304,13 @@ impl SpectreApp {
                                     let _ = ipc_tx.send(status);
                                 }
                                 Ok(msg) if msg.action == "stop_all" => {
+                                    let servers = match spectre_core::server::ServerLauncherData::load_from_file(&config_path) {
+                                        Ok(data) => data.servers,
+                                        Err(e) => {
+                                            let _ = ipc_tx.send(e);
+                                            return;
+                                        }
+                                    };
                                     let mut pids = match shared_pids.lock() {
                                         Ok(g) => g,
                                         Err(_) => {
@@ -2606,27 +3318,31 @@ impl SpectreApp {
                                             return;
                                         }
                                     };
-                                    let to_stop: Vec<(u16, u32)> = msg.servers.iter().filter_map(|s| pids.get(&s.port).copied().map(|pid| (s.port, pid))).collect();
-                                    for (port, _) in &to_stop {
-                                        pids.remove(port);
+                                    let to_stop: Vec<(String, u32)> = servers.iter().filter_map(|s| pids.get(&s.id).copied().map(|pid| (s.id.clone(), pid))).collect();
+                                    for (id, _) in &to_stop {
+                                        pids.remove(id);
                                         if let Some(ref k) = shared_helper_kicked {
-                                            let _ = k.lock().map(|mut m| m.remove(port));
+                                            let _ = k.lock().map(|mut m| m.remove(id));
                                         }
                                         #[cfg(windows)]
                                         if let Ok(mut last) = shared_helper_last_slots.lock() {
-                                            last.remove(port);
+                                            last.remove(id);
                                         }
                                     }
                                     drop(pids);
-                                    for (_, pid) in &to_stop {
+                                    for (id, pid) in &to_stop {
                                         kill_process_by_pid(*pid);
+                                        shared_events.publish(server_events::ServerEvent::Process { server_id: id.clone(), running: false, pid: None, reason: "stop" });
+                                        if let Some(server) = servers.iter().find(|s| &s.id == id) {
+                                            record_audit(&config_path, AuditSource::local().entry("stop").server(&server.id, &server.name));
+                                        }
                                     }
                                     println!("[Service] Stop all: {} processes", to_stop.len());
                                     let _ = ipc_tx.send("All servers stopped".to_string());
                                 }
                                 Ok(msg) if msg.action == "get_running" => {
-                                    let ports: Vec<u16> = shared_pids.lock().map(|p| p.keys().copied().collect()).unwrap_or_default();
-                                    let status = format!("RUNNING:{}", serde_json::to_string(&ports).unwrap_or_else(|_| "[]".to_string()));
+                                    let ids: Vec<String> = shared_pids.lock().map(|p| p.keys().cloned().collect()).unwrap_or_default();
+                                    let status = format!("RUNNING:{}", serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string()));
                                     if perf && t0.elapsed().as_millis() >= 1 {
                                         println!("[Spectre.dbg] IPC get_running took {} ms", t0.elapsed().as_millis());
                                     }
@@ -2636,18 +3352,19 @@ impl SpectreApp {
                                     let _ = ipc_tx.send("REPAINT".to_string());
                                 }
                                 Ok(msg) if msg.action == "get_players" => {
-                                    let idx = msg.server_index.unwrap_or(0);
-                                    let (status, pid_opt) = match msg.servers.get(idx) {
-                                        Some(server) => {
-                                            let pid = shared_pids.lock().ok().and_then(|p| p.get(&server.port).copied());
+                                    let (status, pid_opt) = match server_utility_server_by_id(&config_path, msg.server_id.as_deref()) {
+                                        Ok(server) => {
+                                            let pid = shared_pids.lock().ok().and_then(|p| p.get(&server.id).copied());
                                             let max_clients = server
                                                 .configs
                                                 .iter()
                                                 .find(|c| c.name == server.current_config)
                                                 .map(|c| c.max_clients as u32)
                                                 .unwrap_or(32);
+                                            let layout = SlotLayout::for_server(&server);
+                                            let pid = pid.map(|pid| (pid, layout));
                                             let status = match pid {
-                                                Some(pid) => match ds_helper::get_player_count(pid, max_clients) {
+                                                Some((pid, layout)) => match ds_helper::get_player_count(pid, layout, max_clients) {
                                                     Some((active, total)) => format!("PLAYERS:{},{}", active, total),
                                                     None => "PLAYERS:--,--".to_string(),
                                                 },
@@ -2655,11 +3372,11 @@ impl SpectreApp {
                                             };
                                             (status, pid)
                                         }
-                                        None => ("PLAYERS:--,--".to_string(), None),
+                                        Err(_) => ("PLAYERS:--,--".to_string(), None),
                                     };
                                     let _ = ipc_tx.send(status);
                                     let list_json = match pid_opt {
-                                        Some(pid) => ds_helper::get_player_list(pid)
+                                        Some((pid, layout)) => ds_helper::get_player_list(pid, layout)
                                             .map(|list| {
                                                 let arr: Vec<serde_json::Value> = list
                                                     .iter()
@@ -2676,23 +3393,57 @@ impl SpectreApp {
                                     let _ = ipc_tx.send(format!("PLAYER_LIST:{}", list_json));
                                 }
                                 Ok(msg) if msg.action == "get_log_content" => {
-                                    let path = app_log_path(&config_path);
-                                    ensure_log_file_exists(&path);
-                                    const MAX_LOG_BYTES: usize = 32 * 1024;
-                                    let content = match std::fs::read(&path) {
-                                        Ok(b) => {
-                                            let start = b.len().saturating_sub(MAX_LOG_BYTES);
-                                            String::from_utf8_lossy(&b[start..])
-                                                .replace('\r', "")
-                                                .replace('\0', "")
-                                        }
-                                        Err(_) => String::new(),
-                                    };
+                                    let content = read_app_log(&spectre_core::logging::LogFilter {
+                                        server_id: msg.server_id.clone(),
+                                        level: msg.log_level,
+                                        ..Default::default()
+                                    });
                                     if perf && t0.elapsed().as_millis() >= 1 {
                                         println!("[Spectre.dbg] IPC get_log_content took {} ms", t0.elapsed().as_millis());
                                     }
                                     let _ = ipc_tx.send(format!("LOG_CONTENT:{}", content));
                                 }
+                                Ok(msg) if msg.action == "search_player_history" => {
+                                    let query = msg.history.clone().unwrap_or_default();
+                                    let _ = ipc_tx.send(search_player_history(&config_path, &query));
+                                }
+                                Ok(msg) if msg.action == "get_ban_subscriptions" => {
+                                    let _ = ipc_tx.send(ban_subscription_status(&config_path));
+                                }
+                                Ok(msg) if msg.action == "import_list" || msg.action == "export_list" => {
+                                    let _ = ipc_tx.send(server_utility_list_ipc(&msg.action, msg.list.as_ref()));
+                                }
+                                Ok(msg) if msg.action == "restore_previous_config" => {
+                                    let before = spectre_core::server::ServerLauncherData::load_from_file(&config_path).ok();
+                                    let status = match spectre_core::server::ServerLauncherData::restore_backup(&config_path, 1) {
+                                        Ok(mut data) => {
+                                            write_app_log(
+                                                &shared_events,
+                                                LogRecord::new(
+                                                    Level::Info,
+                                                    "Service",
+                                                    format!("Restored previous config -> {}", config_path.display()),
+                                                ),
+                                            );
+                                            shared_events.publish(server_events::ServerEvent::Config { revision: data.revision });
+                                            let changes = before.map(|b| spectre_core::audit::config_changes(&b, &data)).unwrap_or_default();
+                                            record_audit(&config_path, AuditSource::local().entry("restore").changes(changes));
+                                            annotate_server_utility_state(&mut data, &shared_pids);
+                                            match serde_json::to_string(&data) {
+                                                Ok(json) => format!("RESTORED:{}", json),
+                                                Err(e) => format!("Restore failed: {}", e),
+                                            }
+                                        }
+                                        Err(e) => {
+                                            write_app_log(&shared_events, LogRecord::new(Level::Error, "Service", format!("Restore failed: {}", e)));
+                                            format!("Restore failed: {}", e)
+                                        }
+                                    };
+                                    let _ = ipc_tx.send(status);
+                                }
+                                Ok(msg) if msg.action == "get_users" || msg.action == "set_user" || msg.action == "delete_user" => {
+                                    let _ = ipc_tx.send(server_utility_user_ipc(&config_path, &msg.action, msg.user.as_ref(), &AuditSource::local()));
+                                }
                                 Ok(msg) if msg.action == "open_log_file" => {
                                     let path = app_log_path(&config_path);
                                     ensure_log_file_exists(&path);
@@ -2726,16 +3477,14 @@ impl SpectreApp {
                                 self.webview_fade_alpha = 1.0;
                                 self.ipc_save_rx = Some(ipc_rx);
                                 if card_name == "server_utility" {
-                                    let log_path = app_log_path(&config_path);
-                                    ensure_log_file_exists(&log_path);
-                                    let rotation_days =
+                                    if let Ok(d) =
                                         spectre_core::server::ServerLauncherData::load_from_file(
                                             &config_path,
                                         )
-                                        .map(|d| d.server_manager.log_rotation_days)
-                                        .unwrap_or(0);
-                                    self.log_state =
-                                        Some(Arc::new(Mutex::new((log_path, rotation_days))));
+                                    {
+                                        configure_app_log(&d.server_manager);
+                                    }
+                                    ensure_log_file_exists(app_logger().path());
                                 }
                             }
                             Err(e) => {
@@ -2780,6 +3529,8 @@ impl SpectreApp {
                     || msg == "All servers started"
                     || msg == "Saved OK"
                     || msg.starts_with("STATE:")
+                    || msg.starts_with("RESTORED:")
+                    || msg.starts_with("CONFLICT:")
             }
             let perf = std::env::var("SPECTRE_PERF").is_ok();
             let t_drain = Instant::now();
@@ -2888,63 +3639,132 @@ impl SpectreApp {
                 if let Ok(data) =
                     spectre_core::server::ServerLauncherData::load_from_file(&config_path)
                 {
-                    if data.server_manager.enable_watchdog {
-                        let dead_ports: Vec<u16> = match self.server_pids.lock() {
+                    configure_app_log(&data.server_manager);
+                    if data.has_expired_bans(spectre_core::util::unix_now()) {
+                        prune_expired_bans(&config_path, &self.server_events);
+                    }
+                    self.ban_subscriptions.refresh(&config_path, &data.server_manager);
+                    // Processes that exited without being stopped from Spectre; restarted when
+                    // the watchdog is on.
+                    {
+                        let dead_ids: Vec<String> = match self.server_pids.lock() {
                             Ok(pids) => pids
                                 .iter()
                                 .filter(|(_, &pid)| !process_is_alive(pid))
-                                .map(|(&port, _)| port)
+                                .map(|(id, _)| id.clone())
                                 .collect(),
                             Err(_) => Vec::new(),
                         };
-                        if !dead_ports.is_empty() {
+                        if !dead_ids.is_empty() {
                             if let Ok(mut pids) = self.server_pids.lock() {
-                                for port in &dead_ports {
-                                    pids.remove(port);
+                                for id in &dead_ids {
+                                    pids.remove(id);
+                                }
+                            }
+                            for id in &dead_ids {
+                                self.server_events.publish(server_events::ServerEvent::Process {
+                                    server_id: id.clone(),
+                                    running: false,
+                                    pid: None,
+                                    reason: "exited",
+                                });
+                                if let Some(server) = data.servers.iter().find(|s| &s.id == id) {
+                                    send_notification(
+                                        &data.server_manager,
+                                        Notification::new(
+                                            NotificationKind::Crash,
+                                            &server.id,
+                                            &server.name,
+                                            format!("The dedicated server on port {} exited unexpectedly.", server.port),
+                                        ),
+                                    );
                                 }
                             }
                             #[cfg(windows)]
                             if let Ok(mut k) = self.helper_kicked.lock() {
-                                for port in &dead_ports {
-                                    k.remove(port);
+                                for id in &dead_ids {
+                                    k.remove(id);
                                 }
                             }
                             #[cfg(windows)]
                             if let Ok(mut last) = self.helper_last_slots.lock() {
-                                for port in &dead_ports {
-                                    last.remove(port);
+                                for id in &dead_ids {
+                                    last.remove(id);
                                 }
                             }
-                            for port in dead_ports {
-                                if let Some(server) = data.servers.iter().find(|s| s.port == port) {
-                                    match spectre_core::ds_launch::start_ds(server) {
-                                        Ok(pid) => {
-                                            if let Ok(mut pids) = self.server_pids.lock() {
-                                                pids.insert(port, pid);
+                            if data.server_manager.enable_watchdog {
+                                for id in dead_ids {
+                                    if let Some(server) = data.servers.iter().find(|s| s.id == id) {
+                                        match spectre_core::ds_launch::start_ds(server) {
+                                            Ok(pid) => {
+                                                if let Ok(mut pids) = self.server_pids.lock() {
+                                                    pids.insert(id.clone(), pid);
+                                                }
+                                                self.server_events.publish(
+                                                    server_events::ServerEvent::Process {
+                                                        server_id: id,
+                                                        running: true,
+                                                        pid: Some(pid),
+                                                        reason: "watchdog",
+                                                    },
+                                                );
+                                                if let Ok(mut counters) = self.fleet_counters.lock() {
+                                                    counters.record_watchdog_restart(&server.id);
+                                                }
+                                                send_notification(
+                                                    &data.server_manager,
+                                                    Notification::new(
+                                                        NotificationKind::WatchdogRestart,
+                                                        &server.id,
+                                                        &server.name,
+                                                        format!("Restarted on port {} (pid {}).", server.port, pid),
+                                                    ),
+                                                );
+                                                write_app_log(
+                                                    &self.server_events,
+                                                    LogRecord::new(
+                                                        Level::Warn,
+                                                        "Watchdog",
+                                                        format!(
+                                                            "Restarted server {} (port {} pid {})",
+                                                            server.name, server.port, pid
+                                                        ),
+                                                    )
+                                                    .server(&server.id),
+                                                );
+                                            }
+                                            Err(e) => {
+                                                send_notification(
+                                                    &data.server_manager,
+                                                    Notification::new(
+                                                        NotificationKind::WatchdogRestart,
+                                                        &server.id,
+                                                        &server.name,
+                                                        format!("Restart on port {} failed: {}", server.port, e),
+                                                    ),
+                                                );
+                                                write_app_log(
+                                                    &self.server_events,
+                                                    LogRecord::new(
+                                                        Level::Error,
+                                                        "Watchdog",
+                                                        format!(
+                                                            "Restart {} (port {}) failed: {}",
+                                                            server.name, server.port, e
+                                                        ),
+                                                    )
+                                                    .server(&server.id),
+                                                )
                                             }
-                                            println!(
-                                                "[Watchdog] Restarted server port {} (pid {})",
-                                                port, pid
-                                            );
                                         }
-                                        Err(e) => println!(
-                                            "[Watchdog] Restart port {} failed: {}",
-                                            port, e
-                                        ),
                                     }
                                 }
                             }
                         }
                     }
                     if data.server_manager.restart_interval_days > 0 && !data.servers.is_empty() {
-                        let last_restart_path = config_path
-                            .parent()
-                            .map(|p| p.join("last_restart.txt"))
-                            .unwrap_or_else(|| std::path::PathBuf::from("last_restart.txt"));
-                        let now_secs = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or(Duration::ZERO)
-                            .as_secs();
+                        let last_restart_path = last_restart_path(&config_path);
+                        let now_secs = spectre_core::util::unix_now();
                         let do_restart = match std::fs::read_to_string(&last_restart_path) {
                             Ok(s) => {
                                 let then: u64 = s.trim().parse().unwrap_or(0);
@@ -2956,45 +3776,74 @@ impl SpectreApp {
                             Err(_) => true,
                         };
                         if do_restart {
-                            let to_kill: Vec<(u16, u32)> = match self.server_pids.lock() {
+                            let to_kill: Vec<(String, u32)> = match self.server_pids.lock() {
                                 Ok(pids) => data
                                     .servers
                                     .iter()
                                     .filter_map(|s| {
-                                        pids.get(&s.port).copied().map(|pid| (s.port, pid))
+                                        pids.get(&s.id).copied().map(|pid| (s.id.clone(), pid))
                                     })
                                     .collect(),
                                 Err(_) => Vec::new(),
                             };
                             if let Ok(mut pids) = self.server_pids.lock() {
-                                for (port, _) in &to_kill {
-                                    pids.remove(port);
+                                for (id, _) in &to_kill {
+                                    pids.remove(id);
                                 }
                             }
                             #[cfg(windows)]
                             if let Ok(mut k) = self.helper_kicked.lock() {
-                                for (port, _) in &to_kill {
-                                    k.remove(port);
+                                for (id, _) in &to_kill {
+                                    k.remove(id);
                                 }
                             }
                             #[cfg(windows)]
                             if let Ok(mut last) = self.helper_last_slots.lock() {
-                                for (port, _) in &to_kill {
-                                    last.remove(port);
+                                for (id, _) in &to_kill {
+                                    last.remove(id);
                                 }
                             }
-                            for (_, pid) in &to_kill {
+                            for (id, pid) in &to_kill {
                                 kill_process_by_pid(*pid);
+                                self.server_events.publish(server_events::ServerEvent::Process {
+                                    server_id: id.clone(),
+                                    running: false,
+                                    pid: None,
+                                    reason: "restart",
+                                });
                             }
                             std::thread::sleep(Duration::from_secs(2));
                             for server in &data.servers {
                                 if let Ok(pid) = spectre_core::ds_launch::start_ds(server) {
                                     if let Ok(mut pids) = self.server_pids.lock() {
-                                        pids.insert(server.port, pid);
+                                        pids.insert(server.id.clone(), pid);
                                     }
-                                    println!(
-                                        "[Watchdog] Timed restart: started {} (port {} pid {})",
-                                        server.name, server.port, pid
+                                    self.server_events.publish(server_events::ServerEvent::Process {
+                                        server_id: server.id.clone(),
+                                        running: true,
+                                        pid: Some(pid),
+                                        reason: "restart",
+                                    });
+                                    send_notification(
+                                        &data.server_manager,
+                                        Notification::new(
+                                            NotificationKind::ScheduledRestart,
+                                            &server.id,
+                                            &server.name,
+                                            format!("Scheduled restart on port {} (pid {}).", server.port, pid),
+                                        ),
+                                    );
+                                    write_app_log(
+                                        &self.server_events,
+                                        LogRecord::new(
+                                            Level::Info,
+                                            "Watchdog",
+                                            format!(
+                                                "Timed restart: started {} (port {} pid {})",
+                                                server.name, server.port, pid
+                                            ),
+                                        )
+                                        .server(&server.id),
                                     );
                                 }
                                 std::thread::sleep(Duration::from_millis(500));
@@ -3002,15 +3851,18 @@ impl SpectreApp {
                             let _ = std::fs::write(&last_restart_path, now_secs.to_string());
                         }
                     }
-                    let pids_copy: Vec<(u16, u32)> = match self.server_pids.lock() {
-                        Ok(pids) => pids.iter().map(|(&port, &pid)| (port, pid)).collect(),
+                    let pids_copy: Vec<(String, u32)> = match self.server_pids.lock() {
+                        Ok(pids) => pids.iter().map(|(id, &pid)| (id.clone(), pid)).collect(),
                         Err(_) => Vec::new(),
                     };
-                    for (port, pid) in pids_copy {
-                        let server = match data.servers.iter().find(|s| s.port == port) {
+                    let subscribed_bans = self.ban_subscriptions.entries(&data.server_manager);
+                    let next_restart_at = next_scheduled_restart(&config_path, &data.server_manager);
+                    for (id, pid) in pids_copy {
+                        let server = match data.servers.iter().find(|s| s.id == id) {
                             Some(s) => s,
                             None => continue,
                         };
+                        let port = server.port;
                         let config = match server
                             .configs
                             .iter()
@@ -3019,11 +3871,18 @@ impl SpectreApp {
                             Some(c) => c,
                             None => match server.configs.first() {
                                 Some(c) => {
-                                    println!(
-                                        "[Daemon] port {}: no profile \"{}\", using \"{}\"",
-                                        port, server.current_config, c.name
+                                    write_app_log(
+                                        &self.server_events,
+                                        LogRecord::new(
+                                            Level::Warn,
+                                            "Daemon",
+                                            format!(
+                                                "port {}: no profile \"{}\", using \"{}\"",
+                                                port, server.current_config, c.name
+                                            ),
+                                        )
+                                        .server(&id),
                                     );
-                                    let _ = std::io::stdout().flush();
                                     c
                                 }
                                 None => continue,
@@ -3031,48 +3890,183 @@ impl SpectreApp {
                         };
                         let mut kicked = {
                             if let Ok(kicked_map) = self.helper_kicked.lock() {
-                                kicked_map.get(&port).cloned().unwrap_or_default()
+                                kicked_map.get(&id).cloned().unwrap_or_default()
                             } else {
                                 continue;
                             }
                         };
+                        let mut auto_kick = self.helper_auto_kick.lock().ok();
+                        let auto_kick = auto_kick.as_mut().and_then(|trackers| {
+                            if !config.enable_auto_kick {
+                                trackers.remove(&id);
+                                return None;
+                            }
+                            let entry = trackers.entry(id.clone()).or_default();
+                            if entry.0 != pid {
+                                *entry = (pid, Default::default());
+                            }
+                            Some(&mut entry.1)
+                        });
                         let previous_slots = self
                             .helper_last_slots
                             .lock()
                             .ok()
-                            .and_then(|m| m.get(&port).cloned());
-                        let log_state = self.log_state.clone();
-                        let log_callback = move |line: &str| {
-                            if let Some(ref state) = log_state {
-                                write_app_log(state, line);
+                            .and_then(|m| m.get(&id).cloned());
+                        let log_callback = |level: Level, line: &str| {
+                            write_app_log(
+                                &self.server_events,
+                                LogRecord::new(level, "DS-Helper", line).server(&id),
+                            );
+                        };
+                        let log_ref: Option<LogLineFn> = Some(&log_callback);
+                        let player_callback = |event: PlayerEvent| {
+                            let server_id = id.clone();
+                            match event {
+                                PlayerEvent::Joined { name, ip } => {
+                                    record_player_event(
+                                        &config_path,
+                                        PlayerHistoryEvent::new(EventKind::Join, &server.id, &server.name, name, ip),
+                                    );
+                                    self.server_events.publish(server_events::ServerEvent::PlayerJoined {
+                                        server_id,
+                                        name: name.to_string(),
+                                        ip: ip.to_string(),
+                                    })
+                                }
+                                PlayerEvent::Left { name, ip } => {
+                                    record_player_event(
+                                        &config_path,
+                                        PlayerHistoryEvent::new(EventKind::Leave, &server.id, &server.name, name, ip),
+                                    );
+                                    self.server_events.publish(server_events::ServerEvent::PlayerLeft {
+                                        server_id,
+                                        name: name.to_string(),
+                                        ip: ip.to_string(),
+                                    })
+                                }
+                                PlayerEvent::Kicked { name, ip, kind, reason } => {
+                                    record_player_event(
+                                        &config_path,
+                                        PlayerHistoryEvent::new(EventKind::Kick, &server.id, &server.name, name, ip)
+                                            .reason(reason),
+                                    );
+                                    if let Ok(mut counters) = self.fleet_counters.lock() {
+                                        counters.record_kick(&server.id, kind);
+                                    }
+                                    if matches!(kind, "ban" | "forced_ban" | "name_ban") {
+                                        send_notification(
+                                            &data.server_manager,
+                                            Notification::new(
+                                                NotificationKind::BannedKick,
+                                                &server.id,
+                                                &server.name,
+                                                format!("Kicked \"{}\" ({}): {}", name, ip, reason),
+                                            ),
+                                        );
+                                    }
+                                    record_audit(
+                                        &config_path,
+                                        spectre_core::audit::AuditEntry::new("ds-helper", "local", "kick")
+                                            .server(&server.id, &server.name)
+                                            .target(format!("{} ({})", name, ip))
+                                            .detail(reason),
+                                    )
+                                }
                             }
                         };
-                        let log_ref: Option<&dyn Fn(&str)> = Some(&log_callback);
-                        match ds_helper::enforce_player_lists(
-                            pid,
+                        let player_ref: Option<PlayerEventFn> = Some(&player_callback);
+                        let mut console = ds_helper::WindowConsole::for_pid(pid);
+                        let mut source = ds_helper::ProcessSource::new(pid, SlotLayout::for_server(server));
+                        let poll = PollContext {
                             port,
                             config,
-                            &data.server_manager,
+                            manager: &data.server_manager,
+                            subscribed_bans: &subscribed_bans,
+                            previous_slots: previous_slots.as_deref(),
+                            now: spectre_core::util::unix_now(),
+                            log_line: log_ref,
+                            on_player: player_ref,
+                        };
+                        match enforce_player_lists(
+                            &poll,
+                            &mut source,
+                            console.as_mut().map(|c| c as &mut dyn DsConsole),
                             &mut kicked,
-                            previous_slots.as_deref(),
-                            log_ref,
-                            server.use_sabre_squadron,
+                            auto_kick,
                         ) {
                             Ok(current_slots) => {
+                                if let Some(previous) = previous_slots.as_deref() {
+                                    let max = config.max_clients as usize;
+                                    let (before, now) = (previous.len(), current_slots.len());
+                                    let change = if max > 0 && now >= max && before < max {
+                                        Some((NotificationKind::ServerFull, format!("{}/{} players.", now, max)))
+                                    } else if now == 0 && before > 0 {
+                                        Some((NotificationKind::ServerEmpty, "The last player left.".to_string()))
+                                    } else {
+                                        None
+                                    };
+                                    if let Some((kind, message)) = change {
+                                        send_notification(
+                                            &data.server_manager,
+                                            Notification::new(kind, &server.id, &server.name, message),
+                                        );
+                                    }
+                                }
+                                if server.messages {
+                                    let now = spectre_core::util::unix_now();
+                                    let ctx = MessageContext {
+                                        player: "",
+                                        server: &server.name,
+                                        map: config.maps.first().map(String::as_str).unwrap_or_default(),
+                                        players: current_slots.len(),
+                                        max_players: config.max_clients as u32,
+                                        next_restart: next_restart_at.map(|t| t.saturating_sub(now)),
+                                    };
+                                    let mut lines = Vec::new();
+                                    // No welcome on the first poll: those players were already here.
+                                    if let Some(previous) = previous_slots.as_deref() {
+                                        for (name, _) in current_slots
+                                            .iter()
+                                            .filter(|s| !previous.contains(s) && !kicked.contains(&s.0))
+                                        {
+                                            let ctx = MessageContext { player: name, ..ctx.clone() };
+                                            lines.extend(MessageScheduler::welcome(config, &ctx));
+                                        }
+                                    }
+                                    if let Ok(mut schedulers) = self.helper_messages.lock() {
+                                        let entry = schedulers.entry(id.clone()).or_default();
+                                        if entry.0 != pid {
+                                            *entry = (pid, Default::default());
+                                        }
+                                        lines.extend(entry.1.tick(config, &ctx, now));
+                                    }
+                                    if let Some(console) = console.as_mut() {
+                                        for line in lines {
+                                            if let Err(e) = ds_console::asay(console, &line) {
+                                                log_callback(Level::Warn, &format!("port {}: message not sent: {}", port, e));
+                                                break;
+                                            }
+                                        }
+                                    }
+                                }
+                                if previous_slots.as_deref() != Some(current_slots.as_slice()) {
+                                    self.server_events
+                                        .publish(server_events::ServerEvent::players(&id, &current_slots));
+                                }
                                 if let Ok(mut last) = self.helper_last_slots.lock() {
-                                    last.insert(port, current_slots);
+                                    last.insert(id.clone(), current_slots);
                                 }
                             }
                             Err(e) => {
-                                let line = format!("[DS-Helper] port {}: {}", port, e);
-                                println!("{}", line);
-                                if let Some(ref state) = self.log_state {
-                                    write_app_log(state, &line);
-                                }
+                                write_app_log(
+                                    &self.server_events,
+                                    LogRecord::new(Level::Warn, "DS-Helper", format!("port {}: {}", port, e))
+                                        .server(&id),
+                                );
                             }
                         }
                         if let Ok(mut kicked_map) = self.helper_kicked.lock() {
-                            kicked_map.insert(port, kicked);
+                            kicked_map.insert(id, kicked);
                         }
                     }
                 }
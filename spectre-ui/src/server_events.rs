@@ -0,0 +1,193 @@
+//! Live Server Utility events, pushed to web clients over `/api/v1/events` (SSE) so they
+//! do not have to poll process state, player lists and the app log.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use tokio::sync::broadcast;
+
+/// Events a slow client may fall behind by before it is told to resync.
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// A DS process started or stopped. `reason` is "start", "stop", "exited", "watchdog"
+    /// (restarted after it died), "restart" (timed restart) or "current" (sent when a
+    /// client connects).
+    Process {
+        server_id: String,
+        running: bool,
+        pid: Option<u32>,
+        reason: &'static str,
+    },
+    PlayerJoined {
+        server_id: String,
+        name: String,
+        ip: String,
+    },
+    PlayerLeft {
+        server_id: String,
+        name: String,
+        ip: String,
+    },
+    /// Full `(name, ip)` list whenever it changes.
+    Players {
+        server_id: String,
+        players: Vec<Player>,
+    },
+    /// A record appended to the app log; `line` is formatted as in the log viewer.
+    Log {
+        level: spectre_core::logging::Level,
+        server_id: Option<String>,
+        line: String,
+    },
+    /// The config file was saved or restored.
+    Config { revision: u64 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Player {
+    pub name: String,
+    pub ip: String,
+}
+
+impl ServerEvent {
+    /// The server this event is about, if any (used to hide servers a user is not assigned to).
+    pub fn server_id(&self) -> Option<&str> {
+        match self {
+            ServerEvent::Process { server_id, .. }
+            | ServerEvent::PlayerJoined { server_id, .. }
+            | ServerEvent::PlayerLeft { server_id, .. }
+            | ServerEvent::Players { server_id, .. } => Some(server_id),
+            ServerEvent::Log { server_id, .. } => server_id.as_deref(),
+            ServerEvent::Config { .. } => None,
+        }
+    }
+
+    pub fn players(server_id: &str, slots: &[(String, String)]) -> Self {
+        ServerEvent::Players {
+            server_id: server_id.to_string(),
+            players: slots
+                .iter()
+                .map(|(name, ip)| Player {
+                    name: name.clone(),
+                    ip: ip.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Which events one web client may see.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Servers assigned to the client's user.
+    pub servers: HashSet<String>,
+    /// Log records about no server (logins, bans, the HTTP server) are for admins only.
+    pub all_logs: bool,
+}
+
+impl EventFilter {
+    pub fn for_user(
+        data: &spectre_core::server::ServerLauncherData,
+        user: &spectre_core::server::User,
+    ) -> Self {
+        Self {
+            servers: data
+                .servers
+                .iter()
+                .filter(|s| s.is_accessible_by(user))
+                .map(|s| s.id.clone())
+                .collect(),
+            all_logs: user.privilege() == spectre_core::auth::Privilege::Admin,
+        }
+    }
+
+    pub fn allows(&self, event: &ServerEvent) -> bool {
+        match event {
+            ServerEvent::Log {
+                server_id: None, ..
+            } => self.all_logs,
+            _ => event.server_id().is_none_or(|id| self.servers.contains(id)),
+        }
+    }
+}
+
+/// Cheap to clone; every clone publishes to the same subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ServerEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    /// Send to current subscribers. Having none (no web client connected) is not an error.
+    pub fn publish(&self, event: ServerEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectre_core::logging::Level;
+
+    fn log(server_id: Option<&str>) -> ServerEvent {
+        ServerEvent::Log {
+            level: Level::Info,
+            server_id: server_id.map(str::to_string),
+            line: "line".to_string(),
+        }
+    }
+
+    #[test]
+    fn subscribers_get_published_events_and_filter_by_server() {
+        let bus = EventBus::new();
+        bus.publish(ServerEvent::Config { revision: 1 });
+        let mut receiver = bus.subscribe();
+        bus.clone().publish(ServerEvent::players(
+            "a",
+            &[("Pilot".to_string(), "1.2.3.4".to_string())],
+        ));
+        bus.publish(log(Some("b")));
+        bus.publish(log(None));
+        bus.publish(ServerEvent::Config { revision: 2 });
+
+        let mut received = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            received.push(event);
+        }
+        // Only what was published after subscribing.
+        assert_eq!(received.len(), 4);
+        assert!(
+            matches!(&received[0], ServerEvent::Players { players, .. } if players[0].name == "Pilot")
+        );
+
+        let operator = EventFilter {
+            servers: HashSet::from(["a".to_string()]),
+            all_logs: false,
+        };
+        let allowed: Vec<bool> = received.iter().map(|e| operator.allows(e)).collect();
+        assert_eq!(allowed, [true, false, false, true]);
+        let admin = EventFilter {
+            all_logs: true,
+            ..operator
+        };
+        assert!(admin.allows(&log(None)));
+        assert!(!admin.allows(&log(Some("b"))));
+    }
+}
@@ -1,40 +1,72 @@
 //! HTTP server for Server Utility web UI. Serves the same content as the former webview
 
-//! HTTP server for Server Utility web UI. Protected by a secret token to prevent
-//! unauthenticated RCE via the IPC bridge. Paths from the client are validated
+//! HTTP server for Server Utility web UI. Every request needs a login session (cookie
+//! issued by `/api/login` for a user in hd2_server_config.json) and each IPC action is
+//! gated by the user's privilege level. Paths from the client are validated
 //! before use (no null bytes, no protocol handlers, no traversal).
+//!
+//! Listens on loopback unless another bind address is configured, optionally over HTTPS
+//! with a PEM certificate. Failed logins are throttled per client address.
 
 #![cfg(windows)]
 
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::service::TowerToHyperService;
 use rand::Rng;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::io::Write;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+use crate::server_events::{EventFilter, ServerEvent};
+use spectre_core::logging::{Level, LogRecord};
+use spectre_core::server::UpdateError;
 
 const MAX_LOG_LINES: usize = 500;
-const IPC_TOKEN_HEADER: &str = "x-spectre-token";
 const MAX_PATH_LEN: usize = 2048;
+const SESSION_COOKIE: &str = "spectre_session";
+const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+/// How often an open event stream re-checks its session, so a logout, expiry or password
+/// change ends it even when no events are flowing.
+const EVENT_SESSION_CHECK: Duration = Duration::from_secs(30);
+const OPENAPI_JSON: &str = include_str!("server_utility_openapi.json");
 
 fn generate_token() -> String {
     rand::thread_rng()
         .sample_iter(rand::distributions::Alphanumeric)
-        .take(32)
+        .take(48)
         .map(char::from)
         .collect()
 }
 
+/// A logged-in browser. The password hash at login time is kept so changing a user's
+/// password (or removing the user) ends their existing sessions.
+struct Session {
+    username: String,
+    password_hash: String,
+    expires: Instant,
+}
+
+type Sessions = Arc<std::sync::Mutex<HashMap<String, Session>>>;
+
 fn is_path_safe(s: &str) -> bool {
     if s.is_empty() || s.len() > MAX_PATH_LEN || s.contains('\0') {
         return false;
@@ -104,71 +136,185 @@ fn is_allowed_sabre_exe_path(path: &str) -> bool {
 #[derive(Clone)]
 pub struct ServerUtilityHttpState {
     pub config_path: PathBuf,
-    pub server_pids: Arc<std::sync::Mutex<HashMap<u16, u32>>>,
-    pub log_state: Option<Arc<std::sync::Mutex<(PathBuf, u32)>>>,
-    pub helper_kicked: Option<Arc<std::sync::Mutex<HashMap<u16, HashSet<String>>>>>,
-    pub helper_last_slots: Option<Arc<std::sync::Mutex<HashMap<u16, Vec<(String, String)>>>>>,
+    /// Running DS processes by server ID (`Server::id`), as are the helper maps below.
+    pub server_pids: Arc<std::sync::Mutex<HashMap<String, u32>>>,
+    pub helper_kicked: Option<Arc<std::sync::Mutex<HashMap<String, HashSet<String>>>>>,
+    pub helper_last_slots: Option<Arc<std::sync::Mutex<HashMap<String, Vec<(String, String)>>>>>,
+    pub events: crate::server_events::EventBus,
+    pub fleet_counters: crate::SharedFleetCounters,
+    /// Bearer token accepted on `/metrics`; empty = only logged-in sessions.
+    pub metrics_token: String,
     pub request_log: Arc<std::sync::Mutex<Vec<String>>>,
-    pub log_file_path: Option<PathBuf>,
-    pub log_max_size_bytes: u64,
+    /// `http_server.log`; `None` when file logging is turned off.
+    pub request_logger: Option<Arc<spectre_core::logging::Logger>>,
 }
 
 #[derive(Clone)]
 struct AppState {
     inner: ServerUtilityHttpState,
     shutdown: Arc<AtomicBool>,
-    token: String,
+    sessions: Sessions,
+    login_throttle: Arc<std::sync::Mutex<spectre_core::auth::LoginThrottle>>,
+    /// Served over HTTPS, so the session cookie is marked `Secure`.
+    tls: bool,
+}
+
+fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+        .filter(|v| !v.is_empty())
 }
 
-fn push_log(
-    log: &Arc<std::sync::Mutex<Vec<String>>>,
-    line: &str,
-    log_file: Option<&PathBuf>,
-    log_max_bytes: u64,
-) {
-    if let Ok(mut g) = log.lock() {
+/// The user behind the request's session cookie, re-read from the config so privilege
+/// changes apply immediately. Expired or invalidated sessions are dropped.
+fn current_user(state: &AppState, headers: &HeaderMap) -> Option<spectre_core::server::User> {
+    let token = session_token(headers)?;
+    let mut sessions = state.sessions.lock().ok()?;
+    let session = sessions.get(&token)?;
+    let user = if session.expires > Instant::now() {
+        spectre_core::server::ServerLauncherData::load_from_file(&state.inner.config_path)
+            .ok()
+            .and_then(|data| data.find_user(&session.username).cloned())
+            .filter(|u| u.can_log_in() && u.password_hash == session.password_hash)
+    } else {
+        None
+    };
+    if user.is_none() {
+        sessions.remove(&token);
+    }
+    user
+}
+
+fn session_cookie(token: &str, max_age_secs: u64, secure: bool) -> String {
+    format!(
+        "{}={}; HttpOnly; SameSite=Strict; Path=/; Max-Age={}{}",
+        SESSION_COOKIE,
+        token,
+        max_age_secs,
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// Keep `line` for the dashboard and append it to the request log file.
+fn push_log(state: &ServerUtilityHttpState, level: Level, line: &str) {
+    if let Ok(mut g) = state.request_log.lock() {
         g.push(line.to_string());
         let n = g.len();
         if n > MAX_LOG_LINES {
             g.drain(0..n - MAX_LOG_LINES);
         }
     }
-    if let (Some(path), max_bytes) = (log_file, log_max_bytes) {
-        if max_bytes == 0 {
-            return;
-        }
-        if let Some(parent) = path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        let line_with_newline = format!("{}\n", line);
-        let append = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path);
-        if let Ok(mut f) = append {
-            let _ = f.write_all(line_with_newline.as_bytes());
-            let _ = f.flush();
-        }
-        if let Ok(meta) = std::fs::metadata(path) {
-            if meta.len() > max_bytes {
-                if let Ok(content) = std::fs::read_to_string(path) {
-                        let keep_len = (max_bytes as usize).saturating_sub(1024).min(content.len());
-                        let start = content.len().saturating_sub(keep_len);
-                        let truncated = if start > 0 {
-                            format!("--- log rotated ---\n{}", &content[start..])
-                        } else {
-                            content
-                        };
-                        let _ = std::fs::write(path, truncated);
-                }
-            }
-        }
+    if let Some(logger) = &state.request_logger {
+        let _ = logger.write(&LogRecord::new(level, "HTTP", line));
+    }
+}
+
+fn with_trimmed_paths(s: &spectre_core::server::Server) -> spectre_core::server::Server {
+    let mut s2 = s.clone();
+    s2.hd2ds_path = trim_path_quotes(&s.hd2ds_path);
+    s2.hd2ds_sabresquadron_path = trim_path_quotes(&s.hd2ds_sabresquadron_path);
+    s2.mpmaplist_path = trim_path_quotes(&s.mpmaplist_path);
+    s2
+}
+
+/// Refuse to launch anything but HD2DS.exe / HD2DS_SabreSquadron.exe from a web request.
+fn validate_launch_paths(server: &spectre_core::server::Server) -> Result<(), String> {
+    if !is_path_safe(&server.hd2ds_path)
+        || !is_path_safe(&server.hd2ds_sabresquadron_path)
+        || !is_path_safe(&server.mpmaplist_path)
+    {
+        return Err("Invalid path (unsafe characters or traversal)".to_string());
+    }
+    let exe_path = if server.use_sabre_squadron {
+        server.hd2ds_sabresquadron_path.as_str()
+    } else {
+        server.hd2ds_path.as_str()
+    };
+    let allowed = if server.use_sabre_squadron {
+        is_allowed_sabre_exe_path(exe_path)
+    } else {
+        is_allowed_hd2ds_exe_path(exe_path)
+    };
+    if !allowed {
+        return Err("Executable path must be HD2DS.exe or HD2DS_SabreSquadron.exe".to_string());
+    }
+    Ok(())
+}
+
+fn launch_server(state: &ServerUtilityHttpState, server: &spectre_core::server::Server) -> Result<u32, String> {
+    let pid = spectre_core::ds_launch::start_ds(server)?;
+    if let Ok(mut pids) = state.server_pids.lock() {
+        pids.insert(server.id.clone(), pid);
     }
+    state.events.publish(ServerEvent::Process {
+        server_id: server.id.clone(),
+        running: true,
+        pid: Some(pid),
+        reason: "start",
+    });
+    Ok(pid)
+}
+
+/// Kill the DS for server `id` and drop its helper state. `Ok(false)` if it was not running.
+fn stop_server(state: &ServerUtilityHttpState, id: &str) -> Result<bool, String> {
+    let mut pids = state.server_pids.lock().map_err(|_| "Stop failed (lock)".to_string())?;
+    let Some(pid) = pids.remove(id) else {
+        return Ok(false);
+    };
+    if let Some(ref k) = state.helper_kicked {
+        let _ = k.lock().map(|mut m| m.remove(id));
+    }
+    if let Some(ref last) = state.helper_last_slots {
+        let _ = last.lock().map(|mut m| m.remove(id));
+    }
+    drop(pids);
+    crate::kill_process_by_pid(pid);
+    state.events.publish(ServerEvent::Process {
+        server_id: id.to_string(),
+        running: false,
+        pid: None,
+        reason: "stop",
+    });
+    Ok(true)
+}
+
+/// Player counts `(active, total)` and `(name, ip)` list for a running server.
+fn player_info(
+    state: &ServerUtilityHttpState,
+    server: &spectre_core::server::Server,
+) -> (Option<(u32, u32)>, Option<Vec<(String, String)>>) {
+    let pid = state
+        .server_pids
+        .lock()
+        .ok()
+        .and_then(|p| p.get(&server.id).copied());
+    let Some(pid) = pid else {
+        return (None, None);
+    };
+    let max_clients = server
+        .configs
+        .iter()
+        .find(|c| c.name == server.current_config)
+        .map(|c| c.max_clients as u32)
+        .unwrap_or(32);
+    let layout = spectre_core::player_slots::SlotLayout::for_server(server);
+    (
+        crate::ds_helper::get_player_count(pid, layout, max_clients),
+        crate::ds_helper::get_player_list(pid, layout),
+    )
 }
 
 fn handle_ipc(
     state: &ServerUtilityHttpState,
     msg: &IpcSaveMessage,
+    user: &spectre_core::server::User,
+    who: &crate::AuditSource,
 ) -> Vec<String> {
     let mut responses = Vec::new();
     let config_path = &state.config_path;
@@ -176,6 +322,29 @@ fn handle_ipc(
     let shared_helper_kicked = &state.helper_kicked;
     let shared_helper_last_slots = &state.helper_last_slots;
 
+    // The saved config is the source of truth for every action except "save": servers are
+    // looked up by ID and only those assigned to this user (see `Server::is_accessible_by`)
+    // are visible. Others answer like unknown servers, and bulk actions skip them.
+    let data = match spectre_core::server::ServerLauncherData::load_from_file(config_path) {
+        Ok(d) => d,
+        Err(e) => {
+            responses.push(e);
+            return responses;
+        }
+    };
+    let visible_servers: Vec<&spectre_core::server::Server> =
+        data.servers.iter().filter(|s| s.is_accessible_by(user)).collect();
+    let visible_ids: HashSet<&str> = visible_servers.iter().map(|s| s.id.as_str()).collect();
+    let target = msg
+        .server_id
+        .as_deref()
+        .and_then(|id| visible_servers.iter().find(|s| s.id == id))
+        .copied();
+    if matches!(msg.action.as_str(), "start" | "stop" | "get_players") && target.is_none() {
+        responses.push("Unknown server (save it first)".to_string());
+        return responses;
+    }
+
     match msg.action.as_str() {
         "save" => {
             for s in &msg.servers {
@@ -193,9 +362,7 @@ fn handle_ipc(
                     return responses;
                 }
             }
-            let mut data = spectre_core::server::ServerLauncherData::load_from_file(config_path)
-                .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-            data.servers = msg
+            let servers: Vec<spectre_core::server::Server> = msg
                 .servers
                 .iter()
                 .map(|s| {
@@ -206,121 +373,55 @@ fn handle_ipc(
                     s2
                 })
                 .collect();
-            if let Some(ref sm) = msg.server_manager {
-                data.server_manager = sm.clone();
-            }
-            if let Some(parent) = config_path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            match data.save_to_file(config_path) {
-                Ok(()) => {
-                    let mut data = spectre_core::server::ServerLauncherData::load_from_file(config_path)
-                        .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-                    crate::ensure_server_utility_has_defaults(&mut data);
-                    if let Ok(pids) = shared_pids.lock() {
-                        for server in data.servers.iter_mut() {
-                            server.running = pids.contains_key(&server.port);
-                        }
-                    }
-                    for server in data.servers.iter_mut() {
-                        let path_str = trim_path_quotes(&server.mpmaplist_path);
-                        let maps = if path_str.is_empty() {
-                            std::collections::HashMap::new()
-                        } else {
-                            spectre_core::mpmaplist::load_from_path(std::path::Path::new(&path_str))
-                        };
-                        server.available_maps_by_style = maps;
-                    }
-                    match serde_json::to_string(&data.servers) {
-                        Ok(json) => responses.push(format!("STATE:{}", json)),
-                        Err(_) => responses.push("Saved OK".to_string()),
-                    }
+            let server_manager = msg.server_manager.clone();
+            let mut changes = Vec::new();
+            let result = spectre_core::server::ServerLauncherData::update_file(config_path, msg.revision, |data| {
+                let before = data.clone();
+                data.replace_servers_for(user, servers);
+                if let Some(sm) = server_manager {
+                    data.server_manager = sm;
                 }
-                Err(e) => responses.push(e),
-            }
-        }
-        "start" => {
-            let idx = msg.server_index.unwrap_or(0);
-            let server_opt = msg.servers.get(idx).map(|s| {
-                let mut s2 = s.clone();
-                s2.hd2ds_path = trim_path_quotes(&s.hd2ds_path);
-                s2.hd2ds_sabresquadron_path = trim_path_quotes(&s.hd2ds_sabresquadron_path);
-                s2.mpmaplist_path = trim_path_quotes(&s.mpmaplist_path);
-                s2
+                data.stamp_new_bans(&before, &user.username);
+                changes = spectre_core::audit::config_changes(&before, data);
+                Ok(())
             });
-            if let Some(ref server) = server_opt {
-                if !is_path_safe(&server.hd2ds_path)
-                    || !is_path_safe(&server.hd2ds_sabresquadron_path)
-                    || !is_path_safe(&server.mpmaplist_path)
-                {
-                    responses.push("Invalid path (unsafe characters or traversal)".to_string());
-                    return responses;
-                }
-                let exe_path = if server.use_sabre_squadron {
-                    server.hd2ds_sabresquadron_path.as_str()
-                } else {
-                    server.hd2ds_path.as_str()
-                };
-                let allowed = if server.use_sabre_squadron {
-                    is_allowed_sabre_exe_path(exe_path)
-                } else {
-                    is_allowed_hd2ds_exe_path(exe_path)
-                };
-                if !allowed {
-                    responses.push("Executable path must be HD2DS.exe or HD2DS_SabreSquadron.exe".to_string());
-                    return responses;
+            if let Ok(saved) = &result {
+                state.events.publish(ServerEvent::Config { revision: saved.revision });
+                if !changes.is_empty() {
+                    crate::record_audit(config_path, who.entry("save").changes(changes));
                 }
             }
-            match spectre_core::server::ServerLauncherData::load_from_file(config_path) {
-                Ok(_) => match server_opt {
-                    Some(server) => {
-                        match spectre_core::ds_launch::start_ds(&server) {
-                            Ok(pid) => {
-                                if let Ok(mut pids) = shared_pids.lock() {
-                                    pids.insert(server.port, pid);
-                                }
-                                responses.push("Started OK".to_string());
-                            }
-                            Err(e) => responses.push(e),
+            responses.push(crate::server_utility_save_response(result, shared_pids));
+        }
+        "start" => {
+            let status = match target.map(with_trimmed_paths) {
+                Some(server) => match validate_launch_paths(&server) {
+                    Ok(()) => match launch_server(state, &server) {
+                        Ok(_) => {
+                            crate::record_audit(config_path, who.entry("start").server(&server.id, &server.name));
+                            "Started OK".to_string()
                         }
-                    }
-                    None => responses.push("Invalid server index".to_string()),
+                        Err(e) => e,
+                    },
+                    Err(e) => e,
                 },
-                Err(e) => responses.push(e),
-            }
+                None => "Unknown server (save it first)".to_string(),
+            };
+            responses.push(status);
         }
         "stop" => {
-            let idx = msg.server_index.unwrap_or(0);
-            if let Some(server) = msg.servers.get(idx) {
-                let port = server.port;
-                let mut pids = match shared_pids.lock() {
-                    Ok(g) => g,
-                    Err(_) => {
-                        responses.push("Stop failed (lock)".to_string());
-                        return responses;
-                    }
-                };
-                if let Some(&pid) = pids.get(&port) {
-                    pids.remove(&port);
-                    if let Some(ref k) = shared_helper_kicked {
-                        let _ = k.lock().map(|mut m| m.remove(&port));
-                    }
-                    if let Some(ref last) = shared_helper_last_slots {
-                        let _ = last.lock().map(|mut m| m.remove(&port));
-                    }
-                    drop(pids);
-                    let status = if crate::kill_process_by_pid(pid) {
-                        "Stopped OK".to_string()
-                    } else {
+            let status = match target {
+                Some(server) => match stop_server(state, &server.id) {
+                    Ok(true) => {
+                        crate::record_audit(config_path, who.entry("stop").server(&server.id, &server.name));
                         "Stopped OK".to_string()
-                    };
-                    responses.push(status);
-                } else {
-                    responses.push("Server not running".to_string());
-                }
-            } else {
-                responses.push("Invalid server index".to_string());
-            }
+                    }
+                    Ok(false) => "Server not running".to_string(),
+                    Err(e) => e,
+                },
+                None => "Unknown server (save it first)".to_string(),
+            };
+            responses.push(status);
         }
         "stop_all" => {
             let mut pids = match shared_pids.lock() {
@@ -330,38 +431,37 @@ fn handle_ipc(
                     return responses;
                 }
             };
-            let to_stop: Vec<(u16, u32)> = msg
-                .servers
+            let to_stop: Vec<(String, u32)> = visible_servers
                 .iter()
-                .filter_map(|s| pids.get(&s.port).copied().map(|pid| (s.port, pid)))
+                .filter_map(|s| pids.get(&s.id).copied().map(|pid| (s.id.clone(), pid)))
                 .collect();
-            for (port, _) in &to_stop {
-                pids.remove(port);
+            for (id, _) in &to_stop {
+                pids.remove(id);
                 if let Some(ref k) = shared_helper_kicked {
-                    let _ = k.lock().map(|mut m| m.remove(port));
+                    let _ = k.lock().map(|mut m| m.remove(id));
                 }
                 if let Some(ref last) = shared_helper_last_slots {
-                    let _ = last.lock().map(|mut m| m.remove(port));
+                    let _ = last.lock().map(|mut m| m.remove(id));
                 }
             }
             drop(pids);
-            for (_, pid) in &to_stop {
+            for (id, pid) in &to_stop {
                 crate::kill_process_by_pid(*pid);
+                state.events.publish(ServerEvent::Process {
+                    server_id: id.clone(),
+                    running: false,
+                    pid: None,
+                    reason: "stop",
+                });
+                if let Some(server) = visible_servers.iter().find(|s| &s.id == id) {
+                    crate::record_audit(config_path, who.entry("stop").server(&server.id, &server.name));
+                }
             }
             responses.push("All servers stopped".to_string());
         }
         "start_all" => {
-            let servers: Vec<spectre_core::server::Server> = msg
-                .servers
-                .iter()
-                .map(|s| {
-                    let mut s2 = s.clone();
-                    s2.hd2ds_path = trim_path_quotes(&s.hd2ds_path);
-                    s2.hd2ds_sabresquadron_path = trim_path_quotes(&s.hd2ds_sabresquadron_path);
-                    s2.mpmaplist_path = trim_path_quotes(&s.mpmaplist_path);
-                    s2
-                })
-                .collect();
+            let servers: Vec<spectre_core::server::Server> =
+                visible_servers.iter().map(|s| with_trimmed_paths(s)).collect();
             for s in &servers {
                 if !is_path_safe(&s.hd2ds_path)
                     || !is_path_safe(&s.hd2ds_sabresquadron_path)
@@ -391,15 +491,26 @@ fn handle_ipc(
             let mut started = Vec::new();
             for server in &servers {
                 match spectre_core::ds_launch::start_ds(server) {
-                    Ok(pid) => started.push((server.port, pid)),
+                    Ok(pid) => {
+                        started.push((server.id.clone(), pid));
+                        crate::record_audit(config_path, who.entry("start").server(&server.id, &server.name));
+                    }
                     Err(e) => errs.push(format!("{}: {}", server.name, e)),
                 }
             }
             if let Ok(mut pids) = pids_b.lock() {
-                for (port, pid) in started {
-                    pids.insert(port, pid);
+                for (id, pid) in &started {
+                    pids.insert(id.clone(), *pid);
                 }
             }
+            for (id, pid) in started {
+                state.events.publish(ServerEvent::Process {
+                    server_id: id,
+                    running: true,
+                    pid: Some(pid),
+                    reason: "start",
+                });
+            }
             let status = if errs.is_empty() {
                 "All servers started".to_string()
             } else {
@@ -408,49 +519,29 @@ fn handle_ipc(
             responses.push(status);
         }
         "get_running" => {
-            let ports: Vec<u16> = shared_pids
+            let ids: Vec<&str> = shared_pids
                 .lock()
-                .map(|p| p.keys().copied().collect())
+                .map(|p| p.keys().filter_map(|id| visible_ids.get(id.as_str()).copied()).collect())
                 .unwrap_or_default();
             responses.push(format!(
                 "RUNNING:{}",
-                serde_json::to_string(&ports).unwrap_or_else(|_| "[]".to_string())
+                serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string())
             ));
         }
         "get_players" => {
-            let idx = msg.server_index.unwrap_or(0);
-            let (status, list_json) = match msg.servers.get(idx) {
+            let (status, list_json) = match target {
                 Some(server) => {
-                    let pid = shared_pids
-                        .lock()
-                        .ok()
-                        .and_then(|p| p.get(&server.port).copied());
-                    let max_clients = server
-                        .configs
-                        .iter()
-                        .find(|c| c.name == server.current_config)
-                        .map(|c| c.max_clients as u32)
-                        .unwrap_or(32);
-                    let status = match pid {
-                        Some(pid) => match crate::ds_helper::get_player_count(pid, max_clients) {
-                            Some((active, total)) => format!("PLAYERS:{},{}", active, total),
-                            None => "PLAYERS:--,--".to_string(),
-                        },
+                    let (counts, players) = player_info(state, server);
+                    let status = match counts {
+                        Some((active, total)) => format!("PLAYERS:{},{}", active, total),
                         None => "PLAYERS:--,--".to_string(),
                     };
-                    let list_json = match pid {
-                        Some(pid) => crate::ds_helper::get_player_list(pid)
-                            .map(|list| {
-                                let arr: Vec<serde_json::Value> = list
-                                    .iter()
-                                    .map(|(n, i)| serde_json::json!({"name": n, "ip": i}))
-                                    .collect();
-                                serde_json::to_string(&arr).unwrap_or_else(|_| "[]".to_string())
-                            })
-                            .unwrap_or_else(|| "[]".to_string()),
-                        None => "[]".to_string(),
-                    };
-                    (status, list_json)
+                    let arr: Vec<serde_json::Value> = players
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|(n, i)| serde_json::json!({"name": n, "ip": i}))
+                        .collect();
+                    (status, serde_json::to_string(&arr).unwrap_or_else(|_| "[]".to_string()))
                 }
                 _ => ("PLAYERS:--,--".to_string(), "[]".to_string()),
             };
@@ -458,19 +549,22 @@ fn handle_ipc(
             responses.push(format!("PLAYER_LIST:{}", list_json));
         }
         "get_log_content" => {
-            let path = crate::app_log_path(config_path);
-            crate::ensure_log_file_exists(&path);
-            const MAX_LOG_BYTES: usize = 32 * 1024;
-            let content = match std::fs::read(&path) {
-                Ok(b) => {
-                    let start = b.len().saturating_sub(MAX_LOG_BYTES);
-                    String::from_utf8_lossy(&b[start..])
-                        .replace('\r', "")
-                        .replace('\0', "")
-                }
-                Err(_) => String::new(),
+            let filter = spectre_core::logging::LogFilter {
+                server_id: msg.server_id.clone(),
+                level: msg.log_level,
+                visible_servers: log_scope(&data, user),
+                ..Default::default()
             };
-            responses.push(format!("LOG_CONTENT:{}", content));
+            responses.push(format!("LOG_CONTENT:{}", crate::read_app_log(&filter)));
+        }
+        "search_player_history" => {
+            let mut query = msg.history.clone().unwrap_or_default();
+            query.visible_servers = Some(visible_ids.iter().map(|id| id.to_string()).collect());
+            responses.push(crate::search_player_history(config_path, &query));
+        }
+        "get_ban_subscriptions" => responses.push(crate::ban_subscription_status(config_path)),
+        "import_list" | "export_list" => {
+            responses.push(crate::server_utility_list_ipc(&msg.action, msg.list.as_ref()));
         }
         "repaint" => responses.push("REPAINT".to_string()),
         "refresh_mpmaplist" => {
@@ -493,6 +587,26 @@ fn handle_ipc(
         "browse_mpmaplist" | "browse_hd2_dir" => {
             responses.push("BROWSE_NOT_AVAILABLE".to_string());
         }
+        "restore_previous_config" => {
+            match spectre_core::server::ServerLauncherData::restore_backup(config_path, 1) {
+                Ok(mut restored) => {
+                    state.events.publish(ServerEvent::Config { revision: restored.revision });
+                    crate::record_audit(
+                        config_path,
+                        who.entry("restore").changes(spectre_core::audit::config_changes(&data, &restored)),
+                    );
+                    crate::annotate_server_utility_state(&mut restored, shared_pids);
+                    match serde_json::to_string(&restored) {
+                        Ok(json) => responses.push(format!("RESTORED:{}", json)),
+                        Err(e) => responses.push(format!("Restore failed: {}", e)),
+                    }
+                }
+                Err(e) => responses.push(format!("Restore failed: {}", e)),
+            }
+        }
+        "get_users" | "set_user" | "delete_user" => {
+            responses.push(crate::server_utility_user_ipc(config_path, &msg.action, msg.user.as_ref(), who));
+        }
         "open_log_file" => {
             let path = crate::app_log_path(config_path);
             crate::ensure_log_file_exists(&path);
@@ -517,13 +631,24 @@ fn handle_ipc(
 #[derive(Deserialize)]
 struct IpcSaveMessage {
     action: String,
+    #[serde(default)]
     servers: Vec<spectre_core::server::Server>,
     #[serde(default)]
-    server_index: Option<usize>,
+    server_id: Option<String>,
     #[serde(default)]
     server_manager: Option<spectre_core::server::ServerManager>,
     #[serde(default)]
     browse_which: Option<String>,
+    #[serde(default)]
+    revision: Option<u64>,
+    #[serde(default)]
+    user: Option<crate::IpcUserEdit>,
+    #[serde(default)]
+    log_level: Option<Level>,
+    #[serde(default)]
+    history: Option<spectre_core::player_history::HistoryQuery>,
+    #[serde(default)]
+    list: Option<spectre_core::list_io::ListTransfer>,
 }
 
 #[derive(serde::Serialize)]
@@ -531,34 +656,99 @@ struct IpcResponse {
     responses: Vec<String>,
 }
 
-async fn serve_index(State(state): State<AppState>) -> impl IntoResponse {
+/// `LogFilter::visible_servers` for `user`: the servers assigned to them, or None for admins,
+/// who also see records about no server (logins, bans, the HTTP server itself).
+fn log_scope(
+    data: &spectre_core::server::ServerLauncherData,
+    user: &spectre_core::server::User,
+) -> Option<HashSet<String>> {
+    (user.privilege() < spectre_core::auth::Privilege::Admin).then(|| {
+        data.servers
+            .iter()
+            .filter(|s| s.is_accessible_by(user))
+            .map(|s| s.id.clone())
+            .collect()
+    })
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+/// Shown at `/` without a valid session. `no_admin_password` warns that nobody can log in yet
+/// (a fresh config, whose default admin has no password).
+fn login_page_html(no_admin_password: bool) -> String {
+    let setup = if no_admin_password {
+        "<p style=\"color:#e6b450\">No admin has a password yet, so nobody can log in. Set one in the \
+         Spectre app: Server Utility, Settings tab, Users.</p>"
+    } else {
+        ""
+    };
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Server Utility — Spectre</title></head>\
+     <body style=\"font-family:sans-serif;background:#1e1e1e;color:#ddd;padding:2em\">\
+     <h2>Server Utility</h2>{{SETUP}}<form id=\"login\" style=\"display:grid;gap:8px;max-width:260px\">\
+     <input id=\"u\" placeholder=\"Username\" autocomplete=\"username\" required>\
+     <input id=\"p\" type=\"password\" placeholder=\"Password\" autocomplete=\"current-password\" required>\
+     <button type=\"submit\">Log in</button><p id=\"e\" style=\"color:#e66\"></p></form>\
+     <p style=\"color:#999\">Users and passwords are set in the Spectre app: Server Utility, Settings tab, Users.</p>\
+     <script>document.getElementById('login').onsubmit=function(ev){ev.preventDefault();\
+     fetch('/api/login',{method:'POST',headers:{'Content-Type':'application/json'},\
+     body:JSON.stringify({username:document.getElementById('u').value,password:document.getElementById('p').value})})\
+     .then(function(r){if(r.ok){window.location.reload();}else{r.text().then(function(t){document.getElementById('e').textContent=t||'Login failed';});}})\
+     .catch(function(e){document.getElementById('e').textContent=e.message;});};</script></body></html>"
+        .replace("{{SETUP}}", setup)
+}
+
+/// Shown instead of the Server Utility when hd2_server_config.json cannot be loaded, so the
+/// UI never starts from defaults and auto-saves them over the user's servers.
+fn config_load_error_html(config_path: &std::path::Path, error: &str) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Server Utility — Spectre</title></head>\
+         <body style=\"font-family:sans-serif;background:#1e1e1e;color:#ddd;padding:2em\">\
+         <h2>Server config could not be loaded</h2><p>{}</p><p>File: <code>{}</code></p>\
+         <p>Fix or remove the file and reload this page. It has not been modified.</p></body></html>",
+        escape(error),
+        escape(&config_path.display().to_string())
+    )
+}
+
+async fn serve_index(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
     push_log(
-        &state.inner.request_log,
+        &state.inner,
+        Level::Info,
         "GET /",
-        state.inner.log_file_path.as_ref(),
-        state.inner.log_max_size_bytes,
     );
-    let token = &state.token;
     let config_path = &state.inner.config_path;
-    let path_exists = config_path.exists();
-    let mut data = spectre_core::server::ServerLauncherData::load_from_file(config_path)
-        .unwrap_or_else(|_| spectre_core::server::ServerLauncherData::default());
-    crate::ensure_server_utility_has_defaults(&mut data);
-    if let Ok(pids) = state.inner.server_pids.lock() {
-        for server in data.servers.iter_mut() {
-            server.running = pids.contains_key(&server.port);
+    let mut data = match spectre_core::server::ServerLauncherData::load_from_file(config_path) {
+        Ok(d) => d,
+        Err(e) => {
+            push_log(
+                &state.inner,
+                Level::Warn,
+                &format!("GET / config load failed: {}", e),
+            );
+            return Html(config_load_error_html(config_path, &e));
         }
+    };
+    let user = match current_user(&state, &headers) {
+        Some(u) => u,
+        None => return Html(login_page_html(data.admin_count() == 0)),
+    };
+    data.servers.retain(|s| s.is_accessible_by(&user));
+    if user.privilege() < spectre_core::auth::Privilege::Admin {
+        // Webhook and subscription URLs carry their token; only admins (who can save them) see
+        // them in full.
+        data.server_manager.redact_urls();
     }
-    for server in data.servers.iter_mut() {
-        let maps = if server.mpmaplist_path.is_empty() {
-            std::collections::HashMap::new()
-        } else {
-            let path = std::path::Path::new(&server.mpmaplist_path);
-            spectre_core::mpmaplist::load_from_path(path)
-        };
-        server.available_maps_by_style = maps;
-    }
-    let _ = path_exists;
+    crate::annotate_server_utility_state(&mut data, &state.inner.server_pids);
     let initial_json = match serde_json::to_value(&data) {
         Ok(v) => serde_json::to_string(&v).ok(),
         Err(_) => None,
@@ -571,19 +761,16 @@ async fn serve_index(State(state): State<AppState>) -> impl IntoResponse {
     .unwrap_or_else(|_| "<html><body>Error loading Server Utility</body></html>".to_string());
     let version = env!("CARGO_PKG_VERSION");
     html = html.replace("{{SPECTRE_VERSION}}", version);
-    let token_js: String = token
-        .chars()
-        .map(|c| match c {
-            '\\' => "\\\\".into(),
-            '"' => "\\\"".into(),
-            '\n' => "\\n".into(),
-            '\r' => "\\r".into(),
-            _ => c.to_string(),
-        })
-        .collect();
+    let session_js = serde_json::json!({
+        "username": user.username,
+        "privilege": user.privilege().level(),
+        "privilege_name": user.privilege().name(),
+    })
+    .to_string()
+    .replace("</", "<\\/");
     let ipc_polyfill = format!(
-        r#"window.__spectreIpcToken="{}";window.ipc={{postMessage:function(b){{var h={{'Content-Type':'application/json','X-Spectre-Token':window.__spectreIpcToken||''}};fetch('/api/ipc',{{method:'POST',headers:h,body:b}}).then(function(r){{if(!r.ok)return r.text().then(function(t){{throw new Error(t||r.status);}});return r.json();}}).then(function(d){{(d.responses||[]).forEach(function(m){{if(window.__spectreIpcStatus)window.__spectreIpcStatus(m);}});}}).catch(function(e){{if(window.__spectreIpcStatus)window.__spectreIpcStatus('Error: '+e.message);}});}}}};"#,
-        token_js
+        r#"window.__spectreSession={};window.ipc={{postMessage:function(b){{var h={{'Content-Type':'application/json'}};fetch('/api/ipc',{{method:'POST',headers:h,body:b,credentials:'same-origin'}}).then(function(r){{if(r.status===401){{window.location.reload();return {{responses:[]}};}}if(!r.ok)return r.json().then(function(d){{throw new Error((d.responses||[]).join(' ')||r.status);}},function(){{throw new Error(r.status);}});return r.json();}}).then(function(d){{(d.responses||[]).forEach(function(m){{if(window.__spectreIpcStatus)window.__spectreIpcStatus(m);}});}}).catch(function(e){{if(window.__spectreIpcStatus)window.__spectreIpcStatus('Error: '+e.message);}});}}}};"#,
+        session_js
     );
     if let Some(pos) = html.find("<script>") {
         html.insert_str(pos + 8, &ipc_polyfill);
@@ -591,73 +778,949 @@ async fn serve_index(State(state): State<AppState>) -> impl IntoResponse {
     Html(html)
 }
 
+async fn api_login(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<LoginRequest>,
+) -> Response {
+    let ip = addr.ip();
+    // Checked before the password so a locked-out client cannot keep Argon2 busy either. The
+    // attempt counts as failed until the password is verified.
+    let locked = state
+        .login_throttle
+        .lock()
+        .map(|mut t| t.begin_attempt(ip, Instant::now()))
+        .unwrap_or(Ok(()));
+    if let Err(retry_after) = locked {
+        push_log(
+            &state.inner,
+            Level::Warn,
+            &format!("POST /api/login {} from {} (429 too many failed logins)", req.username, ip),
+        );
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+            "Too many failed logins, try again later",
+        )
+            .into_response();
+    }
+    let data = match spectre_core::server::ServerLauncherData::load_from_file(&state.inner.config_path) {
+        Ok(d) => d,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let user = match data.authenticate(&req.username, &req.password) {
+        Some(u) => u.clone(),
+        None => {
+            push_log(
+                &state.inner,
+                Level::Warn,
+                &format!("POST /api/login {} from {} (401 invalid credentials)", req.username, ip),
+            );
+            return (StatusCode::UNAUTHORIZED, "Invalid username or password").into_response();
+        }
+    };
+    if let Ok(mut t) = state.login_throttle.lock() {
+        t.record_success(ip);
+    }
+    let token = generate_token();
+    if let Ok(mut sessions) = state.sessions.lock() {
+        let now = Instant::now();
+        sessions.retain(|_, s| s.expires > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                username: user.username.clone(),
+                password_hash: user.password_hash.clone(),
+                expires: now + SESSION_TTL,
+            },
+        );
+    }
+    push_log(
+        &state.inner,
+        Level::Info,
+        &format!("POST /api/login {} ({})", user.username, user.privilege().name()),
+    );
+    (
+        StatusCode::OK,
+        [(header::SET_COOKIE, session_cookie(&token, SESSION_TTL.as_secs(), state.tls))],
+        Json(serde_json::json!({
+            "username": user.username,
+            "privilege": user.privilege().level(),
+        })),
+    )
+        .into_response()
+}
+
+async fn api_logout(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let (Some(token), Ok(mut sessions)) = (session_token(&headers), state.sessions.lock()) {
+        sessions.remove(&token);
+    }
+    (
+        StatusCode::NO_CONTENT,
+        [(header::SET_COOKIE, session_cookie("", 0, state.tls))],
+    )
+        .into_response()
+}
+
+fn audit_source(user: &spectre_core::server::User, addr: SocketAddr) -> crate::AuditSource {
+    crate::AuditSource {
+        actor: user.username.clone(),
+        source: addr.ip().to_string(),
+    }
+}
+
 async fn api_ipc(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(msg): Json<IpcSaveMessage>,
 ) -> impl IntoResponse {
-    let supplied = headers
-        .get(IPC_TOKEN_HEADER)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    if supplied.is_empty() || supplied != state.token {
-    push_log(
-        &state.inner.request_log,
-        &format!("POST /api/ipc {} (403 forbidden)", msg.action),
-        state.inner.log_file_path.as_ref(),
-        state.inner.log_max_size_bytes,
-    );
+    let user = match current_user(&state, &headers) {
+        Some(u) => u,
+        None => {
+            push_log(
+                &state.inner,
+                Level::Warn,
+                &format!("POST /api/ipc {} (401 not logged in)", msg.action),
+            );
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(IpcResponse {
+                    responses: vec!["Unauthorized".to_string()],
+                }),
+            );
+        }
+    };
+    let required = spectre_core::auth::required_privilege(&msg.action);
+    if user.privilege() < required {
+        push_log(
+            &state.inner,
+            Level::Warn,
+            &format!("POST /api/ipc {} by {} (403 needs {})", msg.action, user.username, required.name()),
+        );
         return (
             StatusCode::FORBIDDEN,
             Json(IpcResponse {
-                responses: vec!["Forbidden".to_string()],
+                responses: vec![format!("Forbidden: {} requires {} privilege", msg.action, required.name())],
             }),
         );
     }
     push_log(
-        &state.inner.request_log,
-        &format!("POST /api/ipc {}", msg.action),
-        state.inner.log_file_path.as_ref(),
-        state.inner.log_max_size_bytes,
+        &state.inner,
+        Level::Info,
+        &format!("POST /api/ipc {} by {}", msg.action, user.username),
     );
     if state.shutdown.load(Ordering::Relaxed) {
         return (StatusCode::SERVICE_UNAVAILABLE, Json(IpcResponse { responses: vec![] }));
     }
-    let responses = handle_ipc(&state.inner, &msg);
+    let responses = handle_ipc(&state.inner, &msg, &user, &audit_source(&user, addr));
     (StatusCode::OK, Json(IpcResponse { responses }))
 }
 
+// ---- REST API (/api/v1) ----
+//
+// Typed JSON over the same operations as /api/ipc, for scripts and tools. Same session
+// cookie and privilege rules; servers are addressed by `Server::id` and servers
+// not assigned to the caller answer 404. Config writes honour `If-Match` with the
+// config revision (returned as `ETag`) and fail with 409 when it is stale.
+
+type ApiResult = Result<Response, ApiError>;
+
+/// JSON error body `{"error": ...}`; `revision` is set for 409 responses.
+struct ApiError {
+    status: StatusCode,
+    message: String,
+    revision: Option<u64>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self.revision {
+            Some(revision) => (
+                self.status,
+                [(header::ETAG, format!("\"{}\"", revision))],
+                Json(serde_json::json!({ "error": self.message, "revision": revision })),
+            )
+                .into_response(),
+            None => (self.status, Json(serde_json::json!({ "error": self.message }))).into_response(),
+        }
+    }
+}
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> ApiError {
+    ApiError {
+        status,
+        message: message.into(),
+        revision: None,
+    }
+}
+
+fn api_json<T: Serialize>(revision: u64, body: &T) -> Response {
+    (
+        StatusCode::OK,
+        [(header::ETAG, format!("\"{}\"", revision))],
+        Json(body),
+    )
+        .into_response()
+}
+
+fn api_user(
+    state: &AppState,
+    headers: &HeaderMap,
+    required: spectre_core::auth::Privilege,
+    request: &str,
+) -> Result<spectre_core::server::User, ApiError> {
+    let Some(user) = current_user(state, headers) else {
+        push_log(
+            &state.inner,
+            Level::Warn,
+            &format!("{} (401 not logged in)", request),
+        );
+        return Err(api_error(StatusCode::UNAUTHORIZED, "Not logged in"));
+    };
+    if user.privilege() < required {
+        push_log(
+            &state.inner,
+            Level::Warn,
+            &format!("{} by {} (403 needs {})", request, user.username, required.name()),
+        );
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            format!("Requires {} privilege", required.name()),
+        ));
+    }
+    push_log(
+        &state.inner,
+        Level::Info,
+        &format!("{} by {}", request, user.username),
+    );
+    Ok(user)
+}
+
+fn api_load(state: &AppState) -> Result<spectre_core::server::ServerLauncherData, ApiError> {
+    spectre_core::server::ServerLauncherData::load_from_file(&state.inner.config_path)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+fn api_server<'a>(
+    data: &'a spectre_core::server::ServerLauncherData,
+    id: &str,
+    user: &spectre_core::server::User,
+) -> Result<&'a spectre_core::server::Server, ApiError> {
+    data.server_by_id(id)
+        .filter(|s| s.is_accessible_by(user))
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, format!("No server {}", id)))
+}
+
+/// Revision from `If-Match: "12"` (quotes optional); `Err` if the header is malformed.
+fn if_match_revision(headers: &HeaderMap) -> Result<Option<u64>, ApiError> {
+    match headers.get(header::IF_MATCH) {
+        None => Ok(None),
+        Some(v) => v
+            .to_str()
+            .ok()
+            .map(|v| v.trim().trim_start_matches("W/").trim_matches('"'))
+            .and_then(|v| v.parse().ok())
+            .map(Some)
+            .ok_or_else(|| api_error(StatusCode::BAD_REQUEST, "If-Match must be a config revision")),
+    }
+}
+
+fn api_update_error(e: UpdateError) -> ApiError {
+    match e {
+        UpdateError::Conflict(current) => ApiError {
+            status: StatusCode::CONFLICT,
+            message: "Config was changed elsewhere".to_string(),
+            revision: Some(current.revision),
+        },
+        UpdateError::NotFound(e) => api_error(StatusCode::NOT_FOUND, e),
+        UpdateError::Invalid(e) => api_error(StatusCode::BAD_REQUEST, e),
+        UpdateError::Io(e) => api_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[derive(Serialize)]
+struct ApiStatus {
+    version: &'static str,
+    revision: u64,
+    servers: usize,
+    servers_running: usize,
+}
+
+#[derive(Serialize)]
+struct ApiServerSummary {
+    id: String,
+    name: String,
+    port: u16,
+    running: bool,
+    use_sabre_squadron: bool,
+    current_config: String,
+}
+
+#[derive(Serialize)]
+struct ApiPlayers {
+    running: bool,
+    active: Option<u32>,
+    total: Option<u32>,
+    players: Vec<ApiPlayer>,
+}
+
+#[derive(Serialize)]
+struct ApiPlayer {
+    name: String,
+    ip: String,
+}
+
+#[derive(Deserialize)]
+struct LogQuery {
+    lines: Option<usize>,
+    server_id: Option<String>,
+    /// Minimum level.
+    level: Option<spectre_core::logging::Level>,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// csv, json (default) or text.
+    format: Option<String>,
+    /// Required for ban_list and whitelist.
+    server_id: Option<String>,
+    /// Defaults to the server's current config.
+    config: Option<String>,
+}
+
+impl ListQuery {
+    fn format(&self) -> Result<spectre_core::list_io::ListFormat, ApiError> {
+        self.format
+            .as_deref()
+            .map_or(Ok(Default::default()), str::parse)
+            .map_err(|e: String| api_error(StatusCode::BAD_REQUEST, e))
+    }
+}
+
+fn running_ids(state: &AppState) -> HashSet<String> {
+    state
+        .inner
+        .server_pids
+        .lock()
+        .map(|p| p.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+async fn api_v1_status(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /api/v1/status")?;
+    let data = api_load(&state)?;
+    let running = running_ids(&state);
+    let visible: Vec<_> = data.servers.iter().filter(|s| s.is_accessible_by(&user)).collect();
+    Ok(api_json(
+        data.revision,
+        &ApiStatus {
+            version: env!("CARGO_PKG_VERSION"),
+            revision: data.revision,
+            servers: visible.len(),
+            servers_running: visible.iter().filter(|s| running.contains(&s.id)).count(),
+        },
+    ))
+}
+
+async fn api_v1_servers(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /api/v1/servers")?;
+    let data = api_load(&state)?;
+    let running = running_ids(&state);
+    let list: Vec<ApiServerSummary> = data
+        .servers
+        .iter()
+        .filter(|s| s.is_accessible_by(&user))
+        .map(|s| ApiServerSummary {
+            id: s.id.clone(),
+            name: s.name.clone(),
+            port: s.port,
+            running: running.contains(&s.id),
+            use_sabre_squadron: s.use_sabre_squadron,
+            current_config: s.current_config.clone(),
+        })
+        .collect();
+    Ok(api_json(data.revision, &list))
+}
+
+async fn api_v1_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let request = format!("GET /api/v1/servers/{}", id);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, &request)?;
+    let data = api_load(&state)?;
+    let mut server = api_server(&data, &id, &user)?.clone();
+    server.running = running_ids(&state).contains(&server.id);
+    Ok(api_json(data.revision, &server))
+}
+
+async fn api_v1_server_start(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let request = format!("POST /api/v1/servers/{}/start", id);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Operator, &request)?;
+    let data = api_load(&state)?;
+    let server = with_trimmed_paths(api_server(&data, &id, &user)?);
+    if running_ids(&state).contains(&server.id) {
+        return Err(api_error(StatusCode::CONFLICT, "Server is already running"));
+    }
+    validate_launch_paths(&server).map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    let pid = launch_server(&state.inner, &server)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    crate::record_audit(
+        &state.inner.config_path,
+        audit_source(&user, addr).entry("start").server(&server.id, &server.name),
+    );
+    Ok(Json(serde_json::json!({ "running": true, "pid": pid })).into_response())
+}
+
+async fn api_v1_server_stop(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let request = format!("POST /api/v1/servers/{}/stop", id);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Operator, &request)?;
+    let data = api_load(&state)?;
+    let server = api_server(&data, &id, &user)?;
+    match stop_server(&state.inner, &server.id) {
+        Ok(true) => {
+            crate::record_audit(
+                &state.inner.config_path,
+                audit_source(&user, addr).entry("stop").server(&server.id, &server.name),
+            );
+            Ok(Json(serde_json::json!({ "running": false })).into_response())
+        }
+        Ok(false) => Err(api_error(StatusCode::CONFLICT, "Server is not running")),
+        Err(e) => Err(api_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+async fn api_v1_players(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let request = format!("GET /api/v1/servers/{}/players", id);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, &request)?;
+    let data = api_load(&state)?;
+    let server = api_server(&data, &id, &user)?;
+    let (counts, players) = player_info(&state.inner, server);
+    Ok(Json(ApiPlayers {
+        running: running_ids(&state).contains(&server.id),
+        active: counts.map(|c| c.0),
+        total: counts.map(|c| c.1),
+        players: players
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, ip)| ApiPlayer { name, ip })
+            .collect(),
+    })
+    .into_response())
+}
+
+async fn api_v1_configs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let request = format!("GET /api/v1/servers/{}/configs", id);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, &request)?;
+    let data = api_load(&state)?;
+    let server = api_server(&data, &id, &user)?;
+    Ok(api_json(data.revision, &server.configs))
+}
+
+async fn api_v1_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, name)): Path<(String, String)>,
+) -> ApiResult {
+    let request = format!("GET /api/v1/servers/{}/configs/{}", id, name);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, &request)?;
+    let data = api_load(&state)?;
+    let server = api_server(&data, &id, &user)?;
+    let config = server
+        .configs
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, format!("No config {}", name)))?;
+    Ok(api_json(data.revision, config))
+}
+
+/// Create or replace a config profile. The profile name comes from the path.
+async fn api_v1_config_put(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path((id, name)): Path<(String, String)>,
+    Json(mut config): Json<spectre_core::server::ServerConfig>,
+) -> ApiResult {
+    let request = format!("PUT /api/v1/servers/{}/configs/{}", id, name);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Admin, &request)?;
+    let expected = if_match_revision(&headers)?;
+    if name.trim().is_empty() {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Config name is required"));
+    }
+    api_server(&api_load(&state)?, &id, &user)?;
+    config.name = name.clone();
+    let mut changes = Vec::new();
+    let data = spectre_core::server::ServerLauncherData::update_file(&state.inner.config_path, expected, |data| {
+        let before = data.clone();
+        let server = data
+            .servers
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| UpdateError::NotFound(format!("No server {}", id)))?;
+        match server.configs.iter_mut().find(|c| c.name == name) {
+            Some(existing) => *existing = config.clone(),
+            None => server.configs.push(config.clone()),
+        }
+        data.stamp_new_bans(&before, &user.username);
+        changes = spectre_core::audit::config_changes(&before, data);
+        Ok(())
+    })
+    .map_err(api_update_error)?;
+    state.inner.events.publish(ServerEvent::Config { revision: data.revision });
+    if !changes.is_empty() {
+        crate::record_audit(&state.inner.config_path, audit_source(&user, addr).entry("save").changes(changes));
+    }
+    Ok(api_json(data.revision, &config))
+}
+
+async fn api_v1_config_delete(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path((id, name)): Path<(String, String)>,
+) -> ApiResult {
+    let request = format!("DELETE /api/v1/servers/{}/configs/{}", id, name);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Admin, &request)?;
+    let expected = if_match_revision(&headers)?;
+    let current = api_load(&state)?;
+    let server = api_server(&current, &id, &user)?;
+    if !server.configs.iter().any(|c| c.name == name) {
+        return Err(api_error(StatusCode::NOT_FOUND, format!("No config {}", name)));
+    }
+    if server.current_config == name {
+        return Err(api_error(StatusCode::BAD_REQUEST, "Cannot delete the server's current config"));
+    }
+    let data = spectre_core::server::ServerLauncherData::update_file(&state.inner.config_path, expected, |data| {
+        let server = data
+            .servers
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| UpdateError::NotFound(format!("No server {}", id)))?;
+        server.configs.retain(|c| c.name != name);
+        Ok(())
+    })
+    .map_err(api_update_error)?;
+    state.inner.events.publish(ServerEvent::Config { revision: data.revision });
+    crate::record_audit(
+        &state.inner.config_path,
+        audit_source(&user, addr)
+            .entry("delete_config")
+            .server(&server.id, &server.name)
+            .target(name.as_str()),
+    );
+    Ok((StatusCode::NO_CONTENT, [(header::ETAG, format!("\"{}\"", data.revision))]).into_response())
+}
+
+async fn api_v1_logs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LogQuery>,
+) -> ApiResult {
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /api/v1/logs")?;
+    let data = api_load(&state)?;
+    let filter = spectre_core::logging::LogFilter {
+        server_id: query.server_id,
+        level: query.level,
+        limit: query.lines,
+        visible_servers: log_scope(&data, &user),
+        ..Default::default()
+    };
+    let content = crate::read_app_log(&filter);
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(Json(serde_json::json!({ "lines": lines })).into_response())
+}
+
+async fn api_v1_player_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(mut query): Query<spectre_core::player_history::HistoryQuery>,
+) -> ApiResult {
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /api/v1/players/history")?;
+    let data = api_load(&state)?;
+    query.visible_servers = Some(
+        data.servers
+            .iter()
+            .filter(|s| s.is_accessible_by(&user))
+            .map(|s| s.id.clone())
+            .collect(),
+    );
+    let result = spectre_core::player_history::search(&crate::player_history_path(&state.inner.config_path), &query)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(result).into_response())
+}
+
+fn api_list_name(name: &str) -> Result<spectre_core::list_io::ListName, ApiError> {
+    name.parse().map_err(|e: String| api_error(StatusCode::NOT_FOUND, e))
+}
+
+async fn api_v1_list_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult {
+    let request = format!("GET /api/v1/lists/{}", name);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, &request)?;
+    let list = api_list_name(&name)?;
+    let format = query.format()?;
+    let data = api_load(&state)?;
+    if let Some(id) = query.server_id.as_deref() {
+        api_server(&data, id, &user)?;
+    }
+    let entries = spectre_core::list_io::list_entries(&data, list, query.server_id.as_deref(), query.config.as_deref())
+        .map_err(|e| api_error(StatusCode::NOT_FOUND, e))?;
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", list.as_str(), format.extension()),
+            ),
+        ],
+        spectre_core::list_io::export(&entries, format),
+    )
+        .into_response())
+}
+
+/// Merge the request body (in `format`) into a list; replies `{"added": n}`.
+async fn api_v1_list_import(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Query(query): Query<ListQuery>,
+    body: String,
+) -> ApiResult {
+    let request = format!("POST /api/v1/lists/{}/import", name);
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Admin, &request)?;
+    let expected = if_match_revision(&headers)?;
+    let list = api_list_name(&name)?;
+    let imported = spectre_core::list_io::import(&body, query.format()?)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    if let Some(id) = query.server_id.as_deref() {
+        api_server(&api_load(&state)?, id, &user)?;
+    }
+    let mut added = 0;
+    let mut changes = Vec::new();
+    let data = spectre_core::server::ServerLauncherData::update_file(&state.inner.config_path, expected, |data| {
+        let before = data.clone();
+        added = spectre_core::list_io::import_into(
+            data,
+            list,
+            query.server_id.as_deref(),
+            query.config.as_deref(),
+            imported.clone(),
+        )
+        .map_err(UpdateError::NotFound)?;
+        data.stamp_new_bans(&before, &user.username);
+        changes = spectre_core::audit::config_changes(&before, data);
+        Ok(())
+    })
+    .map_err(api_update_error)?;
+    state.inner.events.publish(ServerEvent::Config { revision: data.revision });
+    if !changes.is_empty() {
+        crate::record_audit(
+            &state.inner.config_path,
+            audit_source(&user, addr).entry("import_list").changes(changes),
+        );
+    }
+    Ok(api_json(data.revision, &serde_json::json!({ "added": added })))
+}
+
+/// Server-Sent Events: the current state of each visible server, then live process, player,
+/// log and config events. Ends when the session is no longer valid, checked on config saves
+/// and every `EVENT_SESSION_CHECK`.
+async fn api_v1_events(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    let user = api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /api/v1/events")?;
+    let data = api_load(&state)?;
+    let mut filter = EventFilter::for_user(&data, &user);
+
+    let pids = state.inner.server_pids.lock().map(|p| p.clone()).unwrap_or_default();
+    let slots = state
+        .inner
+        .helper_last_slots
+        .as_ref()
+        .and_then(|m| m.lock().ok().map(|m| m.clone()))
+        .unwrap_or_default();
+    let mut initial = Vec::new();
+    for id in &filter.servers {
+        let pid = pids.get(id).copied();
+        initial.push(ServerEvent::Process {
+            server_id: id.clone(),
+            running: pid.is_some(),
+            pid,
+            reason: "current",
+        });
+        if let (Some(_), Some(players)) = (pid, slots.get(id)) {
+            initial.push(ServerEvent::players(id, players));
+        }
+    }
+
+    // Subscribe before returning so nothing published after the snapshot is missed.
+    let mut receiver = state.inner.events.subscribe();
+    let (tx, rx) = tokio::sync::mpsc::channel(32);
+    tokio::spawn(async move {
+        let mut session_check = tokio::time::interval(EVENT_SESSION_CHECK);
+        session_check.tick().await;
+        loop {
+            let item = tokio::select! {
+                _ = tx.closed() => return,
+                _ = session_check.tick() => None,
+                item = receiver.recv() => match item {
+                    Ok(event) => Some(Ok(event)),
+                    Err(RecvError::Lagged(n)) => Some(Err(n)),
+                    Err(RecvError::Closed) => return,
+                },
+            };
+            if matches!(item, None | Some(Ok(ServerEvent::Config { .. }))) {
+                // A save can log the user out or change their servers; the file is read off
+                // the async workers.
+                let (state, headers) = (state.clone(), headers.clone());
+                let refreshed = tokio::task::spawn_blocking(move || {
+                    let user = current_user(&state, &headers)?;
+                    let data = spectre_core::server::ServerLauncherData::load_from_file(&state.inner.config_path).ok()?;
+                    Some(EventFilter::for_user(&data, &user))
+                })
+                .await;
+                match refreshed {
+                    Ok(Some(refreshed)) => filter = refreshed,
+                    _ => return,
+                }
+            }
+            let event = match item {
+                Some(Ok(event)) if filter.allows(&event) => Event::default().json_data(&event),
+                // The client fell behind; it should re-request state instead of trusting deltas.
+                Some(Err(n)) => Ok(Event::default().event("lagged").data(n.to_string())),
+                _ => continue,
+            };
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    let stream = tokio_stream::iter(initial.into_iter().map(|event| Event::default().json_data(&event)))
+        .chain(ReceiverStream::new(rx));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// `Authorization: Bearer <token>` matches the configured metrics token. Compared in
+/// constant time so the token cannot be guessed byte by byte.
+fn metrics_token_matches(state: &AppState, headers: &HeaderMap) -> bool {
+    let expected = state.inner.metrics_token.as_bytes();
+    let Some(given) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    !expected.is_empty()
+        && given.len() == expected.len()
+        && given.bytes().zip(expected).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Prometheus scrape target. With the metrics token every server is reported; with a session
+/// only the servers assigned to that user.
+async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    let user = if metrics_token_matches(&state, &headers) {
+        None
+    } else {
+        Some(api_user(&state, &headers, spectre_core::auth::Privilege::Viewer, "GET /metrics")?)
+    };
+    let data = api_load(&state)?;
+    let pids = state.inner.server_pids.lock().map(|p| p.clone()).unwrap_or_default();
+    let slots = state
+        .inner
+        .helper_last_slots
+        .as_ref()
+        .and_then(|m| m.lock().ok().map(|m| m.clone()))
+        .unwrap_or_default();
+    let now = spectre_core::util::unix_now();
+    let samples: Vec<spectre_core::metrics::ServerSample> = data
+        .servers
+        .iter()
+        .filter(|s| user.as_ref().is_none_or(|u| s.is_accessible_by(u)))
+        .map(|s| {
+            let stats = pids.get(&s.id).and_then(|&pid| crate::process_stats(pid));
+            spectre_core::metrics::ServerSample {
+                id: s.id.clone(),
+                name: s.name.clone(),
+                port: s.port,
+                up: stats.is_some(),
+                uptime_seconds: stats.as_ref().map(|p| now.saturating_sub(p.started_at)),
+                players: stats.as_ref().and(slots.get(&s.id)).map(|list| list.len()),
+                max_clients: s
+                    .configs
+                    .iter()
+                    .find(|c| c.name == s.current_config)
+                    .map(|c| c.max_clients as u32)
+                    .unwrap_or(0),
+                cpu_seconds: stats.as_ref().map(|p| p.cpu_seconds),
+                memory_bytes: stats.as_ref().map(|p| p.memory_bytes).filter(|&m| m > 0),
+            }
+        })
+        .collect();
+    let counters = state.inner.fleet_counters.lock().map(|c| c.clone()).unwrap_or_default();
+    Ok((
+        [(header::CONTENT_TYPE, spectre_core::metrics::CONTENT_TYPE)],
+        spectre_core::metrics::render(&samples, &counters),
+    )
+        .into_response())
+}
+
+async fn api_v1_audit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<spectre_core::audit::AuditQuery>,
+) -> ApiResult {
+    api_user(&state, &headers, spectre_core::auth::Privilege::Admin, "GET /api/v1/audit")?;
+    let entries = spectre_core::audit::read_entries(&crate::audit_log_path(&state.inner.config_path), &query)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(entries).into_response())
+}
+
+async fn api_v1_openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI_JSON)
+}
+
 pub struct ServerHandle {
     pub port: u16,
+    pub bind_ip: IpAddr,
+    pub https: bool,
     pub join_handle: Option<JoinHandle<()>>,
     pub request_log: Arc<std::sync::Mutex<Vec<String>>>,
     shutdown: Arc<AtomicBool>,
     shutdown_tx: Option<oneshot::Sender<()>>,
 }
 
+/// Where and how the web server listens.
+pub struct ListenOptions {
+    pub bind_address: String,
+    pub port: u16,
+    /// PEM certificate chain and PKCS#8 private key. Plain HTTP when `None`.
+    pub tls: Option<(PathBuf, PathBuf)>,
+}
+
+fn load_tls_acceptor(cert_path: &std::path::Path, key_path: &std::path::Path) -> Result<tokio_native_tls::TlsAcceptor, String> {
+    let cert = std::fs::read(cert_path).map_err(|e| format!("TLS certificate {}: {}", cert_path.display(), e))?;
+    let key = std::fs::read(key_path).map_err(|e| format!("TLS key {}: {}", key_path.display(), e))?;
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .map_err(|e| format!("TLS certificate/key (PEM, PKCS#8 key expected): {}", e))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity).map_err(|e| format!("TLS: {}", e))?;
+    Ok(acceptor.into())
+}
+
+const ACCEPT_BACKOFF_MIN: Duration = Duration::from_millis(50);
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `axum::serve` only speaks plain HTTP, so HTTPS connections are accepted here and handed
+/// to hyper after the handshake.
+async fn serve_tls(listener: tokio::net::TcpListener, acceptor: tokio_native_tls::TlsAcceptor, router: Router) {
+    let mut backoff = ACCEPT_BACKOFF_MIN;
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(_) => {
+                // E.g. out of file handles: wait instead of spinning on the error.
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(ACCEPT_BACKOFF_MAX);
+                continue;
+            }
+        };
+        backoff = ACCEPT_BACKOFF_MIN;
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(router.clone().layer(Extension(ConnectInfo(addr))));
+        tokio::spawn(async move {
+            // A failed or stalled handshake (e.g. a plain-HTTP client) only drops this connection.
+            let Ok(Ok(stream)) = tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await else {
+                return;
+            };
+            let _ = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
 pub fn start(
-    port: u16,
+    options: &ListenOptions,
     state: ServerUtilityHttpState,
 ) -> Result<ServerHandle, String> {
+    let bind_ip: IpAddr = options
+        .bind_address
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid bind address \"{}\"", options.bind_address))?;
+    let tls_acceptor = match &options.tls {
+        Some((cert, key)) => Some(load_tls_acceptor(cert, key)?),
+        None => None,
+    };
     let request_log = state.request_log.clone();
     let shutdown = Arc::new(AtomicBool::new(false));
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let token = generate_token();
+    crate::upgrade_server_utility_config(&state.config_path);
     let app_state = AppState {
         inner: state,
         shutdown: shutdown.clone(),
-        token,
+        sessions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        login_throttle: Arc::new(std::sync::Mutex::new(spectre_core::auth::LoginThrottle::default())),
+        tls: tls_acceptor.is_some(),
     };
+    let app_https = app_state.tls;
 
-    let listener = std::net::TcpListener::bind(("0.0.0.0", port))
-        .map_err(|e| format!("Bind {}: {}", port, e))?;
+    let listener = std::net::TcpListener::bind((bind_ip, options.port))
+        .map_err(|e| format!("Bind {}:{}: {}", bind_ip, options.port, e))?;
     listener.set_nonblocking(true).map_err(|e| e.to_string())?;
     let addr = listener.local_addr().map_err(|e| e.to_string())?;
     let actual_port = addr.port();
 
     let router = Router::new()
         .route("/", get(serve_index))
+        .route("/api/login", post(api_login))
+        .route("/api/logout", post(api_logout))
         .route("/api/ipc", post(api_ipc))
+        .route("/api/v1/openapi.json", get(api_v1_openapi))
+        .route("/api/v1/status", get(api_v1_status))
+        .route("/api/v1/servers", get(api_v1_servers))
+        .route("/api/v1/servers/:id", get(api_v1_server))
+        .route("/api/v1/servers/:id/start", post(api_v1_server_start))
+        .route("/api/v1/servers/:id/stop", post(api_v1_server_stop))
+        .route("/api/v1/servers/:id/players", get(api_v1_players))
+        .route("/api/v1/servers/:id/configs", get(api_v1_configs))
+        .route(
+            "/api/v1/servers/:id/configs/:name",
+            get(api_v1_config).put(api_v1_config_put).delete(api_v1_config_delete),
+        )
+        .route("/api/v1/logs", get(api_v1_logs))
+        .route("/api/v1/players/history", get(api_v1_player_history))
+        .route("/api/v1/lists/:name", get(api_v1_list_export))
+        .route("/api/v1/lists/:name/import", post(api_v1_list_import))
+        .route("/api/v1/events", get(api_v1_events))
+        .route("/api/v1/audit", get(api_v1_audit))
+        .route("/metrics", get(metrics))
         .with_state(app_state);
 
     let join_handle = thread::spawn(move || {
@@ -667,16 +1730,28 @@ pub fn start(
             .expect("tokio runtime");
         rt.block_on(async move {
             let listener = tokio::net::TcpListener::from_std(listener.into()).expect("tokio listener");
-            let server = axum::serve(listener, router);
-            tokio::select! {
-                r = server => { r.ok(); }
-                _ = shutdown_rx => {}
+            match tls_acceptor {
+                Some(acceptor) => {
+                    tokio::select! {
+                        _ = serve_tls(listener, acceptor, router) => {}
+                        _ = shutdown_rx => {}
+                    }
+                }
+                None => {
+                    let server = axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>());
+                    tokio::select! {
+                        r = server => { r.ok(); }
+                        _ = shutdown_rx => {}
+                    }
+                }
             }
         });
     });
 
     Ok(ServerHandle {
         port: actual_port,
+        bind_ip,
+        https: app_https,
         join_handle: Some(join_handle),
         request_log,
         shutdown,
@@ -685,6 +1760,16 @@ pub fn start(
 }
 
 impl ServerHandle {
+    /// Address to open locally: loopback and "any" binds are reachable as localhost.
+    pub fn local_url(&self) -> String {
+        let scheme = if self.https { "https" } else { "http" };
+        if self.bind_ip.is_loopback() || self.bind_ip.is_unspecified() {
+            format!("{}://localhost:{}", scheme, self.port)
+        } else {
+            format!("{}://{}", scheme, SocketAddr::new(self.bind_ip, self.port))
+        }
+    }
+
     pub fn stop(&mut self) -> Option<JoinHandle<()>> {
         self.shutdown.store(true, Ordering::Relaxed);
         if let Some(tx) = self.shutdown_tx.take() {
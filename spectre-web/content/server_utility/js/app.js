@@ -2,6 +2,7 @@
   'use strict';
 
   const state = {
+    revision: 0,
     servers: [
       {
         name: 'Server 1',
@@ -61,6 +62,12 @@
       enable_watchdog: true,
       restart_interval_days: 0,
       log_rotation_days: 0,
+      log_max_size_mb: 10,
+      log_archive_count: 5,
+      log_level: 'info',
+      webhooks: [],
+      ban_list_subscriptions: [],
+      config_backup_count: 5,
       enable_forced_ban_list: true,
       forced_ban_list: []
     }
@@ -71,6 +78,9 @@
   let lastPlayerListJson = null;
   // Track whether we currently have a running player poll interval attached.
   let lastPlayerPollRunning = false;
+  // Open /api/v1/events stream (HTTP sessions only). While set, process, player and log
+  // changes are pushed by the server and the player list is not polled.
+  let liveEvents = null;
 
   function ipcLog(msg, detail) {
     console.log('[IPC JS] ' + msg, detail !== undefined ? detail : '');
@@ -95,6 +105,7 @@
       if (initial.server_manager && typeof initial.server_manager === 'object') {
         state.server_manager = Object.assign({}, state.server_manager, initial.server_manager);
       }
+      if (typeof initial.revision === 'number') state.revision = initial.revision;
       delete window.__spectreInitialState;
     } catch (e) {
       console.warn('[IPC JS] Failed to apply __spectreInitialState:', e);
@@ -103,6 +114,10 @@
     ipcLog('No __spectreInitialState, using built-in default');
   }
 
+  // Set by the HTTP server for logged-in browser sessions; absent in the local app (full access).
+  const session = (typeof window !== 'undefined' && window.__spectreSession) || null;
+  const canEditConfig = !session || session.privilege >= 2;
+
   function getSelectedServer() {
     return state.servers[state.selectedServerIndex] || null;
   }
@@ -121,6 +136,12 @@
     el.onchange = function () {
       state.selectedServerIndex = parseInt(this.value, 10);
       state.selectedConfigIndex = 0;
+      if (liveEvents) {
+        lastPlayerListJson = null;
+        state.currentPlayerList = [];
+        state.playerCount = { active: '--', total: '--' };
+        requestPlayers();
+      }
       render();
     };
   }
@@ -159,6 +180,7 @@
         document.querySelectorAll('.tab').forEach(t => t.setAttribute('aria-selected', 'false'));
         tab.setAttribute('aria-selected', 'true');
         if (name === 'logs') requestLogContent();
+        if (name === 'general') { requestUsers(); requestBanSubscriptionStatus(); }
       };
     });
     document.querySelectorAll('.tab-panel').forEach(p => {
@@ -209,14 +231,28 @@
     set('max-ping', c.max_ping);
     set('max-freq', c.max_freq != null ? c.max_freq : 50);
     set('max-inactivity', c.max_inactivity != null ? c.max_inactivity : 0);
+    setCheck('enable-auto-kick', !!c.enable_auto_kick);
+    set('auto-kick-grace', c.auto_kick_grace != null ? c.auto_kick_grace : 30);
     set('voice-chat', c.voice_chat != null ? String(c.voice_chat) : '0');
     var sm = state.server_manager;
     if (sm) {
       setCheck('watchdog-restart-on-crash', sm.enable_watchdog != null ? sm.enable_watchdog : true);
       set('watchdog-restart-days', sm.restart_interval_days != null ? sm.restart_interval_days : 0);
       set('log-rotation-days', sm.log_rotation_days != null ? sm.log_rotation_days : 0);
+      set('log-max-size-mb', sm.log_max_size_mb != null ? sm.log_max_size_mb : 10);
+      set('log-archive-count', sm.log_archive_count != null ? sm.log_archive_count : 5);
+      set('log-level', sm.log_level || 'info');
+      set('config-backup-count', sm.config_backup_count != null ? sm.config_backup_count : 5);
     }
     setCheck('enable-whitelist', c.enable_whitelist != null ? c.enable_whitelist : false);
+    set('clan-tag', c.clan_tag || '');
+    setCheck('enforce-clan-tag', !!c.enforce_clan_tag);
+    set('clan-reserve', c.clan_reserve != null ? c.clan_reserve : 0);
+    setCheck('server-messages', s ? !!s.messages : false);
+    set('welcome-message', c.welcome_message || '');
+    set('restart-warning', c.restart_warning != null ? c.restart_warning : 'Server restarts in {next_restart}.');
+    set('restart-warning-minutes', (c.restart_warning_minutes || [10, 1]).join(', '));
+    set('message-interval', c.message_interval != null ? c.message_interval : 300);
   }
 
   function bindConfigToForm() {
@@ -270,12 +306,37 @@
     c.max_freq = parseInt(get('max-freq'), 10);
     if (isNaN(c.max_freq)) c.max_freq = 50;
     c.max_inactivity = parseInt(get('max-inactivity'), 10) || 0;
+    c.enable_auto_kick = document.getElementById('enable-auto-kick') ? document.getElementById('enable-auto-kick').checked : !!c.enable_auto_kick;
+    c.auto_kick_grace = parseInt(get('auto-kick-grace'), 10);
+    if (isNaN(c.auto_kick_grace) || c.auto_kick_grace < 0) c.auto_kick_grace = 30;
     c.voice_chat = parseInt(get('voice-chat'), 10) || 0;
     if (c.voice_chat > 6) c.voice_chat = 6;
     if (s) s.current_config = c.name;
     if (!c.ban_list) c.ban_list = [];
     if (!c.whitelist) c.whitelist = [];
     c.enable_whitelist = document.getElementById('enable-whitelist') ? document.getElementById('enable-whitelist').checked : (c.enable_whitelist != null ? c.enable_whitelist : false);
+    if (!c.name_ban_list) c.name_ban_list = [];
+    if (!c.clan_roster) c.clan_roster = [];
+    if (!c.messages) c.messages = [];
+    if (s && document.getElementById('server-messages')) s.messages = getCheck('server-messages');
+    if (document.getElementById('welcome-message')) c.welcome_message = get('welcome-message').trim();
+    if (document.getElementById('restart-warning')) c.restart_warning = get('restart-warning').trim();
+    if (document.getElementById('restart-warning-minutes')) {
+      c.restart_warning_minutes = get('restart-warning-minutes').split(',')
+        .map(function (m) { return parseInt(m, 10); })
+        .filter(function (m) { return !isNaN(m) && m > 0; });
+    }
+    if (document.getElementById('message-interval')) {
+      c.message_interval = parseInt(get('message-interval'), 10);
+      if (isNaN(c.message_interval) || c.message_interval < 1) c.message_interval = 300;
+    }
+    if (document.getElementById('clan-tag')) c.clan_tag = get('clan-tag').trim();
+    if (document.getElementById('enforce-clan-tag')) c.enforce_clan_tag = getCheck('enforce-clan-tag');
+    if (document.getElementById('clan-reserve')) {
+      c.clan_reserve = parseInt(get('clan-reserve'), 10) || 0;
+      if (c.clan_reserve < 0) c.clan_reserve = 0;
+      if (c.clan_reserve > c.max_clients) c.clan_reserve = c.max_clients;
+    }
     var sm = state.server_manager;
     if (sm) {
       sm.enable_watchdog = document.getElementById('watchdog-restart-on-crash') ? document.getElementById('watchdog-restart-on-crash').checked : sm.enable_watchdog;
@@ -289,6 +350,23 @@
         var ld = parseInt(logDaysEl.value, 10);
         sm.log_rotation_days = (isNaN(ld) || ld < 0) ? 0 : Math.min(365, ld);
       }
+      var logSizeEl = document.getElementById('log-max-size-mb');
+      if (logSizeEl) {
+        var lm = parseInt(logSizeEl.value, 10);
+        sm.log_max_size_mb = (isNaN(lm) || lm < 0) ? 10 : Math.min(1024, lm);
+      }
+      var logArchivesEl = document.getElementById('log-archive-count');
+      if (logArchivesEl) {
+        var la = parseInt(logArchivesEl.value, 10);
+        sm.log_archive_count = (isNaN(la) || la < 0) ? 5 : Math.min(50, la);
+      }
+      var logLevelEl = document.getElementById('log-level');
+      if (logLevelEl && logLevelEl.value) sm.log_level = logLevelEl.value;
+      var backupsEl = document.getElementById('config-backup-count');
+      if (backupsEl) {
+        var nb = parseInt(backupsEl.value, 10);
+        sm.config_backup_count = (isNaN(nb) || nb < 0) ? 0 : Math.min(50, nb);
+      }
     }
   }
 
@@ -296,6 +374,11 @@
   let selectedAvailableMapIndex = -1;
   let selectedBanIndex = -1;
   let selectedWhitelistIndex = -1;
+  let selectedNameBanIndex = -1;
+  let selectedRosterIndex = -1;
+  let selectedMessageIndex = -1;
+  let users = [];
+  let selectedUserIndex = -1;
   let unsavedChanges = false;
   let unsavedPollInterval = null;
   const UNSAVED_POLL_MS = 400;
@@ -341,9 +424,13 @@
   }
 
   function performSave() {
+    if (!canEditConfig) {
+      showMessage('Read-only: your account cannot change the config', 'warning');
+      return;
+    }
     bindConfigToForm();
     ensureCurrentConfigs();
-    const payload = JSON.stringify({ action: 'save', servers: state.servers, server_manager: state.server_manager });
+    const payload = JSON.stringify({ action: 'save', servers: state.servers, server_manager: state.server_manager, revision: state.revision });
     ipcLog('Auto-save', 'ipc.postMessage body=' + payload.length + ' bytes');
     if (typeof window.ipc !== 'undefined' && window.ipc.postMessage) {
       try {
@@ -361,26 +448,162 @@
     }
   }
 
+  // Server actions run against the saved config (by server id), so pending edits are
+  // saved first and the action is sent once the save is confirmed.
+  let afterSave = null;
+  function runAfterSave(fn) {
+    if (!canEditConfig || (!unsavedChanges && autoSaveTimeout === null)) {
+      fn();
+      return;
+    }
+    afterSave = fn;
+    if (autoSaveTimeout !== null) {
+      clearTimeout(autoSaveTimeout);
+      autoSaveTimeout = null;
+    }
+    performSave();
+  }
+
   function requestRunningState() {
     if (typeof window.ipc !== 'undefined' && window.ipc.postMessage) {
       try {
-        window.ipc.postMessage(JSON.stringify({ action: 'get_running', servers: state.servers }));
+        window.ipc.postMessage(JSON.stringify({ action: 'get_running' }));
       } catch (e) { /* ignore */ }
     }
   }
 
+  // Level and server chosen in the Logs tab; live log lines are filtered the same way.
+  var LOG_LEVELS = ['debug', 'info', 'warn', 'error'];
+
+  function logFilter() {
+    var levelEl = document.getElementById('logs-level');
+    var serverEl = document.getElementById('logs-this-server');
+    var current = getSelectedServer();
+    return {
+      level: levelEl && levelEl.value ? levelEl.value : null,
+      serverId: serverEl && serverEl.checked && current && current.id ? current.id : null
+    };
+  }
+
+  function logLineVisible(ev) {
+    var f = logFilter();
+    if (f.serverId && ev.server_id !== f.serverId) return false;
+    return !f.level || LOG_LEVELS.indexOf(ev.level) >= LOG_LEVELS.indexOf(f.level);
+  }
+
   function requestLogContent() {
     if (typeof window.ipc !== 'undefined' && window.ipc.postMessage) {
       try {
-        window.ipc.postMessage(JSON.stringify({ action: 'get_log_content', servers: state.servers }));
+        var f = logFilter();
+        var msg = { action: 'get_log_content' };
+        if (f.level) msg.log_level = f.level;
+        if (f.serverId) msg.server_id = f.serverId;
+        window.ipc.postMessage(JSON.stringify(msg));
       } catch (e) { /* ignore */ }
     }
   }
 
+  function requestPlayers() {
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    try {
+      var current = getSelectedServer();
+      if (!current || !current.id || !current.running) return;
+      window.ipc.postMessage(JSON.stringify({
+        action: 'get_players',
+        server_id: current.id
+      }));
+    } catch (e) {}
+  }
+
+  function handleLiveEvent(ev) {
+    var selected = getSelectedServer();
+    var isSelected = !!(selected && selected.id && selected.id === ev.server_id);
+    if (ev.type === 'process') {
+      state.servers.forEach(function (s) {
+        if (s.id === ev.server_id) s.running = ev.running;
+      });
+      if (isSelected && !ev.running) {
+        state.playerCount = { active: '--', total: '--' };
+        state.currentPlayerList = [];
+        lastPlayerListJson = null;
+      }
+      requestRender();
+    } else if (ev.type === 'players') {
+      if (!isSelected) return;
+      state.currentPlayerList = Array.isArray(ev.players) ? ev.players : [];
+      lastPlayerListJson = null;
+      state.playerCount = { active: String(state.currentPlayerList.length), total: state.playerCount.total };
+      requestRender();
+    } else if (ev.type === 'log') {
+      var logEl = document.getElementById('log-content');
+      if (logEl && state.activeTab === 'logs' && logLineVisible(ev)) logEl.textContent += ev.line + '\n';
+    }
+  }
+
+  function connectLiveEvents() {
+    if (!session || typeof window.EventSource === 'undefined') return;
+    liveEvents = new EventSource('/api/v1/events');
+    liveEvents.onmessage = function (e) {
+      try { handleLiveEvent(JSON.parse(e.data)); } catch (err) { /* ignore */ }
+    };
+    // Missed events: fall back to asking for the current state.
+    liveEvents.addEventListener('lagged', function () {
+      requestRunningState();
+      requestPlayers();
+    });
+    liveEvents.onerror = function () {
+      // EventSource retries on its own; it only gives up when the server refuses the
+      // stream (session gone), so show the login page.
+      if (liveEvents && liveEvents.readyState === EventSource.CLOSED) window.location.reload();
+    };
+  }
+
+  function requestUsers() {
+    if (!canEditConfig || typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    try {
+      window.ipc.postMessage(JSON.stringify({ action: 'get_users', servers: [] }));
+    } catch (e) { /* ignore */ }
+  }
+
+  function sendUserEdit(action, user) {
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    try {
+      window.ipc.postMessage(JSON.stringify({ action: action, servers: [], user: user }));
+    } catch (e) { ipcLog('User postMessage error', e); }
+  }
+
+  function renderUsers() {
+    const ul = document.getElementById('users-list');
+    if (!ul) return;
+    if (selectedUserIndex >= users.length) selectedUserIndex = -1;
+    if (users.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No users.</li>';
+      return;
+    }
+    const names = ['Viewer', 'Operator', 'Admin'];
+    ul.innerHTML = users.map(function (u, i) {
+      var label = u.username + ' (' + (names[u.privilege_level] || 'Admin') + ')' + (u.has_password ? '' : ' - no password, cannot log in');
+      return '<li class="' + (i === selectedUserIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(label) + '</li>';
+    }).join('');
+  }
+
+  function restorePreviousConfig() {
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    if (!window.confirm('Replace the current configuration with the previously saved version? The current configuration is kept as a backup.')) return;
+    if (autoSaveTimeout !== null) {
+      clearTimeout(autoSaveTimeout);
+      autoSaveTimeout = null;
+    }
+    try {
+      window.ipc.postMessage(JSON.stringify({ action: 'restore_previous_config', servers: [] }));
+      showMessage('Restoring..');
+    } catch (e) { ipcLog('Restore postMessage error', e); }
+  }
+
   function openLogFile() {
     if (typeof window.ipc !== 'undefined' && window.ipc.postMessage) {
       try {
-        window.ipc.postMessage(JSON.stringify({ action: 'open_log_file', servers: state.servers }));
+        window.ipc.postMessage(JSON.stringify({ action: 'open_log_file' }));
       } catch (e) { /* ignore */ }
     }
   }
@@ -449,6 +672,26 @@
     ).join('');
   }
 
+  // Ban entries are objects; lists saved by older versions may still hold "ip:>reason" strings.
+  function banEntryIp(entry) {
+    return typeof entry === 'string' ? entry.split(':>')[0].trim() : (entry && entry.ip) || '';
+  }
+
+  function banEntryLabel(entry) {
+    if (typeof entry === 'string') return entry;
+    var label = entry.ip + (entry.reason ? ' :> ' + entry.reason : '');
+    if (entry.expires_at) label += ' (until ' + new Date(entry.expires_at * 1000).toLocaleString() + ')';
+    if (entry.added_by) label += ' by ' + entry.added_by;
+    return label;
+  }
+
+  function newBanEntry(ip, reason) {
+    var now = Math.floor(Date.now() / 1000);
+    var durationEl = document.getElementById('ban-list-add-duration');
+    var duration = durationEl ? parseInt(durationEl.value, 10) || 0 : 0;
+    return { ip: ip, reason: reason, added_by: '', created_at: now, expires_at: duration > 0 ? now + duration : null };
+  }
+
   function renderBanList() {
     const ul = document.getElementById('ban-list');
     if (!ul) return;
@@ -461,10 +704,64 @@
       return;
     }
     ul.innerHTML = list.map(function (entry, i) {
-      return '<li class="' + (i === selectedBanIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(entry) + '</li>';
+      return '<li class="' + (i === selectedBanIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(banEntryLabel(entry)) + '</li>';
+    }).join('');
+  }
+
+  var WEBHOOK_EVENT_COUNT = 6;
+  var selectedWebhookIndex = -1;
+
+  function renderWebhooks() {
+    const ul = document.getElementById('webhook-list');
+    if (!ul) return;
+    const list = (state.server_manager && state.server_manager.webhooks) || [];
+    if (selectedWebhookIndex >= list.length) selectedWebhookIndex = -1;
+    if (list.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No webhooks.</li>';
+      return;
+    }
+    ul.innerHTML = list.map(function (w, i) {
+      // Only the host is shown: webhook paths usually contain the secret token.
+      var host = String(w.url || '').replace(/^(https?:\/\/[^\/]+).*$/, '$1');
+      var events = !w.events || w.events.length === 0 || w.events.length >= WEBHOOK_EVENT_COUNT ? 'all events' : w.events.join(', ').replace(/_/g, ' ');
+      var label = w.name + ' (' + (w.format === 'discord' ? 'Discord' : 'JSON') + ', ' + host + ') - ' + events;
+      return '<li class="' + (i === selectedWebhookIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(label) + '</li>';
     }).join('');
   }
 
+  var selectedBanSubIndex = -1;
+  var banSubStatus = {};
+
+  function renderBanSubscriptions() {
+    const ul = document.getElementById('ban-sub-list');
+    if (!ul) return;
+    const list = (state.server_manager && state.server_manager.ban_list_subscriptions) || [];
+    if (selectedBanSubIndex >= list.length) selectedBanSubIndex = -1;
+    if (list.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No subscriptions.</li>';
+      return;
+    }
+    ul.innerHTML = list.map(function (s, i) {
+      var status = banSubStatus[s.name];
+      var detail;
+      if (!s.enabled) detail = 'disabled';
+      else if (!status) detail = 'not fetched yet';
+      else if (status.error) detail = status.count + ' entries, last refresh failed: ' + status.error;
+      else detail = status.count + ' entries, updated ' + new Date(status.updated_at * 1000).toLocaleString();
+      // URL sources are shown by host only, like webhooks: the path often holds a token.
+      var source = String(s.source || '').replace(/^(https?:\/\/[^\/]+)\/.+$/i, '$1/…');
+      var label = s.name + ' (' + source + ', every ' + (s.refresh_minutes || 60) + ' min) - ' + detail;
+      return '<li class="' + (i === selectedBanSubIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(label) + '</li>';
+    }).join('');
+  }
+
+  function requestBanSubscriptionStatus() {
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    try {
+      window.ipc.postMessage(JSON.stringify({ action: 'get_ban_subscriptions' }));
+    } catch (e) { /* ignore */ }
+  }
+
   function renderWhitelist() {
     const ul = document.getElementById('whitelist-list');
     if (!ul) return;
@@ -481,6 +778,57 @@
     }).join('');
   }
 
+  const NAME_MATCH_LABELS = { exact: 'exact', ignore_case: 'any case', regex: 'regex' };
+
+  function renderNameBans() {
+    const ul = document.getElementById('name-ban-list');
+    if (!ul) return;
+    const c = getSelectedConfig();
+    const list = c ? (c.name_ban_list || []) : [];
+    if (selectedNameBanIndex >= list.length) selectedNameBanIndex = -1;
+    if (list.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No name bans.</li>';
+      selectedNameBanIndex = -1;
+      return;
+    }
+    ul.innerHTML = list.map(function (rule, i) {
+      var label = rule.pattern + ' (' + (NAME_MATCH_LABELS[rule.mode] || rule.mode) + ')' + (rule.reason ? ' :> ' + rule.reason : '');
+      return '<li class="' + (i === selectedNameBanIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(label) + '</li>';
+    }).join('');
+  }
+
+  function renderClanRoster() {
+    const ul = document.getElementById('clan-roster-list');
+    if (!ul) return;
+    const c = getSelectedConfig();
+    const list = c ? (c.clan_roster || []) : [];
+    if (selectedRosterIndex >= list.length) selectedRosterIndex = -1;
+    if (list.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No clan members.</li>';
+      selectedRosterIndex = -1;
+      return;
+    }
+    ul.innerHTML = list.map(function (name, i) {
+      return '<li class="' + (i === selectedRosterIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(name) + '</li>';
+    }).join('');
+  }
+
+  function renderMessages() {
+    const ul = document.getElementById('messages-list');
+    if (!ul) return;
+    const c = getSelectedConfig();
+    const list = c ? (c.messages || []) : [];
+    if (selectedMessageIndex >= list.length) selectedMessageIndex = -1;
+    if (list.length === 0) {
+      ul.innerHTML = '<li class="empty-hint">No announcements.</li>';
+      selectedMessageIndex = -1;
+      return;
+    }
+    ul.innerHTML = list.map(function (text, i) {
+      return '<li class="' + (i === selectedMessageIndex ? 'selected' : '') + '" data-index="' + i + '">' + escapeHtml(text) + '</li>';
+    }).join('');
+  }
+
   function renderCurrentPlayersTable() {
     const tbody = document.getElementById('current-players-tbody');
     if (!tbody) return;
@@ -503,6 +851,36 @@
     }).join('');
   }
 
+  function formatDuration(seconds) {
+    if (seconds == null) return '';
+    var h = Math.floor(seconds / 3600);
+    var m = Math.floor((seconds % 3600) / 60);
+    return h > 0 ? h + 'h ' + m + 'm' : m > 0 ? m + 'm' : seconds + 's';
+  }
+
+  function renderPlayerHistory(result) {
+    var tbody = document.getElementById('history-tbody');
+    var aliasesEl = document.getElementById('history-aliases');
+    if (!tbody || !aliasesEl) return;
+    var sessions = (result && result.sessions) || [];
+    if (sessions.length === 0) {
+      tbody.innerHTML = '<tr><td colspan="6" class="empty-hint">No sessions found.</td></tr>';
+    } else {
+      tbody.innerHTML = sessions.slice().reverse().map(function (s) {
+        var joined = new Date(s.joined_at * 1000).toLocaleString();
+        var duration = s.left_at == null ? 'unknown' : formatDuration(s.duration_seconds);
+        return '<tr><td>' + escapeHtml(joined) + '</td><td>' + escapeHtml(s.server_name || '') + '</td><td>' +
+          escapeHtml(s.name) + '</td><td>' + escapeHtml(s.ip) + '</td><td>' + escapeHtml(duration) + '</td><td>' +
+          escapeHtml(s.kick_reason || '') + '</td></tr>';
+      }).join('');
+    }
+    aliasesEl.innerHTML = ((result && result.aliases) || []).map(function (a) {
+      var label = a.ip + ': ' + a.name + ' (' + a.sessions + ' sessions, last seen ' +
+        new Date(a.last_seen * 1000).toLocaleString() + ')';
+      return '<li>' + escapeHtml(label) + '</li>';
+    }).join('');
+  }
+
   function countRunning() {
     return state.servers.filter(function (s) { return s.running; }).length;
   }
@@ -535,7 +913,12 @@
     renderAvailableMapList();
     renderMapList();
     renderBanList();
+    renderNameBans();
     renderWhitelist();
+    renderClanRoster();
+    renderMessages();
+    renderWebhooks();
+    renderBanSubscriptions();
     renderCurrentPlayersTable();
     const gameSelect = document.getElementById('game-select');
     if (gameSelect && s) gameSelect.value = s.use_sabre_squadron ? 'sabre' : 'hd2';
@@ -590,17 +973,8 @@
     var isRunningFlag = !!(s && s.running);
     if (typeof window.ipc !== 'undefined' && window.ipc.postMessage) {
       if (isRunningFlag && !lastPlayerPollRunning) {
-        function requestPlayers() {
-          try {
-            window.ipc.postMessage(JSON.stringify({
-              action: 'get_players',
-              server_index: state.selectedServerIndex,
-              servers: state.servers
-            }));
-          } catch (e) {}
-        }
         requestPlayers();
-        window._playerPollTimer = setInterval(requestPlayers, 2000);
+        window._playerPollTimer = liveEvents ? null : setInterval(requestPlayers, 2000);
         lastPlayerPollRunning = true;
       } else if (!isRunningFlag && lastPlayerPollRunning) {
         if (typeof window._playerPollTimer !== 'undefined' && window._playerPollTimer !== null) {
@@ -826,6 +1200,8 @@
       portEl.value = s.port || 22000;
       if (hd2dsEl) hd2dsEl.value = s.hd2ds_path != null ? trimPathQuotes(s.hd2ds_path) : '';
       if (sabreEl) sabreEl.value = s.hd2ds_sabresquadron_path != null ? trimPathQuotes(s.hd2ds_sabresquadron_path) : '';
+      var usersEl = document.getElementById('edit-server-users');
+      if (usersEl) usersEl.value = (s.users || []).join(', ');
       var pairs = getDuplicateHd2Pairs();
       var msg = '';
       var myDir = getEffectiveHd2Dir(s);
@@ -900,6 +1276,10 @@
     s.port = port;
     s.hd2ds_path = hd2dsEl ? trimPathQuotes(hd2dsEl.value) : (s.hd2ds_path || '');
     s.hd2ds_sabresquadron_path = sabreEl ? trimPathQuotes(sabreEl.value) : (s.hd2ds_sabresquadron_path || '');
+    var usersInput = document.getElementById('edit-server-users');
+    if (usersInput) {
+      s.users = usersInput.value.split(',').map(function (u) { return u.trim(); }).filter(function (u) { return u; });
+    }
     setUnsaved(true);
     dialog.close();
     render();
@@ -955,10 +1335,9 @@
       if (s.running) {
         window.ipc.postMessage(JSON.stringify({
           action: 'stop',
-          server_index: state.selectedServerIndex,
-          servers: state.servers
+          server_id: s.id
         }));
-        ipcLog('Stop Server', state.selectedServerIndex);
+        ipcLog('Stop Server', s.id);
       } else {
         var path = trimPathQuotes(s.use_sabre_squadron ? (s.hd2ds_sabresquadron_path || '') : (s.hd2ds_path || ''));
         if (!path) {
@@ -983,12 +1362,15 @@
         state.serverError = false;
         state.serverStarting = true;
         render();
-        window.ipc.postMessage(JSON.stringify({
-          action: 'start',
-          server_index: state.selectedServerIndex,
-          servers: state.servers
-        }));
-        ipcLog('Start Server', state.selectedServerIndex);
+        var selectedIndex = state.selectedServerIndex;
+        runAfterSave(function () {
+          var target = state.servers[selectedIndex];
+          window.ipc.postMessage(JSON.stringify({
+            action: 'start',
+            server_id: target ? target.id : null
+          }));
+          ipcLog('Start Server', target ? target.id : '(none)');
+        });
       }
     } catch (err) { ipcLog('Start/Stop Server postMessage error', err); }
   });
@@ -1002,10 +1384,12 @@
     }
     try {
       if (countRunning() > 0) {
-        window.ipc.postMessage(JSON.stringify({ action: 'stop_all', servers: state.servers }));
+        window.ipc.postMessage(JSON.stringify({ action: 'stop_all' }));
         ipcLog('Stop All Servers');
       } else {
-        window.ipc.postMessage(JSON.stringify({ action: 'start_all', servers: state.servers }));
+        runAfterSave(function () {
+          window.ipc.postMessage(JSON.stringify({ action: 'start_all' }));
+        });
         ipcLog('Start All Servers', state.servers.length);
       }
     } catch (err) { ipcLog('Start/Stop All postMessage error', err); }
@@ -1138,6 +1522,49 @@
       renderBanList();
       return;
     }
+    const userLi = e.target.closest('#users-list li[data-index]');
+    if (userLi) {
+      selectedUserIndex = parseInt(userLi.dataset.index, 10);
+      const u = users[selectedUserIndex];
+      if (u) {
+        const nameEl = document.getElementById('user-edit-name');
+        const privEl = document.getElementById('user-edit-privilege');
+        if (nameEl) nameEl.value = u.username;
+        if (privEl) privEl.value = String(Math.min(u.privilege_level, 2));
+      }
+      renderUsers();
+      return;
+    }
+    const webhookLi = e.target.closest('#webhook-list li[data-index]');
+    if (webhookLi) {
+      selectedWebhookIndex = parseInt(webhookLi.dataset.index, 10);
+      renderWebhooks();
+      return;
+    }
+    const banSubLi = e.target.closest('#ban-sub-list li[data-index]');
+    if (banSubLi) {
+      selectedBanSubIndex = parseInt(banSubLi.dataset.index, 10);
+      renderBanSubscriptions();
+      return;
+    }
+    const nameBanLi = e.target.closest('#name-ban-list li[data-index]');
+    if (nameBanLi) {
+      selectedNameBanIndex = parseInt(nameBanLi.dataset.index, 10);
+      renderNameBans();
+      return;
+    }
+    const messageLi = e.target.closest('#messages-list li[data-index]');
+    if (messageLi) {
+      selectedMessageIndex = parseInt(messageLi.dataset.index, 10);
+      renderMessages();
+      return;
+    }
+    const rosterLi = e.target.closest('#clan-roster-list li[data-index]');
+    if (rosterLi) {
+      selectedRosterIndex = parseInt(rosterLi.dataset.index, 10);
+      renderClanRoster();
+      return;
+    }
     const wlLi = e.target.closest('#whitelist-list li[data-index]');
     if (wlLi) {
       const idx = parseInt(wlLi.dataset.index, 10);
@@ -1154,8 +1581,8 @@
         const c = getSelectedConfig();
         if (c) {
           c.ban_list = c.ban_list || [];
-          if (c.ban_list.indexOf(ip) === -1) {
-            c.ban_list.push(ip + ':>' + 'Unspecified ban'.slice(0, BAN_REASON_MAX));
+          if (!c.ban_list.some(function (entry) { return banEntryIp(entry) === ip; })) {
+            c.ban_list.push(newBanEntry(ip, 'Unspecified ban'.slice(0, BAN_REASON_MAX)));
             selectedBanIndex = c.ban_list.length - 1;
             setUnsaved(true);
             renderBanList();
@@ -1197,9 +1624,8 @@
     const ip = (ipEl.value || '').trim();
     if (!ip) return;
     const comment = commentEl ? (commentEl.value || '').trim().slice(0, BAN_REASON_MAX) : '';
-    const entry = comment ? ip + ':>' + comment : ip;
     c.ban_list = c.ban_list || [];
-    c.ban_list.push(entry);
+    c.ban_list.push(newBanEntry(ip, comment));
     ipEl.value = '';
     if (commentEl) { commentEl.value = ''; const counterEl = document.getElementById('ban-list-add-comment-counter'); if (counterEl) counterEl.textContent = '0/' + BAN_REASON_MAX; }
     selectedBanIndex = c.ban_list.length - 1;
@@ -1216,6 +1642,176 @@
     renderBanList();
   });
 
+  document.getElementById('history-search')?.addEventListener('click', function () {
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    var name = (document.getElementById('history-name')?.value || '').trim();
+    var ip = (document.getElementById('history-ip')?.value || '').trim();
+    var history = {};
+    if (name) history.name = name;
+    if (ip) history.ip = ip;
+    try {
+      window.ipc.postMessage(JSON.stringify({ action: 'search_player_history', history: history }));
+    } catch (e) { /* ignore */ }
+  });
+
+  // List import/export: the host parses and writes the files and merges imports into the list
+  // sent along; the result is saved like any other edit.
+  var pendingListImport = null;
+  function listEntries(name) {
+    if (name === 'forced_ban_list') return (state.server_manager && state.server_manager.forced_ban_list) || [];
+    const c = getSelectedConfig();
+    return c ? (c[name] || []) : null;
+  }
+  function listFormat(name, fileName) {
+    var ext = (fileName || '').split('.').pop().toLowerCase();
+    if (ext === 'csv' || ext === 'json') return ext;
+    if (ext === 'txt') return 'text';
+    var el = document.querySelector('[data-list-format="' + name + '"]');
+    return el ? el.value : 'csv';
+  }
+  document.addEventListener('click', function (e) {
+    var exportBtn = e.target.closest('[data-list-export]');
+    var importBtn = e.target.closest('[data-list-import]');
+    if (!exportBtn && !importBtn) return;
+    if (typeof window.ipc === 'undefined' || !window.ipc.postMessage) return;
+    if (exportBtn) {
+      var name = exportBtn.getAttribute('data-list-export');
+      var entries = listEntries(name);
+      if (!entries) return;
+      try {
+        window.ipc.postMessage(JSON.stringify({ action: 'export_list', list: { name: name, format: listFormat(name), entries: entries } }));
+      } catch (err) { /* ignore */ }
+      return;
+    }
+    pendingListImport = importBtn.getAttribute('data-list-import');
+    var fileEl = document.getElementById('list-import-file');
+    if (fileEl) { fileEl.value = ''; fileEl.click(); }
+  });
+  document.getElementById('list-import-file')?.addEventListener('change', function () {
+    var file = this.files && this.files[0];
+    var name = pendingListImport;
+    if (!file || !name) return;
+    var entries = listEntries(name);
+    if (!entries) return;
+    var reader = new FileReader();
+    reader.onload = function () {
+      try {
+        window.ipc.postMessage(JSON.stringify({
+          action: 'import_list',
+          list: { name: name, format: listFormat(name, file.name), content: String(reader.result || ''), entries: entries }
+        }));
+      } catch (err) { showMessage('Import failed', true); }
+    };
+    reader.readAsText(file);
+  });
+  function applyImportedList(result) {
+    if (result.name === 'forced_ban_list') {
+      if (!state.server_manager) return;
+      state.server_manager.forced_ban_list = result.entries;
+    } else {
+      const c = getSelectedConfig();
+      if (!c) return;
+      c[result.name] = result.entries;
+    }
+    if (result.added > 0) setUnsaved(true);
+    showMessage('Imported ' + result.added + ' new ' + (result.added === 1 ? 'entry' : 'entries'));
+    requestRender();
+  }
+  function downloadList(result) {
+    var blob = new Blob([result.content], { type: 'application/octet-stream' });
+    var a = document.createElement('a');
+    a.href = URL.createObjectURL(blob);
+    a.download = result.filename;
+    document.body.appendChild(a);
+    a.click();
+    a.remove();
+    setTimeout(function () { URL.revokeObjectURL(a.href); }, 1000);
+  }
+
+  document.getElementById('webhook-add')?.addEventListener('click', function () {
+    const sm = state.server_manager;
+    const nameEl = document.getElementById('webhook-add-name');
+    const urlEl = document.getElementById('webhook-add-url');
+    const formatEl = document.getElementById('webhook-add-format');
+    if (!sm || !urlEl) return;
+    const url = (urlEl.value || '').trim();
+    if (!/^https?:\/\/[^\/\s]+/.test(url)) {
+      showMessage('Webhook URL must start with http:// or https://', true);
+      return;
+    }
+    const events = Array.prototype.slice.call(document.querySelectorAll('#webhook-add-events input:checked'))
+      .map(function (el) { return el.value; });
+    if (events.length === 0) {
+      showMessage('Pick at least one event for the webhook.', true);
+      return;
+    }
+    sm.webhooks = sm.webhooks || [];
+    sm.webhooks.push({
+      name: (nameEl && nameEl.value.trim()) || 'Webhook ' + (sm.webhooks.length + 1),
+      url: url,
+      format: formatEl ? formatEl.value : 'discord',
+      events: events.length >= WEBHOOK_EVENT_COUNT ? [] : events,
+      enabled: true
+    });
+    if (nameEl) nameEl.value = '';
+    urlEl.value = '';
+    selectedWebhookIndex = sm.webhooks.length - 1;
+    setUnsaved(true);
+    renderWebhooks();
+  });
+  document.getElementById('webhook-remove')?.addEventListener('click', function () {
+    const sm = state.server_manager;
+    if (!sm || !sm.webhooks || selectedWebhookIndex < 0 || selectedWebhookIndex >= sm.webhooks.length) return;
+    sm.webhooks.splice(selectedWebhookIndex, 1);
+    selectedWebhookIndex = Math.min(selectedWebhookIndex, sm.webhooks.length - 1);
+    setUnsaved(true);
+    renderWebhooks();
+  });
+
+  document.getElementById('ban-sub-add')?.addEventListener('click', function () {
+    const sm = state.server_manager;
+    const nameEl = document.getElementById('ban-sub-add-name');
+    const sourceEl = document.getElementById('ban-sub-add-source');
+    const formatEl = document.getElementById('ban-sub-add-format');
+    const refreshEl = document.getElementById('ban-sub-add-refresh');
+    if (!sm || !sourceEl) return;
+    const source = (sourceEl.value || '').trim();
+    if (!source) return;
+    sm.ban_list_subscriptions = sm.ban_list_subscriptions || [];
+    if (sm.ban_list_subscriptions.some(function (s) { return String(s.source).trim() === source; })) {
+      showMessage('Already subscribed to that list.', true);
+      return;
+    }
+    sm.ban_list_subscriptions.push({
+      name: (nameEl && nameEl.value.trim()) || 'Ban list ' + (sm.ban_list_subscriptions.length + 1),
+      source: source,
+      enabled: true,
+      format: formatEl && formatEl.value ? formatEl.value : null,
+      refresh_minutes: Math.max(1, parseInt(refreshEl && refreshEl.value, 10) || 60)
+    });
+    if (nameEl) nameEl.value = '';
+    sourceEl.value = '';
+    selectedBanSubIndex = sm.ban_list_subscriptions.length - 1;
+    setUnsaved(true);
+    renderBanSubscriptions();
+  });
+  document.getElementById('ban-sub-toggle')?.addEventListener('click', function () {
+    const list = state.server_manager && state.server_manager.ban_list_subscriptions;
+    if (!list || selectedBanSubIndex < 0 || selectedBanSubIndex >= list.length) return;
+    list[selectedBanSubIndex].enabled = !list[selectedBanSubIndex].enabled;
+    setUnsaved(true);
+    renderBanSubscriptions();
+  });
+  document.getElementById('ban-sub-remove')?.addEventListener('click', function () {
+    const list = state.server_manager && state.server_manager.ban_list_subscriptions;
+    if (!list || selectedBanSubIndex < 0 || selectedBanSubIndex >= list.length) return;
+    list.splice(selectedBanSubIndex, 1);
+    selectedBanSubIndex = Math.min(selectedBanSubIndex, list.length - 1);
+    setUnsaved(true);
+    renderBanSubscriptions();
+  });
+  document.getElementById('ban-sub-status')?.addEventListener('click', requestBanSubscriptionStatus);
+
   // whitelist events keep their own click handlers for add/remove;
   // row selection is handled by the global click delegate above.
   document.getElementById('whitelist-add')?.addEventListener('click', function () {
@@ -1245,6 +1841,78 @@
     renderWhitelist();
   });
 
+  document.getElementById('name-ban-add')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    const patternEl = document.getElementById('name-ban-add-pattern');
+    const modeEl = document.getElementById('name-ban-add-mode');
+    const reasonEl = document.getElementById('name-ban-add-reason');
+    if (!c || !patternEl) return;
+    const pattern = (patternEl.value || '').trim();
+    if (!pattern) return;
+    c.name_ban_list = c.name_ban_list || [];
+    c.name_ban_list.push({
+      pattern: pattern,
+      mode: modeEl ? modeEl.value : 'ignore_case',
+      reason: reasonEl ? (reasonEl.value || '').trim().slice(0, BAN_REASON_MAX) : ''
+    });
+    patternEl.value = '';
+    if (reasonEl) reasonEl.value = '';
+    selectedNameBanIndex = c.name_ban_list.length - 1;
+    setUnsaved(true);
+    renderNameBans();
+  });
+  document.getElementById('name-ban-remove')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    if (!c || !c.name_ban_list || selectedNameBanIndex < 0 || selectedNameBanIndex >= c.name_ban_list.length) return;
+    c.name_ban_list.splice(selectedNameBanIndex, 1);
+    selectedNameBanIndex = Math.min(selectedNameBanIndex, c.name_ban_list.length - 1);
+    setUnsaved(true);
+    renderNameBans();
+  });
+
+  document.getElementById('clan-roster-add')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    const nameEl = document.getElementById('clan-roster-add-name');
+    if (!c || !nameEl) return;
+    const name = (nameEl.value || '').trim();
+    if (!name) return;
+    c.clan_roster = c.clan_roster || [];
+    c.clan_roster.push(name);
+    nameEl.value = '';
+    selectedRosterIndex = c.clan_roster.length - 1;
+    setUnsaved(true);
+    renderClanRoster();
+  });
+  document.getElementById('message-add')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    const textEl = document.getElementById('message-add-text');
+    if (!c || !textEl) return;
+    const text = (textEl.value || '').trim();
+    if (!text) return;
+    c.messages = c.messages || [];
+    c.messages.push(text);
+    textEl.value = '';
+    selectedMessageIndex = c.messages.length - 1;
+    setUnsaved(true);
+    renderMessages();
+  });
+  document.getElementById('message-remove')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    if (!c || !c.messages || selectedMessageIndex < 0 || selectedMessageIndex >= c.messages.length) return;
+    c.messages.splice(selectedMessageIndex, 1);
+    selectedMessageIndex = Math.min(selectedMessageIndex, c.messages.length - 1);
+    setUnsaved(true);
+    renderMessages();
+  });
+  document.getElementById('clan-roster-remove')?.addEventListener('click', function () {
+    const c = getSelectedConfig();
+    if (!c || !c.clan_roster || selectedRosterIndex < 0 || selectedRosterIndex >= c.clan_roster.length) return;
+    c.clan_roster.splice(selectedRosterIndex, 1);
+    selectedRosterIndex = Math.min(selectedRosterIndex, c.clan_roster.length - 1);
+    setUnsaved(true);
+    renderClanRoster();
+  });
+
   document.getElementById('style-select')?.addEventListener('change', function () {
     bindConfigToForm();
     const c = getSelectedConfig();
@@ -1269,7 +1937,7 @@
       requestAnimationFrame(function () {
         requestAnimationFrame(function () {
           try {
-            window.ipc.postMessage(JSON.stringify({ action: 'repaint', servers: state.servers }));
+            window.ipc.postMessage(JSON.stringify({ action: 'repaint' }));
           } catch (e) {}
         });
       });
@@ -1294,12 +1962,21 @@
       try {
         var json = msg.slice(6);
         var next = JSON.parse(json);
+        if (next && !Array.isArray(next) && Array.isArray(next.servers)) {
+          if (typeof next.revision === 'number') state.revision = next.revision;
+          next = next.servers;
+        }
         if (Array.isArray(next)) {
           state.servers = next;
           setUnsaved(false);
           showMessage('Saved');
           requestRender();
           requestHostRepaint();
+          if (afterSave) {
+            var pending = afterSave;
+            afterSave = null;
+            pending();
+          }
         }
       } catch (e) { showMessage('Save failed.', true); }
     } else if (msg && msg.startsWith('REFRESH:')) {
@@ -1314,7 +1991,61 @@
           requestHostRepaint();
         }
       } catch (e) { showMessage('Refresh failed.', true); }
-    } else if (msg && msg.indexOf('Save') !== -1) showMessage('Save failed', true);
+    } else if (msg && msg.indexOf('RESTORED:') === 0) {
+      try {
+        var restored = JSON.parse(msg.slice('RESTORED:'.length));
+        if (restored && Array.isArray(restored.servers)) {
+          state.servers = restored.servers;
+          if (typeof restored.revision === 'number') state.revision = restored.revision;
+          if (restored.server_manager) state.server_manager = Object.assign({}, state.server_manager, restored.server_manager);
+          state.selectedServerIndex = Math.max(0, Math.min(state.selectedServerIndex, state.servers.length - 1));
+          state.selectedConfigIndex = 0;
+          setUnsaved(false);
+          showMessage('Previous config restored');
+          requestRender();
+          requestHostRepaint();
+        }
+      } catch (e) { showMessage('Restore failed.', true); }
+    } else if (msg && msg.indexOf('CONFLICT:') === 0) {
+      afterSave = null;
+      try {
+        var current = JSON.parse(msg.slice('CONFLICT:'.length));
+        var reload = window.confirm('The config was changed elsewhere since you loaded it.\n\nOK: load the latest config (your unsaved edits are discarded).\nCancel: keep your edits (the next save overwrites the other changes).');
+        if (typeof current.revision === 'number') state.revision = current.revision;
+        if (reload && Array.isArray(current.servers)) {
+          state.servers = current.servers;
+          if (current.server_manager) state.server_manager = Object.assign({}, state.server_manager, current.server_manager);
+          state.selectedServerIndex = Math.max(0, Math.min(state.selectedServerIndex, state.servers.length - 1));
+          state.selectedConfigIndex = 0;
+          setUnsaved(false);
+          showMessage('Loaded latest config');
+          requestRender();
+          requestHostRepaint();
+        } else {
+          setUnsaved(true);
+          showMessage('Not saved: config changed elsewhere', true);
+        }
+      } catch (e) { showMessage('Save failed.', true); }
+    } else if (msg && msg.indexOf('BAN_SUBSCRIPTIONS:') === 0) {
+      try {
+        banSubStatus = JSON.parse(msg.slice('BAN_SUBSCRIPTIONS:'.length)) || {};
+        renderBanSubscriptions();
+      } catch (e) { /* ignore */ }
+    } else if (msg && msg.indexOf('LIST_IMPORTED:') === 0) {
+      try {
+        applyImportedList(JSON.parse(msg.slice('LIST_IMPORTED:'.length)));
+      } catch (e) { showMessage('Import failed', true); }
+    } else if (msg && msg.indexOf('LIST_EXPORT:') === 0) {
+      try {
+        downloadList(JSON.parse(msg.slice('LIST_EXPORT:'.length)));
+      } catch (e) { showMessage('Export failed', true); }
+    } else if (msg && (msg.indexOf('List import failed:') === 0 || msg.indexOf('List transfer failed:') === 0)) {
+      showMessage(msg, true);
+    } else if (msg && msg.indexOf('Save failed:') === 0) {
+      afterSave = null;
+      showMessage(msg, true);
+    }
+    else if (msg && msg.indexOf('Save') !== -1) showMessage('Save failed', true);
     else if (msg === 'Started OK') {
       showMessage('Started');
       state.serverStarting = false;
@@ -1348,10 +2079,10 @@
       try {
         state.serverStarting = false;
         var json = msg.slice(7);
-        var ports = JSON.parse(json);
-        if (Array.isArray(ports)) {
+        var ids = JSON.parse(json);
+        if (Array.isArray(ids)) {
           state.servers.forEach(function (s) {
-            s.running = ports.indexOf(s.port) !== -1;
+            s.running = !!s.id && ids.indexOf(s.id) !== -1;
           });
           requestRender();
         }
@@ -1376,6 +2107,19 @@
         state.currentPlayerList = [];
         requestRender();
       }
+    } else if (msg && msg.indexOf('USERS:') === 0) {
+      try {
+        users = JSON.parse(msg.slice('USERS:'.length)) || [];
+        renderUsers();
+      } catch (e) { /* ignore */ }
+    } else if (msg && msg.indexOf('User update failed:') === 0) {
+      showMessage(msg, true);
+    } else if (msg && msg.indexOf('PLAYER_HISTORY:') === 0) {
+      try {
+        renderPlayerHistory(JSON.parse(msg.slice('PLAYER_HISTORY:'.length)));
+      } catch (e) { /* ignore */ }
+    } else if (msg && msg.indexOf('Player history failed:') === 0) {
+      showMessage(msg, true);
     } else if (msg && msg.indexOf('LOG_CONTENT:') === 0) {
       var logEl = document.getElementById('log-content');
       if (logEl) logEl.textContent = msg.slice('LOG_CONTENT:'.length);
@@ -1420,6 +2164,51 @@
   document.getElementById('logs-open-folder')?.addEventListener('click', function () {
     openLogFile();
   });
+  document.getElementById('logs-level')?.addEventListener('change', requestLogContent);
+  document.getElementById('logs-this-server')?.addEventListener('change', requestLogContent);
+  document.getElementById('config-restore-previous')?.addEventListener('click', function () {
+    restorePreviousConfig();
+  });
+  document.getElementById('user-save')?.addEventListener('click', function () {
+    const nameEl = document.getElementById('user-edit-name');
+    const passEl = document.getElementById('user-edit-password');
+    const privEl = document.getElementById('user-edit-privilege');
+    const username = nameEl ? nameEl.value.trim() : '';
+    if (!username) {
+      showMessage('Enter a username', true);
+      return;
+    }
+    const password = passEl ? passEl.value : '';
+    sendUserEdit('set_user', {
+      username: username,
+      password: password ? password : null,
+      privilege_level: privEl ? parseInt(privEl.value, 10) || 0 : 0
+    });
+    if (passEl) passEl.value = '';
+    showMessage('Saving user..');
+  });
+  document.getElementById('user-remove')?.addEventListener('click', function () {
+    const u = users[selectedUserIndex];
+    if (!u) return;
+    if (!window.confirm('Remove user ' + u.username + '?')) return;
+    sendUserEdit('delete_user', { username: u.username });
+    selectedUserIndex = -1;
+  });
+  if (session) {
+    const info = document.getElementById('session-info');
+    const userEl = document.getElementById('session-user');
+    if (userEl) userEl.textContent = session.username + ' (' + (session.privilege_name || '') + ')';
+    if (info) info.classList.remove('hidden');
+    document.getElementById('session-logout')?.addEventListener('click', function () {
+      fetch('/api/logout', { method: 'POST' }).finally(function () { window.location.reload(); });
+    });
+  }
+  if (!canEditConfig) {
+    ['users-card', 'save-config', 'config-restore-previous'].forEach(function (id) {
+      var el = document.getElementById(id);
+      if (el) el.style.display = 'none';
+    });
+  }
 
   document.getElementById('mpmaplist-clear')?.addEventListener('click', function () {
     bindConfigToForm();
@@ -1430,7 +2219,9 @@
     setUnsaved(true);
     requestRender();
   });
+  connectLiveEvents();
   render();
   if (state.activeTab === 'logs') requestLogContent();
+  if (state.activeTab === 'general') { requestUsers(); requestBanSubscriptionStatus(); }
   ipcLog('Ready');
 })();
@@ -26,6 +26,7 @@ pub fn embedded_card_html(
     Err("spectre-web is only supported on Windows (WebView2).".to_string())
 }
 #[cfg(not(windows))]
+#[derive(Default)]
 pub struct AppState;
 #[cfg(not(windows))]
 impl AppState {